@@ -0,0 +1,35 @@
+//! The batch conversions against a loop of single calls over a million
+//! points. Run once plain and once with `--features parallel` to see how the
+//! batches scale across rayon's pool.
+
+mod common;
+
+use a5_rust::{a5_cell_to_lon_lat, a5_cell_to_lon_lat_batch, a5_free_cell_array_ptr, a5_free_lonlatdegrees_array_ptr, a5_lon_lat_to_cell, a5_lon_lat_to_cell_batch, LonLatDegrees};
+
+const RESOLUTION: i32 = 12;
+
+fn main() {
+    let coords: Vec<LonLatDegrees> = common::points(1_000_000, 7).into_iter().map(|(lon, lat)| LonLatDegrees { lon, lat }).collect();
+    let mode = if cfg!(feature = "parallel") { format!("parallel on {} threads", std::thread::available_parallelism().map_or(1, |n| n.get())) } else { "serial".to_string() };
+    println!("{} points at resolution {}, batches {}", coords.len(), RESOLUTION, mode);
+
+    let (single, cells) = common::fastest(1, || coords.iter().map(|c| a5_lon_lat_to_cell(c.lon, c.lat, RESOLUTION).value).collect::<Vec<u64>>());
+    let (batch, batched) = common::fastest(1, || {
+        let mut arr = a5_lon_lat_to_cell_batch(coords.as_ptr(), coords.len(), RESOLUTION, std::ptr::null(), std::ptr::null_mut());
+        let cells = unsafe { std::slice::from_raw_parts(arr.data, arr.len) }.to_vec();
+        a5_free_cell_array_ptr(&mut arr);
+        cells
+    });
+    assert_eq!(cells, batched);
+    println!("lon_lat_to_cell: single calls {:?}, batch {:?}, {:.1}x", single, batch, single.as_secs_f64() / batch.as_secs_f64());
+
+    let (single, centers) = common::fastest(1, || cells.iter().map(|&cell| a5_cell_to_lon_lat(cell)).map(|c| (c.longitude.to_bits(), c.latitude.to_bits())).collect::<Vec<_>>());
+    let (batch, batched) = common::fastest(1, || {
+        let mut arr = a5_cell_to_lon_lat_batch(cells.as_ptr(), cells.len(), std::ptr::null(), std::ptr::null_mut());
+        let centers: Vec<(u64, u64)> = unsafe { std::slice::from_raw_parts(arr.data, arr.len) }.iter().map(|p| (p.lon.to_bits(), p.lat.to_bits())).collect();
+        a5_free_lonlatdegrees_array_ptr(&mut arr);
+        centers
+    });
+    assert_eq!(centers, batched);
+    println!("cell_to_lon_lat: single calls {:?}, batch {:?}, {:.1}x", single, batch, single.as_secs_f64() / batch.as_secs_f64());
+}
@@ -0,0 +1,39 @@
+//! Boundaries of a small working set of cells looked up over and over, with
+//! the boundary cache off and then sized to hold the whole set. Needs
+//! `--features boundary-cache`.
+
+mod common;
+
+use a5_rust::{a5_cell_to_boundary, a5_free_lonlatdegrees_array_ptr, a5_lon_lat_to_cell, a5_set_boundary_cache_size, CellBoundaryOptions};
+
+const RESOLUTION: i32 = 12;
+const CELLS: usize = 1000;
+const ROUNDS: usize = 50;
+
+/// Fetches the open boundary of every cell `ROUNDS` times, returning the total
+/// vertex count so the lookups cannot be optimized away.
+fn lookups(cells: &[u64]) -> usize {
+    let mut vertices = 0;
+    for _ in 0..ROUNDS {
+        for &cell in cells {
+            let mut arr = a5_cell_to_boundary(cell, CellBoundaryOptions::new(false, None));
+            assert!(arr.error.is_null());
+            vertices += arr.len;
+            a5_free_lonlatdegrees_array_ptr(&mut arr);
+        }
+    }
+    vertices
+}
+
+fn main() {
+    let cells: Vec<u64> = common::points(CELLS, 11).into_iter().map(|(lon, lat)| a5_lon_lat_to_cell(lon, lat, RESOLUTION).value).collect();
+    println!("{} boundaries of {} cells at resolution {}", CELLS * ROUNDS, CELLS, RESOLUTION);
+
+    assert!(a5_set_boundary_cache_size(0));
+    let (uncached, expected) = common::fastest(3, || lookups(&cells));
+    assert!(a5_set_boundary_cache_size(CELLS));
+    let (cached, vertices) = common::fastest(3, || lookups(&cells));
+    assert!(a5_set_boundary_cache_size(0));
+    assert_eq!(vertices, expected);
+    println!("uncached {:?}, cached {:?}, {:.1}x", uncached, cached, uncached.as_secs_f64() / cached.as_secs_f64());
+}
@@ -0,0 +1,29 @@
+//! Timing shared by the benchmarks. They run with `cargo bench` on the
+//! standard harness-free setup, so each one is a plain `main` that prints its
+//! measurements.
+
+use std::time::{Duration, Instant};
+
+/// Runs `f` `runs` times after one warm-up call and returns the fastest run,
+/// which is the least disturbed by the rest of the machine.
+pub fn fastest<T>(runs: usize, mut f: impl FnMut() -> T) -> (Duration, T) {
+    let mut result = f();
+    let mut best = Duration::MAX;
+    for _ in 0..runs {
+        let start = Instant::now();
+        result = f();
+        best = best.min(start.elapsed());
+    }
+    (best, result)
+}
+
+/// Deterministic pseudo-random points, so every run indexes the same input.
+#[allow(dead_code)]
+pub fn points(n: usize, seed: u64) -> Vec<(f64, f64)> {
+    let mut state = seed;
+    let mut next = move || {
+        state = state.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+        (state >> 11) as f64 / (1u64 << 53) as f64
+    };
+    (0..n).map(|_| (next() * 360.0 - 180.0, (next() * 2.0 - 1.0).asin().to_degrees())).collect()
+}
@@ -0,0 +1,42 @@
+//! Sizes and speeds of the packed cell set encodings, `a5_compress_cells` and
+//! `a5_cells_to_msgpack`, on polyfills of a city and a region against the
+//! 8 bytes per cell of a raw array.
+
+mod common;
+
+use a5_rust::{a5_cells_to_msgpack, a5_compress_cells, a5_decompress_cells, a5_free_bytes, a5_free_cell_array_ptr, a5_msgpack_to_cells, a5_polygon_to_cells, FillMode, LonLatDegrees};
+
+fn square(min_lon: f64, min_lat: f64, max_lon: f64, max_lat: f64) -> Vec<LonLatDegrees> {
+    [(min_lon, min_lat), (max_lon, min_lat), (max_lon, max_lat), (min_lon, max_lat)].iter().map(|&(lon, lat)| LonLatDegrees { lon, lat }).collect()
+}
+
+fn take(mut arr: a5_rust::CellArray) -> Vec<u64> {
+    assert!(arr.error.is_null());
+    let cells = unsafe { std::slice::from_raw_parts(arr.data, arr.len) }.to_vec();
+    a5_free_cell_array_ptr(&mut arr);
+    cells
+}
+
+fn bytes(encode: extern "C" fn(*const u64, usize, *mut usize) -> *mut u8, cells: &[u64]) -> Vec<u8> {
+    let mut len = 0;
+    let ptr = encode(cells.as_ptr(), cells.len(), &mut len);
+    let bytes = unsafe { std::slice::from_raw_parts(ptr, len) }.to_vec();
+    a5_free_bytes(ptr, len);
+    bytes
+}
+
+fn main() {
+    for (name, ring, resolution) in [("New York", square(-74.3, 40.5, -73.7, 40.9), 14), ("Western Europe", square(0.0, 40.0, 10.0, 50.0), 9)] {
+        let cells = take(a5_polygon_to_cells(ring.as_ptr(), ring.len(), resolution, FillMode::Intersects));
+        println!("{}: {} cells at resolution {}, {} raw bytes", name, cells.len(), resolution, cells.len() * 8);
+        for (encoding, encode, decode) in [
+            ("compress", a5_compress_cells as extern "C" fn(_, _, _) -> _, a5_decompress_cells as extern "C" fn(_, _) -> _),
+            ("msgpack", a5_cells_to_msgpack, a5_msgpack_to_cells),
+        ] {
+            let (encoded, blob) = common::fastest(10, || bytes(encode, &cells));
+            let (decoded, back) = common::fastest(10, || take(decode(blob.as_ptr(), blob.len())));
+            assert_eq!(back, cells);
+            println!("  {}: {} bytes, ratio {:.2}, encode {:?}, decode {:?}", encoding, blob.len(), (cells.len() * 8) as f64 / blob.len() as f64, encoded, decoded);
+        }
+    }
+}
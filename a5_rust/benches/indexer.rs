@@ -0,0 +1,32 @@
+//! An `A5Indexer` against the stateless conversion over a million points, to
+//! show whether holding the resolution in a handle saves anything per point.
+
+mod common;
+
+use a5_rust::{a5_free_cell_array_ptr, a5_indexer_free, a5_indexer_index, a5_indexer_index_batch, a5_indexer_new, a5_lon_lat_to_cell, a5_lon_lat_to_cell_batch, LonLatDegrees};
+
+const RESOLUTION: i32 = 12;
+
+fn main() {
+    let coords: Vec<LonLatDegrees> = common::points(1_000_000, 3).into_iter().map(|(lon, lat)| LonLatDegrees { lon, lat }).collect();
+    println!("{} points at resolution {}", coords.len(), RESOLUTION);
+    let indexer = a5_indexer_new(RESOLUTION);
+
+    let (stateless, expected) = common::fastest(1, || coords.iter().map(|c| a5_lon_lat_to_cell(c.lon, c.lat, RESOLUTION).value).collect::<Vec<u64>>());
+    let (indexed, cells) = common::fastest(1, || coords.iter().map(|c| a5_indexer_index(indexer, c.lon, c.lat).value).collect::<Vec<u64>>());
+    assert_eq!(cells, expected);
+    println!("single: a5_lon_lat_to_cell {:?}, a5_indexer_index {:?}", stateless, indexed);
+
+    let batch = |f: &dyn Fn() -> a5_rust::CellArray| {
+        let mut arr = f();
+        let cells = unsafe { std::slice::from_raw_parts(arr.data, arr.len) }.to_vec();
+        a5_free_cell_array_ptr(&mut arr);
+        cells
+    };
+    let (stateless, batched) = common::fastest(1, || batch(&|| a5_lon_lat_to_cell_batch(coords.as_ptr(), coords.len(), RESOLUTION, std::ptr::null(), std::ptr::null_mut())));
+    let (indexed, cells) = common::fastest(1, || batch(&|| a5_indexer_index_batch(indexer, coords.as_ptr(), coords.len())));
+    assert_eq!((&batched, &cells), (&expected, &expected));
+    println!("batch: a5_lon_lat_to_cell_batch {:?}, a5_indexer_index_batch {:?}", stateless, indexed);
+
+    a5_indexer_free(indexer);
+}
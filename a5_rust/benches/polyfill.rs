@@ -0,0 +1,72 @@
+//! Polygon fill of a country-sized outline: the pruned walk of
+//! `a5_polygon_to_cells` against testing the center of every cell on the
+//! sphere.
+
+mod common;
+
+use a5_rust::{a5_free_cell_array_ptr, a5_polygon_to_cells, FillMode, LonLatDegrees};
+
+/// A rough outline of mainland France, about 550,000 km².
+const FRANCE: [(f64, f64); 12] = [
+    (-1.8, 43.4),
+    (3.2, 42.4),
+    (7.5, 43.8),
+    (6.6, 45.1),
+    (7.6, 47.6),
+    (8.2, 49.0),
+    (4.8, 50.0),
+    (2.5, 51.1),
+    (-1.3, 49.7),
+    (-4.8, 48.5),
+    (-1.1, 46.3),
+    (-1.8, 43.4),
+];
+
+fn ring_contains(ring: &[(f64, f64)], lon: f64, lat: f64) -> bool {
+    let mut inside = false;
+    for i in 0..ring.len() {
+        let ((x1, y1), (x2, y2)) = (ring[i], ring[(i + 1) % ring.len()]);
+        if (y1 > lat) != (y2 > lat) && lon < x1 + (lat - y1) / (y2 - y1) * (x2 - x1) {
+            inside = !inside;
+        }
+    }
+    inside
+}
+
+fn pruned(ring: &[LonLatDegrees], resolution: i32) -> Vec<u64> {
+    let mut arr = a5_polygon_to_cells(ring.as_ptr(), ring.len(), resolution, FillMode::Center);
+    assert!(arr.error.is_null());
+    let mut cells = unsafe { std::slice::from_raw_parts(arr.data, arr.len) }.to_vec();
+    a5_free_cell_array_ptr(&mut arr);
+    cells.sort_unstable();
+    cells
+}
+
+fn brute_force(resolution: i32) -> Vec<u64> {
+    let all = a5::uncompact(&a5::get_res0_cells().unwrap(), resolution).unwrap();
+    let mut cells: Vec<u64> = all
+        .into_iter()
+        .filter(|&cell| {
+            let c = a5::cell_to_lonlat(cell).unwrap();
+            ring_contains(&FRANCE, c.longitude(), c.latitude())
+        })
+        .collect();
+    cells.sort_unstable();
+    cells
+}
+
+fn main() {
+    let ring: Vec<LonLatDegrees> = FRANCE.iter().map(|&(lon, lat)| LonLatDegrees { lon, lat }).collect();
+
+    let resolution = 8;
+    let (walk, walked) = common::fastest(5, || pruned(&ring, resolution));
+    let (scan, scanned) = common::fastest(1, || brute_force(resolution));
+    assert_eq!(walked, scanned);
+    println!("resolution {}: {} cells, pruned walk {:?}, every cell {:?}, {:.0}x faster", resolution, walked.len(), walk, scan, scan.as_secs_f64() / walk.as_secs_f64());
+
+    // Finer fills, where a full scan would visit billions of cells
+    for resolution in [10, 12] {
+        let (walk, walked) = common::fastest(3, || pruned(&ring, resolution));
+        println!("resolution {}: {} cells, pruned walk {:?}", resolution, walked.len(), walk);
+    }
+}
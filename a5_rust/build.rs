@@ -0,0 +1,28 @@
+fn main() {
+    #[cfg(feature = "header")]
+    header::generate();
+}
+
+/// Writes include/a5geo.h from cbindgen.toml, as plain C whose declarations
+/// sit in an `extern "C"` block when included from C++.
+#[cfg(feature = "header")]
+mod header {
+    use std::path::Path;
+
+    pub fn generate() {
+        let crate_dir = Path::new(env!("CARGO_MANIFEST_DIR"));
+        println!("cargo:rerun-if-changed=cbindgen.toml");
+        println!("cargo:rerun-if-changed=src");
+
+        let mut config = cbindgen::Config::from_file(crate_dir.join("cbindgen.toml")).expect("cbindgen.toml should be readable");
+        config.language = cbindgen::Language::C;
+        config.cpp_compat = true;
+        config.include_guard = Some("A5GEO_H".to_string());
+        cbindgen::Builder::new()
+            .with_crate(crate_dir)
+            .with_config(config)
+            .generate()
+            .expect("exported items should parse")
+            .write_to_file(crate_dir.join("include/a5geo.h"));
+    }
+}
@@ -0,0 +1,152 @@
+//! Minimal Arrow C Data Interface export.
+//!
+//! The structs follow the layout in the Arrow specification
+//! (https://arrow.apache.org/docs/format/CDataInterface.html). Every exported
+//! array and schema owns its memory through `private_data` and frees it in its
+//! `release` callback, which releases its children first, as the spec asks.
+
+use std::ffi::{c_void, CString};
+use std::os::raw::c_char;
+
+/// Schema field may contain nulls.
+pub(crate) const ARROW_FLAG_NULLABLE: i64 = 2;
+
+#[repr(C)]
+pub struct ArrowSchema {
+    pub format: *const c_char,
+    pub name: *const c_char,
+    pub metadata: *const c_char,
+    pub flags: i64,
+    pub n_children: i64,
+    pub children: *mut *mut ArrowSchema,
+    pub dictionary: *mut ArrowSchema,
+    pub release: Option<unsafe extern "C" fn(schema: *mut ArrowSchema)>,
+    pub private_data: *mut c_void,
+}
+
+#[repr(C)]
+pub struct ArrowArray {
+    pub length: i64,
+    pub null_count: i64,
+    pub offset: i64,
+    pub n_buffers: i64,
+    pub n_children: i64,
+    pub buffers: *mut *const c_void,
+    pub children: *mut *mut ArrowArray,
+    pub dictionary: *mut ArrowArray,
+    pub release: Option<unsafe extern "C" fn(array: *mut ArrowArray)>,
+    pub private_data: *mut c_void,
+}
+
+struct SchemaPrivate {
+    format: CString,
+    name: CString,
+    children: Vec<*mut ArrowSchema>,
+}
+
+unsafe extern "C" fn release_schema(schema: *mut ArrowSchema) {
+    if schema.is_null() || (*schema).release.is_none() {
+        return;
+    }
+    let private = Box::from_raw((*schema).private_data as *mut SchemaPrivate);
+    for &child in &private.children {
+        if let Some(release) = (*child).release {
+            release(child);
+        }
+        drop(Box::from_raw(child));
+    }
+    (*schema).release = None;
+}
+
+/// Builds a schema node named `name` with Arrow type `format`.
+pub fn schema(format: &str, name: &str, flags: i64, children: Vec<ArrowSchema>) -> ArrowSchema {
+    let children: Vec<*mut ArrowSchema> = children.into_iter().map(|child| Box::into_raw(Box::new(child))).collect();
+    let mut private = Box::new(SchemaPrivate { format: CString::new(format).unwrap(), name: CString::new(name).unwrap(), children });
+    ArrowSchema {
+        format: private.format.as_ptr(),
+        name: private.name.as_ptr(),
+        metadata: std::ptr::null(),
+        flags,
+        n_children: private.children.len() as i64,
+        children: if private.children.is_empty() { std::ptr::null_mut() } else { private.children.as_mut_ptr() },
+        dictionary: std::ptr::null_mut(),
+        release: Some(release_schema),
+        private_data: Box::into_raw(private) as *mut c_void,
+    }
+}
+
+struct ArrayPrivate {
+    // Bitmaps are stored as words so the buffer is 8-byte aligned
+    validity: Option<Vec<u64>>,
+    offsets: Option<Vec<i32>>,
+    values: Option<Vec<f64>>,
+    buffers: Vec<*const c_void>,
+    children: Vec<*mut ArrowArray>,
+}
+
+unsafe extern "C" fn release_array(array: *mut ArrowArray) {
+    if array.is_null() || (*array).release.is_none() {
+        return;
+    }
+    let private = Box::from_raw((*array).private_data as *mut ArrayPrivate);
+    for &child in &private.children {
+        if let Some(release) = (*child).release {
+            release(child);
+        }
+        drop(Box::from_raw(child));
+    }
+    (*array).release = None;
+}
+
+fn array(length: usize, null_count: usize, validity: Option<Vec<u64>>, offsets: Option<Vec<i32>>, values: Option<Vec<f64>>, children: Vec<ArrowArray>) -> ArrowArray {
+    let children: Vec<*mut ArrowArray> = children.into_iter().map(|child| Box::into_raw(Box::new(child))).collect();
+    let mut private = Box::new(ArrayPrivate { validity, offsets, values, buffers: Vec::new(), children });
+    private.buffers.push(private.validity.as_ref().map_or(std::ptr::null(), |v| v.as_ptr() as *const c_void));
+    if let Some(offsets) = &private.offsets {
+        private.buffers.push(offsets.as_ptr() as *const c_void);
+    }
+    if let Some(values) = &private.values {
+        private.buffers.push(values.as_ptr() as *const c_void);
+    }
+    ArrowArray {
+        length: length as i64,
+        null_count: null_count as i64,
+        offset: 0,
+        n_buffers: private.buffers.len() as i64,
+        n_children: private.children.len() as i64,
+        buffers: private.buffers.as_mut_ptr(),
+        children: if private.children.is_empty() { std::ptr::null_mut() } else { private.children.as_mut_ptr() },
+        dictionary: std::ptr::null_mut(),
+        release: Some(release_array),
+        private_data: Box::into_raw(private) as *mut c_void,
+    }
+}
+
+/// Primitive `f64` array with no nulls of its own.
+pub fn f64_array(values: Vec<f64>) -> ArrowArray {
+    array(values.len(), 0, None, None, Some(values), Vec::new())
+}
+
+/// Validity bitmap with bit `i` set where `valid[i]` is true.
+fn bitmap(valid: &[bool]) -> Vec<u64> {
+    let mut bitmap = vec![0u64; valid.len().div_ceil(64)];
+    let bytes = unsafe { std::slice::from_raw_parts_mut(bitmap.as_mut_ptr() as *mut u8, bitmap.len() * 8) };
+    for (i, _) in valid.iter().enumerate().filter(|(_, &v)| v) {
+        bytes[i / 8] |= 1 << (i % 8);
+    }
+    bitmap
+}
+
+/// Struct array over `children`, with row `i` null where `valid[i]` is false.
+pub fn struct_array(valid: &[bool], children: Vec<ArrowArray>) -> ArrowArray {
+    let null_count = valid.iter().filter(|&&v| !v).count();
+    array(valid.len(), null_count, Some(bitmap(valid)), None, None, children)
+}
+
+/// List array whose row `i` is the slice `offsets[i]..offsets[i + 1]` of
+/// `values`, null where `valid[i]` is false. `offsets` has one more entry
+/// than `valid`.
+pub fn list_array(valid: &[bool], offsets: Vec<i32>, values: ArrowArray) -> ArrowArray {
+    let null_count = valid.iter().filter(|&&v| !v).count();
+    array(valid.len(), null_count, Some(bitmap(valid)), Some(offsets), None, vec![values])
+}
@@ -0,0 +1,116 @@
+//! A least recently used cache of cell boundaries behind `a5_cell_to_boundary`,
+//! built with the `boundary-cache` feature.
+//!
+//! The cache holds each cell's closed ring at the default segment count, from
+//! which both the open and the closed ring are copied out. It is empty and
+//! disabled until `a5_set_boundary_cache_size` gives it a capacity.
+
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, Mutex, MutexGuard, OnceLock};
+
+use crate::Error;
+
+/// An LRU map from cell ids to values. Each use stamps the entry with a new
+/// tick, and `order` maps ticks back to keys so the stalest entry is first.
+pub struct Lru<V> {
+    capacity: usize,
+    tick: u64,
+    hits: u64,
+    entries: HashMap<u64, (V, u64)>,
+    order: BTreeMap<u64, u64>,
+}
+
+impl<V: Clone> Lru<V> {
+    pub fn new(capacity: usize) -> Self {
+        Self { capacity, tick: 0, hits: 0, entries: HashMap::new(), order: BTreeMap::new() }
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    #[cfg(test)]
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// The number of `get` calls that found their key, since the cache was made.
+    #[cfg(test)]
+    pub fn hits(&self) -> u64 {
+        self.hits
+    }
+
+    /// Returns the value for `key`, marking it as the most recently used.
+    pub fn get(&mut self, key: u64) -> Option<V> {
+        self.tick += 1;
+        let (value, tick) = self.entries.get_mut(&key)?;
+        self.order.remove(tick);
+        *tick = self.tick;
+        self.order.insert(self.tick, key);
+        self.hits += 1;
+        Some(value.clone())
+    }
+
+    /// Inserts or replaces the value for `key` as the most recently used,
+    /// evicting the least recently used entry if the cache is over capacity.
+    pub fn insert(&mut self, key: u64, value: V) {
+        if self.capacity == 0 {
+            return;
+        }
+        self.tick += 1;
+        if let Some((_, tick)) = self.entries.insert(key, (value, self.tick)) {
+            self.order.remove(&tick);
+        }
+        self.order.insert(self.tick, key);
+        self.evict();
+    }
+
+    /// Sets the capacity, evicting the least recently used entries beyond it.
+    pub fn resize(&mut self, capacity: usize) {
+        self.capacity = capacity;
+        self.evict();
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.order.clear();
+    }
+
+    fn evict(&mut self) {
+        while self.entries.len() > self.capacity {
+            let Some((_, key)) = self.order.pop_first() else {
+                break;
+            };
+            self.entries.remove(&key);
+        }
+    }
+}
+
+/// Locks the global boundary cache. A panic while it was held cannot leave an
+/// entry half written, so a poisoned lock is taken over rather than failing.
+pub fn boundaries() -> MutexGuard<'static, Lru<Arc<Vec<a5::LonLat>>>> {
+    static BOUNDARIES: OnceLock<Mutex<Lru<Arc<Vec<a5::LonLat>>>>> = OnceLock::new();
+    BOUNDARIES.get_or_init(|| Mutex::new(Lru::new(0))).lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+/// Returns the closed default boundary of `cell` from the cache, computing
+/// and caching it on a miss, or `None` while the cache is disabled. The lock
+/// is not held while a boundary is computed, so threads missing on the same
+/// cell may each compute it.
+pub fn closed_boundary(cell: u64) -> Option<Result<Arc<Vec<a5::LonLat>>, Error>> {
+    {
+        let mut cache = boundaries();
+        if cache.capacity() == 0 {
+            return None;
+        }
+        if let Some(hit) = cache.get(cell) {
+            return Some(Ok(hit));
+        }
+    }
+    let options = a5::core::cell::CellToBoundaryOptions { closed_ring: true, segments: None };
+    Some(a5::cell_to_boundary(cell, Some(options)).map(|ring| {
+        let ring = Arc::new(ring);
+        boundaries().insert(cell, ring.clone());
+        ring
+    }).map_err(Error::from))
+}
@@ -0,0 +1,150 @@
+//! Dissolving a set of cells into the outline of their union.
+//!
+//! Cells at one resolution tile the sphere edge to edge, and neighbours compute
+//! a shared edge from the same vertices up to rounding, so the outline is made
+//! of the edges that belong to exactly one cell of the set. Those edges keep
+//! their cell's winding, so chaining them yields outer rings wound like the
+//! cells and holes wound the other way.
+
+use crate::geometry::{planar_ring, ring_contains, signed_area, Point};
+use std::collections::HashMap;
+
+/// Vertices closer than this many degrees are treated as the same vertex.
+/// Shared vertices of neighbouring cells differ by around 1e-12 degrees.
+const TOLERANCE: f64 = 1e-9;
+
+/// Interns vertices, merging those within `TOLERANCE` of each other. Points
+/// are binned on a `TOLERANCE` grid and each lookup checks the neighbouring
+/// bins, wrapping around the antimeridian.
+#[derive(Default)]
+struct Vertices {
+    bins: HashMap<(i64, i64), Vec<usize>>,
+    points: Vec<Point>,
+}
+
+impl Vertices {
+    fn id(&mut self, (lon, lat): Point) -> usize {
+        // A pole has no meaningful longitude, so all its vertices are one
+        let (lon, lat) = if lat.abs() >= 90.0 - TOLERANCE { (0.0, 90f64.copysign(lat)) } else { ((lon + 180.0).rem_euclid(360.0) - 180.0, lat) };
+        let turn = (360.0 / TOLERANCE).round() as i64;
+        let (x, y) = ((((lon + 180.0) / TOLERANCE).round() as i64).rem_euclid(turn), (lat / TOLERANCE).round() as i64);
+        for dx in -1..=1 {
+            for dy in -1..=1 {
+                let found = self.bins.get(&((x + dx).rem_euclid(turn), y + dy)).and_then(|ids| {
+                    ids.iter().copied().find(|&id| {
+                        let (other_lon, other_lat) = self.points[id];
+                        let d_lon = (lon - other_lon).abs();
+                        d_lon.min(360.0 - d_lon) <= TOLERANCE && (lat - other_lat).abs() <= TOLERANCE
+                    })
+                });
+                if let Some(id) = found {
+                    return id;
+                }
+            }
+        }
+        self.points.push((lon, lat));
+        self.bins.entry((x, y)).or_default().push(self.points.len() - 1);
+        self.points.len() - 1
+    }
+}
+
+/// Returns the edges of `rings` that no other ring shares, in their ring's
+/// direction, as pairs of interned vertex ids.
+fn outline_edges(vertices: &mut Vertices, rings: &[Vec<Point>]) -> Vec<(usize, usize)> {
+    let mut edges: HashMap<(usize, usize), ((usize, usize), usize)> = HashMap::new();
+    for ring in rings {
+        let ids: Vec<usize> = ring.iter().map(|&p| vertices.id(p)).collect();
+        for i in 0..ids.len() {
+            let (a, b) = (ids[i], ids[(i + 1) % ids.len()]);
+            if a != b {
+                edges.entry((a.min(b), a.max(b))).or_insert(((a, b), 0)).1 += 1;
+            }
+        }
+    }
+    let mut outline: Vec<(usize, usize)> = edges.into_values().filter(|&(_, count)| count == 1).map(|(edge, _)| edge).collect();
+    // Sort so the output does not depend on hash order
+    outline.sort_unstable();
+    outline
+}
+
+/// Chains directed edges into closed rings of vertex ids. Where the outline
+/// touches itself at a vertex, any unused outgoing edge is followed; the rings
+/// still cover each edge exactly once.
+fn chain(edges: &[(usize, usize)]) -> Vec<Vec<usize>> {
+    let mut outgoing: HashMap<usize, Vec<usize>> = HashMap::new();
+    for (i, &(a, _)) in edges.iter().enumerate().rev() {
+        outgoing.entry(a).or_default().push(i);
+    }
+    let mut used = vec![false; edges.len()];
+    let mut rings = Vec::new();
+    for start in 0..edges.len() {
+        if used[start] {
+            continue;
+        }
+        let mut ring = Vec::new();
+        let mut edge = start;
+        loop {
+            used[edge] = true;
+            ring.push(edges[edge].0);
+            let next = outgoing.get_mut(&edges[edge].1).and_then(|candidates| {
+                while let Some(&candidate) = candidates.last() {
+                    if !used[candidate] {
+                        return Some(candidate);
+                    }
+                    candidates.pop();
+                }
+                None
+            });
+            match next {
+                Some(next) => edge = next,
+                None => break,
+            }
+        }
+        rings.push(ring);
+    }
+    rings
+}
+
+/// Dissolves cells given by their open boundary `rings`, all at one
+/// resolution and without duplicates, into polygons made of an outer ring
+/// followed by its holes. Rings are planar as from `planar_ring`, outer rings
+/// wound like the input and holes the other way.
+///
+/// A hole that no outer ring contains, such as the gap left by a set covering
+/// all but a few cells, or a ring around a pole whose cap is missing, belongs
+/// to the rest of the sphere; it is placed in a polygon whose outer ring is the
+/// whole [-180, 180] x [-90, 90] plane, which is also the result for cells
+/// covering the whole sphere.
+pub fn dissolve(rings: &[Vec<Point>]) -> Vec<Vec<Vec<Point>>> {
+    let Some(winding) = rings.first().map(|ring| signed_area(&planar_ring(ring)).signum()) else {
+        return Vec::new();
+    };
+    let mut vertices = Vertices::default();
+    let edges = outline_edges(&mut vertices, rings);
+    let outline: Vec<Vec<Point>> = chain(&edges).into_iter().map(|ids| planar_ring(&ids.iter().map(|&id| vertices.points[id]).collect::<Vec<_>>())).collect();
+
+    let (outers, holes): (Vec<Vec<Point>>, Vec<Vec<Point>>) = outline.into_iter().partition(|ring| signed_area(ring).signum() == winding);
+    let mut polygons: Vec<Vec<Vec<Point>>> = outers.into_iter().map(|outer| vec![outer]).collect();
+    let mut world = vec![(-180.0, -90.0), (180.0, -90.0), (180.0, 90.0), (-180.0, 90.0)];
+    if winding < 0.0 {
+        world.reverse();
+    }
+    if polygons.is_empty() && holes.is_empty() {
+        return vec![vec![world]];
+    }
+    let mut orphans = Vec::new();
+    for hole in holes {
+        // An edge midpoint lies on this hole alone, unlike a vertex, which a
+        // hole can share with its outer ring where they touch
+        let (x, y) = ((hole[0].0 + hole[1].0) / 2.0, (hole[0].1 + hole[1].1) / 2.0);
+        let containing = polygons.iter_mut().filter(|polygon| [-360.0, 0.0, 360.0].iter().any(|o| ring_contains(&polygon[0], x + o, y))).min_by(|a, b| signed_area(&a[0]).abs().total_cmp(&signed_area(&b[0]).abs()));
+        match containing {
+            Some(polygon) => polygon.push(hole),
+            None => orphans.push(hole),
+        }
+    }
+    if !orphans.is_empty() {
+        polygons.push(std::iter::once(world).chain(orphans).collect());
+    }
+    polygons
+}
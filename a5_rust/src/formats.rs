@@ -0,0 +1,282 @@
+//! Text and binary encodings of cell geometry for handing to GIS tooling.
+//!
+//! Writers take the rings produced by `cell_parts`: open, counter-clockwise
+//! and already split at the antimeridian. They close each ring themselves.
+
+use crate::geometry::Point;
+use crate::{A5ErrorCode, Error};
+use std::fmt::Write;
+
+/// Writes `ring` as a closed GeoJSON linear ring.
+fn write_geojson_ring(out: &mut String, ring: &[Point]) {
+    out.push('[');
+    for (lon, lat) in ring.iter().chain(ring.first()) {
+        let _ = write!(out, "[{},{}],", lon, lat);
+    }
+    out.pop();
+    out.push(']');
+}
+
+/// Writes a GeoJSON geometry object: a Polygon for a single part, otherwise
+/// a MultiPolygon with one polygon per part.
+pub fn write_geojson_geometry(out: &mut String, parts: &[Vec<Point>]) {
+    if let [ring] = parts {
+        out.push_str("{\"type\":\"Polygon\",\"coordinates\":[");
+        write_geojson_ring(out, ring);
+        out.push_str("]}");
+    } else {
+        out.push_str("{\"type\":\"MultiPolygon\",\"coordinates\":[");
+        for (i, ring) in parts.iter().enumerate() {
+            out.push_str(if i == 0 { "[" } else { ",[" });
+            write_geojson_ring(out, ring);
+            out.push(']');
+        }
+        out.push_str("]}");
+    }
+}
+
+/// Writes a GeoJSON MultiPolygon with one polygon per entry of `polygons`,
+/// each an outer ring followed by its holes.
+pub fn write_geojson_multipolygon(out: &mut String, polygons: &[Vec<Vec<Point>>]) {
+    out.push_str("{\"type\":\"MultiPolygon\",\"coordinates\":[");
+    for (i, rings) in polygons.iter().enumerate() {
+        out.push_str(if i == 0 { "[" } else { ",[" });
+        for (j, ring) in rings.iter().enumerate() {
+            if j > 0 {
+                out.push(',');
+            }
+            write_geojson_ring(out, ring);
+        }
+        out.push(']');
+    }
+    out.push_str("]}");
+}
+
+/// Builds a FeatureCollection with one Feature per `(cell, parts)` entry,
+/// carrying the cell hex id in `properties.a5` when `include_id` is set.
+pub fn geojson_feature_collection(cells: &[(u64, Vec<Vec<Point>>)], include_id: bool) -> String {
+    let mut out = String::from("{\"type\":\"FeatureCollection\",\"features\":[");
+    for (i, (cell, parts)) in cells.iter().enumerate() {
+        if i > 0 {
+            out.push(',');
+        }
+        out.push_str("{\"type\":\"Feature\",\"properties\":");
+        if include_id {
+            let _ = write!(out, "{{\"a5\":\"{:016x}\"}}", cell);
+        } else {
+            out.push_str("{}");
+        }
+        out.push_str(",\"geometry\":");
+        write_geojson_geometry(&mut out, parts);
+        out.push('}');
+    }
+    out.push_str("]}");
+    out
+}
+
+/// Builds a CSV table with a header row and one row per cell: the hex id in
+/// column `a5`, then `center_lon,center_lat` when `centers` is given and
+/// `resolution` when `include_resolution` is set. Rows end in `\n`.
+pub fn cells_csv(cells: &[u64], centers: Option<&[Point]>, include_resolution: bool) -> String {
+    let mut out = String::from("a5");
+    if centers.is_some() {
+        out.push_str(",center_lon,center_lat");
+    }
+    if include_resolution {
+        out.push_str(",resolution");
+    }
+    out.push('\n');
+    for (i, &cell) in cells.iter().enumerate() {
+        let _ = write!(out, "{:016x}", cell);
+        if let Some((lon, lat)) = centers.map(|centers| centers[i]) {
+            let _ = write!(out, ",{},{}", lon, lat);
+        }
+        if include_resolution {
+            let _ = write!(out, ",{}", a5::get_resolution(cell));
+        }
+        out.push('\n');
+    }
+    out
+}
+
+/// Encodes `ring` as a WKT `POLYGON`. Coordinates use the shortest decimal
+/// form that parses back to the same `f64`.
+pub fn wkt_polygon(ring: &[Point]) -> String {
+    let mut out = String::from("POLYGON((");
+    for (lon, lat) in ring.iter().chain(ring.first()) {
+        let _ = write!(out, "{} {}, ", lon, lat);
+    }
+    out.truncate(out.len() - 2);
+    out.push_str("))");
+    out
+}
+
+/// Encodes `ring` as a little-endian OGC WKB Polygon with a single closed ring.
+pub fn wkb_polygon(ring: &[Point]) -> Vec<u8> {
+    let points = ring.len() + 1;
+    let mut out = Vec::with_capacity(13 + points * 16);
+    out.push(1); // little-endian byte order
+    out.extend_from_slice(&3u32.to_le_bytes()); // Polygon
+    out.extend_from_slice(&1u32.to_le_bytes());
+    out.extend_from_slice(&(points as u32).to_le_bytes());
+    for (lon, lat) in ring.iter().chain(ring.first()) {
+        out.extend_from_slice(&lon.to_le_bytes());
+        out.extend_from_slice(&lat.to_le_bytes());
+    }
+    out
+}
+
+/// The fewest trailing zeros of any of `cells`. Shifting them all right by
+/// this drops the resolution marker's zeros, so neighbouring cells at one
+/// resolution end up a small gap apart.
+fn common_shift(cells: &[u64]) -> u32 {
+    cells.iter().map(|cell| cell.trailing_zeros()).min().unwrap_or(0).min(63)
+}
+
+/// Appends the gaps between consecutive sorted `cells` shifted right by
+/// `shift`, the first measured from 0, each as an unsigned LEB128 varint.
+fn write_deltas(out: &mut Vec<u8>, cells: &[u64], shift: u32) {
+    let mut previous = 0;
+    for &cell in cells {
+        let mut delta = (cell >> shift) - previous;
+        while delta >= 0x80 {
+            out.push(delta as u8 | 0x80);
+            delta >>= 7;
+        }
+        out.push(delta as u8);
+        previous = cell >> shift;
+    }
+}
+
+/// Reverses `write_deltas`, describing malformed input with `malformed`.
+fn read_deltas(bytes: &[u8], shift: u32, malformed: impl Fn(&str) -> Error) -> Result<Vec<u64>, Error> {
+    let mut cells = Vec::with_capacity(bytes.len());
+    let (mut cell, mut delta, mut bits) = (0u64, 0u64, 0);
+    for &byte in bytes {
+        if bits > 63 || (bits == 63 && byte > 1) {
+            return Err(malformed("varint overflows 64 bits"));
+        }
+        delta |= ((byte & 0x7f) as u64) << bits;
+        bits += 7;
+        if byte & 0x80 == 0 {
+            cell = cell.checked_add(delta).filter(|&cell| cell.leading_zeros() >= shift).ok_or_else(|| malformed("cell id overflows 64 bits"))?;
+            cells.push(cell << shift);
+            (delta, bits) = (0, 0);
+        }
+    }
+    if bits != 0 {
+        return Err(malformed("truncated varint"));
+    }
+    Ok(cells)
+}
+
+/// Encodes sorted `cells` as a MessagePack array of three items: the cell
+/// count as an unsigned integer, the shift from `common_shift`, then a `bin`
+/// holding the ids as from `write_deltas`.
+pub fn cells_msgpack(cells: &[u64]) -> Vec<u8> {
+    let shift = common_shift(cells);
+    let mut deltas = Vec::with_capacity(cells.len() * 2);
+    write_deltas(&mut deltas, cells, shift);
+    let mut out = Vec::with_capacity(deltas.len() + 16);
+    out.push(0x93);
+    match cells.len() as u64 {
+        n @ 0..=0x7f => out.push(n as u8),
+        n @ 0..=0xff => out.extend_from_slice(&[0xcc, n as u8]),
+        n @ 0..=0xffff => {
+            out.push(0xcd);
+            out.extend_from_slice(&(n as u16).to_be_bytes());
+        }
+        n @ 0..=0xffff_ffff => {
+            out.push(0xce);
+            out.extend_from_slice(&(n as u32).to_be_bytes());
+        }
+        n => {
+            out.push(0xcf);
+            out.extend_from_slice(&n.to_be_bytes());
+        }
+    }
+    out.push(shift as u8);
+    match deltas.len() {
+        n @ 0..=0xff => out.extend_from_slice(&[0xc4, n as u8]),
+        n @ 0..=0xffff => {
+            out.push(0xc5);
+            out.extend_from_slice(&(n as u16).to_be_bytes());
+        }
+        n => {
+            out.push(0xc6);
+            out.extend_from_slice(&(n as u32).to_be_bytes());
+        }
+    }
+    out.extend_from_slice(&deltas);
+    out
+}
+
+/// Decodes the cell ids written by `cells_msgpack`. Any MessagePack width is
+/// accepted for the count and the `bin`, but the input must hold exactly that
+/// one array.
+pub fn msgpack_cells(bytes: &[u8]) -> Result<Vec<u64>, Error> {
+    let malformed = |what: &str| Error::new(A5ErrorCode::InvalidCell, format!("invalid cell set msgpack: {}", what));
+    let mut rest = bytes;
+    let mut take = |n: usize| -> Result<u64, Error> {
+        if rest.len() < n {
+            return Err(malformed("truncated"));
+        }
+        let (head, tail) = rest.split_at(n);
+        rest = tail;
+        Ok(head.iter().fold(0, |acc, &b| (acc << 8) | b as u64))
+    };
+    if take(1)? != 0x93 {
+        return Err(malformed("expected an array of 3 items"));
+    }
+    let count = match take(1)? {
+        n @ 0..=0x7f => n,
+        0xcc => take(1)?,
+        0xcd => take(2)?,
+        0xce => take(4)?,
+        0xcf => take(8)?,
+        _ => return Err(malformed("expected an unsigned cell count")),
+    };
+    let shift = match take(1)? {
+        n @ 0..=63 => n,
+        _ => return Err(malformed("expected a shift below 64")),
+    };
+    let len = match take(1)? {
+        0xc4 => take(1)?,
+        0xc5 => take(2)?,
+        0xc6 => take(4)?,
+        _ => return Err(malformed("expected bin data")),
+    } as usize;
+    if rest.len() != len {
+        return Err(malformed(if rest.len() < len { "truncated" } else { "trailing bytes" }));
+    }
+
+    let cells = read_deltas(rest, shift as u32, malformed)?;
+    if cells.len() as u64 != count {
+        return Err(malformed(&format!("header says {} cells but data holds {}", count, cells.len())));
+    }
+    Ok(cells)
+}
+
+/// Encodes sorted, deduplicated `cells` as a bare byte stream: one byte
+/// holding the shift from `common_shift`, then the ids as from `write_deltas`.
+/// An empty set is an empty stream.
+pub fn compress_cells(cells: &[u64]) -> Vec<u8> {
+    if cells.is_empty() {
+        return Vec::new();
+    }
+    let shift = common_shift(cells);
+    let mut out = Vec::with_capacity(cells.len() * 2 + 1);
+    out.push(shift as u8);
+    write_deltas(&mut out, cells, shift);
+    out
+}
+
+/// Decodes a stream from `compress_cells`.
+pub fn decompress_cells(bytes: &[u8]) -> Result<Vec<u64>, Error> {
+    let malformed = |what: &str| Error::new(A5ErrorCode::InvalidCell, format!("invalid cell stream: {}", what));
+    match bytes.split_first() {
+        None => Ok(Vec::new()),
+        Some((&shift, _)) if shift > 63 => Err(malformed("shift byte above 63")),
+        Some((&shift, deltas)) => read_deltas(deltas, shift as u32, malformed),
+    }
+}
@@ -0,0 +1,376 @@
+//! Spherical geometry helpers shared by the exported functions.
+//!
+//! All lengths are measured on the authalic sphere of radius
+//! `AUTHALIC_RADIUS_EARTH` (6 371 007.2 m), the sphere on which A5 cells have
+//! equal area.
+
+pub use a5::core::constants::AUTHALIC_RADIUS_EARTH;
+
+/// Great-circle distance in meters between two points given in degrees.
+pub fn haversine_distance(lon1: f64, lat1: f64, lon2: f64, lat2: f64) -> f64 {
+    let (phi1, phi2) = (lat1.to_radians(), lat2.to_radians());
+    let d_phi = phi2 - phi1;
+    let d_lambda = (lon2 - lon1).to_radians();
+    let h = (d_phi / 2.0).sin().powi(2) + phi1.cos() * phi2.cos() * (d_lambda / 2.0).sin().powi(2);
+    2.0 * AUTHALIC_RADIUS_EARTH * h.sqrt().min(1.0).asin()
+}
+
+/// Initial great-circle bearing in degrees clockwise from true north, in
+/// [0, 360), for travel from the first point to the second. NaN when the
+/// direction is undefined: the points coincide or are antipodal.
+pub fn initial_bearing(lon1: f64, lat1: f64, lon2: f64, lat2: f64) -> f64 {
+    let (phi1, phi2) = (lat1.to_radians(), lat2.to_radians());
+    let d_lambda = (lon2 - lon1).to_radians();
+    let y = d_lambda.sin() * phi2.cos();
+    let x = phi1.cos() * phi2.sin() - phi1.sin() * phi2.cos() * d_lambda.cos();
+    if x.abs() < 1e-15 && y.abs() < 1e-15 {
+        return f64::NAN;
+    }
+    y.atan2(x).to_degrees().rem_euclid(360.0)
+}
+
+/// Radius in meters of the WGS84 sphere Web Mercator projects from.
+pub const WEB_MERCATOR_RADIUS: f64 = 6_378_137.0;
+
+/// Latitude in degrees at which Web Mercator's square world ends.
+pub const WEB_MERCATOR_MAX_LATITUDE: f64 = 85.051_128_779_806_59;
+
+/// Projects a point to Web Mercator (EPSG:3857) meters, with the latitude
+/// clamped to +-`WEB_MERCATOR_MAX_LATITUDE` first, since the poles lie at
+/// infinity.
+pub fn web_mercator(lon: f64, lat: f64) -> Point {
+    let phi = lat.clamp(-WEB_MERCATOR_MAX_LATITUDE, WEB_MERCATOR_MAX_LATITUDE).to_radians();
+    (WEB_MERCATOR_RADIUS * lon.to_radians(), WEB_MERCATOR_RADIUS * (std::f64::consts::FRAC_PI_4 + phi / 2.0).tan().ln())
+}
+
+/// A vertex in the plane of longitude/latitude degrees.
+pub type Point = (f64, f64);
+
+/// Unwraps ring longitudes so that consecutive vertices are never more than
+/// 180 degrees apart, letting a ring that crosses the antimeridian be treated
+/// as one contiguous shape. A closing vertex equal to the first is dropped.
+pub fn unwrap_ring(points: impl IntoIterator<Item = Point>) -> Vec<Point> {
+    let mut ring: Vec<Point> = Vec::new();
+    for (mut lon, lat) in points {
+        // Shift by whole turns so vertices already in range stay bit-exact
+        if let Some(&(prev, _)) = ring.last() {
+            lon -= ((lon - prev) / 360.0).round() * 360.0;
+        }
+        ring.push((lon, lat));
+    }
+    if ring.len() > 1 && ring.first() == ring.last() {
+        ring.pop();
+    }
+    ring
+}
+
+/// Turns a spherical ring into a planar one with longitudes unwrapped from its
+/// first vertex. A5 puts the poles on cell vertices from resolution 1 on, and
+/// such a vertex has no meaningful longitude, so it is replaced by two points
+/// on the pole's latitude below its neighbours. A ring around a pole is closed
+/// along the latitude of the pole on its side, so it still encloses the cap.
+pub fn planar_ring(raw: &[Point]) -> Vec<Point> {
+    let n = raw.len();
+    let mut points = Vec::with_capacity(n + 1);
+    for (i, &(lon, lat)) in raw.iter().enumerate() {
+        if lat.abs() >= 90.0 - 1e-9 {
+            points.push((raw[(i + n - 1) % n].0, lat));
+            points.push((raw[(i + 1) % n].0, lat));
+        } else {
+            points.push((lon, lat));
+        }
+    }
+    let mut ring = unwrap_ring(points);
+
+    // After unwrapping, a ring around a pole does not return to its start
+    let (first, last) = (ring[0], ring[ring.len() - 1]);
+    if (first.0 - last.0).abs() > 180.0 {
+        let pole = if raw.iter().map(|p| p.1).sum::<f64>() > 0.0 { 90.0 } else { -90.0 };
+        let end = first.0 + (last.0 - first.0).signum() * 360.0;
+        ring.extend([(end, first.1), (end, pole), (first.0, pole)]);
+    }
+    ring
+}
+
+/// Even-odd test of whether `(x, y)` lies inside `ring`, so either winding
+/// order is accepted.
+pub fn ring_contains(ring: &[Point], x: f64, y: f64) -> bool {
+    let mut inside = false;
+    let mut j = ring.len().wrapping_sub(1);
+    for i in 0..ring.len() {
+        let ((xi, yi), (xj, yj)) = (ring[i], ring[j]);
+        if (yi > y) != (yj > y) && x < (xj - xi) * (y - yi) / (yj - yi) + xi {
+            inside = !inside;
+        }
+        j = i;
+    }
+    inside
+}
+
+fn orientation(a: Point, b: Point, c: Point) -> f64 {
+    (b.0 - a.0) * (c.1 - a.1) - (b.1 - a.1) * (c.0 - a.0)
+}
+
+/// Whether the segments `a1-a2` and `b1-b2` properly cross each other.
+fn segments_cross(a1: Point, a2: Point, b1: Point, b2: Point) -> bool {
+    let (d1, d2) = (orientation(b1, b2, a1), orientation(b1, b2, a2));
+    let (d3, d4) = (orientation(a1, a2, b1), orientation(a1, a2, b2));
+    d1 * d2 < 0.0 && d3 * d4 < 0.0
+}
+
+fn edges_cross(a: &[Point], b: &[Point]) -> bool {
+    let edges = |ring: &[Point]| (0..ring.len()).map(|i| (ring[i], ring[(i + 1) % ring.len()])).collect::<Vec<_>>();
+    let b_edges = edges(b);
+    edges(a).iter().any(|&(a1, a2)| b_edges.iter().any(|&(b1, b2)| segments_cross(a1, a2, b1, b2)))
+}
+
+/// Whether two rings share any area: one has a vertex inside the other or
+/// their edges cross.
+pub fn rings_intersect(a: &[Point], b: &[Point]) -> bool {
+    a.iter().any(|&(x, y)| ring_contains(b, x, y)) || b.iter().any(|&(x, y)| ring_contains(a, x, y)) || edges_cross(a, b)
+}
+
+/// Whether `inner` lies entirely within `outer`.
+pub fn ring_encloses(outer: &[Point], inner: &[Point]) -> bool {
+    inner.iter().all(|&(x, y)| ring_contains(outer, x, y)) && !outer.iter().any(|&(x, y)| ring_contains(inner, x, y)) && !edges_cross(outer, inner)
+}
+
+/// Twice the signed area of `ring`, positive when it winds counter-clockwise.
+pub fn signed_area(ring: &[Point]) -> f64 {
+    (0..ring.len()).map(|i| {
+        let ((x1, y1), (x2, y2)) = (ring[i], ring[(i + 1) % ring.len()]);
+        x1 * y2 - x2 * y1
+    }).sum()
+}
+
+/// Returns `ring` wound counter-clockwise, reversing it if needed.
+pub fn counter_clockwise(mut ring: Vec<Point>) -> Vec<Point> {
+    if signed_area(&ring) < 0.0 {
+        ring.reverse();
+    }
+    ring
+}
+
+/// Keeps the part of `subject` inside the convex, counter-clockwise ring
+/// `clip` (Sutherland-Hodgman). A concave subject can come out as several
+/// pieces joined by zero-width bridges along the clip edges, which leaves its
+/// area correct.
+pub fn clip_to_convex(subject: &[Point], clip: &[Point]) -> Vec<Point> {
+    let mut out = subject.to_vec();
+    for i in 0..clip.len() {
+        let (p, q) = (clip[i], clip[(i + 1) % clip.len()]);
+        let side = |(x, y): Point| (q.0 - p.0) * (y - p.1) - (q.1 - p.1) * (x - p.0);
+        let ring = std::mem::take(&mut out);
+        for j in 0..ring.len() {
+            let (a, b) = (ring[j], ring[(j + 1) % ring.len()]);
+            let (sa, sb) = (side(a), side(b));
+            if sa >= 0.0 {
+                out.push(a);
+            }
+            if (sa >= 0.0) != (sb >= 0.0) {
+                let t = sa / (sa - sb);
+                out.push((a.0 + t * (b.0 - a.0), a.1 + t * (b.1 - a.1)));
+            }
+        }
+    }
+    out
+}
+
+/// Keeps the part of `ring` on one side of the meridian `x`, where `keep`
+/// says whether a longitude is on the kept side (Sutherland-Hodgman).
+fn clip_at_meridian(ring: &[Point], x: f64, keep: impl Fn(f64) -> bool) -> Vec<Point> {
+    let mut out = Vec::new();
+    for i in 0..ring.len() {
+        let (a, b) = (ring[i], ring[(i + 1) % ring.len()]);
+        if keep(a.0) {
+            out.push(a);
+        }
+        if keep(a.0) != keep(b.0) {
+            let t = (x - a.0) / (b.0 - a.0);
+            out.push((x, a.1 + t * (b.1 - a.1)));
+        }
+    }
+    out
+}
+
+/// Splits a ring whose unwrapped longitudes pass ±180 into the parts on each
+/// side of the antimeridian, each shifted back into [-180, 180]. A ring that
+/// stays within range is returned unchanged.
+pub fn split_antimeridian(ring: &[Point]) -> Vec<Vec<Point>> {
+    let (min, max) = ring.iter().fold((f64::INFINITY, f64::NEG_INFINITY), |(lo, hi), p| (lo.min(p.0), hi.max(p.0)));
+    let (x, shift) = if max > 180.0 {
+        (180.0, -360.0)
+    } else if min < -180.0 {
+        (-180.0, 360.0)
+    } else {
+        return vec![ring.to_vec()];
+    };
+    let inside = clip_at_meridian(ring, x, |lon| if x > 0.0 { lon <= x } else { lon >= x });
+    let outside = clip_at_meridian(ring, x, |lon| if x > 0.0 { lon > x } else { lon < x });
+    let outside = outside.into_iter().map(|(lon, lat)| (lon + shift, lat)).collect();
+    [inside, outside].into_iter().filter(|part| part.len() >= 3).collect()
+}
+
+fn to_unit_vector((lon, lat): Point) -> [f64; 3] {
+    let (lambda, phi) = (lon.to_radians(), lat.to_radians());
+    [phi.cos() * lambda.cos(), phi.cos() * lambda.sin(), phi.sin()]
+}
+
+fn dot(a: [f64; 3], b: [f64; 3]) -> f64 {
+    a[0] * b[0] + a[1] * b[1] + a[2] * b[2]
+}
+
+fn cross(a: [f64; 3], b: [f64; 3]) -> [f64; 3] {
+    [a[1] * b[2] - a[2] * b[1], a[2] * b[0] - a[0] * b[2], a[0] * b[1] - a[1] * b[0]]
+}
+
+fn from_unit_vector(v: [f64; 3]) -> Point {
+    (v[1].atan2(v[0]).to_degrees(), v[2].atan2(v[0].hypot(v[1])).to_degrees())
+}
+
+/// Gnomonic projection onto the plane touching the sphere at a point, with x
+/// pointing east and y north there, in units of the sphere's radius. Great
+/// circles project to straight lines. At a pole, where east is undefined, x
+/// points toward longitude 90 and y toward longitude 180 at the north pole or
+/// 0 at the south pole, keeping the frame right-handed.
+pub struct TangentPlane {
+    center: [f64; 3],
+    east: [f64; 3],
+    north: [f64; 3],
+}
+
+impl TangentPlane {
+    pub fn new(center: Point) -> Self {
+        let center = to_unit_vector(center);
+        let east = cross([0.0, 0.0, 1.0], center);
+        let norm = dot(east, east).sqrt();
+        let east = if norm < 1e-12 { [0.0, 1.0, 0.0] } else { east.map(|c| c / norm) };
+        Self { center, east, north: cross(center, east) }
+    }
+
+    /// Projects `p`, or returns `None` if it lies 90 degrees or more from the
+    /// center, beyond the plane's reach.
+    pub fn project(&self, p: Point) -> Option<Point> {
+        let v = to_unit_vector(p);
+        let depth = dot(v, self.center);
+        (depth > 1e-12).then(|| (dot(v, self.east) / depth, dot(v, self.north) / depth))
+    }
+
+    pub fn unproject(&self, (x, y): Point) -> Point {
+        let v: [f64; 3] = std::array::from_fn(|i| self.center[i] + x * self.east[i] + y * self.north[i]);
+        from_unit_vector(v)
+    }
+}
+
+/// Great-circle path from `a` to `b`: its length in meters and the point at
+/// fraction `t` of the way along it, with longitude in [-180, 180]. Working on
+/// unit vectors means the antimeridian needs no special handling. Returns
+/// `None` for antipodal points, between which the path is not unique.
+pub fn geodesic(a: Point, b: Point) -> Option<(f64, impl Fn(f64) -> Point)> {
+    let (u, v) = (to_unit_vector(a), to_unit_vector(b));
+    let normal = cross(u, v);
+    let angle = dot(normal, normal).sqrt().atan2(dot(u, v));
+    if angle > std::f64::consts::PI - 1e-9 {
+        return None;
+    }
+    let point = move |t: f64| {
+        if angle < 1e-15 {
+            return from_unit_vector(u);
+        }
+        let (wu, wv) = (((1.0 - t) * angle).sin() / angle.sin(), (t * angle).sin() / angle.sin());
+        from_unit_vector([wu * u[0] + wv * v[0], wu * u[1] + wv * v[1], wu * u[2] + wv * v[2]])
+    };
+    Some((angle * AUTHALIC_RADIUS_EARTH, point))
+}
+
+/// Area in square meters of the spherical polygon whose great-circle edges join
+/// the vertices of `ring`.
+///
+/// The polygon is split into a fan of triangles about `apex`, which must lie
+/// inside it, and each triangle's spherical excess `E` comes from the
+/// Oosterom-Strackee formula `tan(E / 2) = a . (b x c) / (1 + a.b + b.c + c.a)`
+/// on unit vectors. Excesses are signed, so the winding only flips the sign
+/// of the sum and the magnitude is returned.
+pub fn spherical_polygon_area(apex: Point, ring: &[Point]) -> f64 {
+    let a = to_unit_vector(apex);
+    let excess: f64 = (0..ring.len()).map(|i| {
+        let (b, c) = (to_unit_vector(ring[i]), to_unit_vector(ring[(i + 1) % ring.len()]));
+        2.0 * dot(a, cross(b, c)).atan2(1.0 + dot(a, b) + dot(b, c) + dot(c, a))
+    }).sum();
+    excess.abs() * AUTHALIC_RADIUS_EARTH * AUTHALIC_RADIUS_EARTH
+}
+
+/// Area-weighted centroid of the spherical polygon whose great-circle edges
+/// join the vertices of `ring`, projected back onto the sphere, for a ring
+/// covering less than a hemisphere.
+///
+/// The first moment of the polygon's surface is half the sum over its edges of
+/// each edge's unit normal times its angle, which is exact for great-circle
+/// edges; the winding only flips its sign, which is undone by facing it
+/// towards the vertices.
+pub fn spherical_polygon_centroid(ring: &[Point]) -> Point {
+    let vectors: Vec<[f64; 3]> = ring.iter().map(|&p| to_unit_vector(p)).collect();
+    let mut moment = [0.0; 3];
+    for i in 0..vectors.len() {
+        let (a, b) = (vectors[i], vectors[(i + 1) % vectors.len()]);
+        let normal = cross(a, b);
+        let norm = dot(normal, normal).sqrt();
+        if norm > 1e-15 {
+            let weight = angle_between(a, b) / norm;
+            moment = [moment[0] + weight * normal[0], moment[1] + weight * normal[1], moment[2] + weight * normal[2]];
+        }
+    }
+    let sum = vectors.iter().fold([0.0; 3], |acc, v| [acc[0] + v[0], acc[1] + v[1], acc[2] + v[2]]);
+    if dot(moment, sum) < 0.0 {
+        moment = [-moment[0], -moment[1], -moment[2]];
+    }
+    from_unit_vector(moment)
+}
+
+/// Angle in radians between unit vectors `a` and `b`.
+fn angle_between(a: [f64; 3], b: [f64; 3]) -> f64 {
+    let normal = cross(a, b);
+    dot(normal, normal).sqrt().atan2(dot(a, b))
+}
+
+/// Distance in meters from `p` to the great-circle arc from `a` to `b`: the
+/// cross-track distance where `p` lies alongside the arc, otherwise the
+/// distance to the nearer end.
+fn distance_to_arc(p: [f64; 3], a: [f64; 3], b: [f64; 3]) -> f64 {
+    let normal = cross(a, b);
+    let norm = dot(normal, normal).sqrt();
+    let alongside = norm > 1e-15 && dot(cross(normal, a), p) >= 0.0 && dot(cross(b, normal), p) >= 0.0;
+    let angle = if alongside { (dot(normal, p) / norm).abs().min(1.0).asin() } else { angle_between(p, a).min(angle_between(p, b)) };
+    angle * AUTHALIC_RADIUS_EARTH
+}
+
+/// Douglas-Peucker simplification on the sphere: keeps the first and last
+/// points and every point needed so that no dropped point lies more than
+/// `tolerance` meters from the great-circle path through the kept ones.
+///
+/// A closed ring, whose last point repeats its first, is always split at its
+/// two levels of farthest points whatever the tolerance, so it keeps at least
+/// four distinct vertices if it had them and never collapses to a line.
+pub fn simplify(points: &[Point], tolerance: f64) -> Vec<Point> {
+    if points.len() < 3 {
+        return points.to_vec();
+    }
+    let vectors: Vec<[f64; 3]> = points.iter().map(|&p| to_unit_vector(p)).collect();
+    let closed = points[0] == points[points.len() - 1];
+    let mut keep = vec![false; points.len()];
+    let (first, last) = (0, points.len() - 1);
+    keep[first] = true;
+    keep[last] = true;
+    let mut ranges = vec![(first, last, if closed { 2u32 } else { 0 })];
+    while let Some((lo, hi, forced)) = ranges.pop() {
+        let farthest = (lo + 1..hi).map(|i| (i, distance_to_arc(vectors[i], vectors[lo], vectors[hi]))).max_by(|a, b| a.1.total_cmp(&b.1));
+        if let Some((i, distance)) = farthest {
+            if forced > 0 || distance > tolerance {
+                keep[i] = true;
+                ranges.push((lo, i, forced.saturating_sub(1)));
+                ranges.push((i, hi, forced.saturating_sub(1)));
+            }
+        }
+    }
+    points.iter().zip(keep).filter(|&(_, kept)| kept).map(|(&p, _)| p).collect()
+}
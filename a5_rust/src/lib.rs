@@ -1,10 +1,272 @@
-use a5;
+//! C ABI over the `a5` crate, called from the DuckDB extension in `src/`.
+//!
+//! # Thread safety
+//!
+//! Every exported function may be called from any number of threads at once,
+//! and gives the same result whatever other threads are doing. The only state
+//! outside a call is:
+//!
+//! - values computed once on first use and read-only afterwards, behind
+//!   `OnceLock`s: the resolution 0 cells, the version strings, and the `a5`
+//!   crate's origin and constant tables;
+//! - per-thread state: the last error of the `_simple` functions, and the
+//!   `a5` crate's projection cache, which each thread builds on its first
+//!   conversion and keeps until it exits;
+//! - with the `parallel` feature, rayon's global thread pool, which does its
+//!   own synchronization;
+//! - with the `boundary-cache` feature, the cache of cell boundaries behind a
+//!   mutex, which only ever holds the boundaries the uncached path computes.
+//!
+//! Memory returned to a caller belongs to that caller alone and may be freed
+//! from any thread. An `A5Arena` is not synchronized, so it must not be used
+//! by two threads at once; give each thread its own.
+//!
+//! # Array arguments
+//!
+//! An input array is a pointer and a length. A null pointer with length 0 is
+//! an empty array; with a nonzero length it is a caller bug and is rejected
+//! with `NullPointer`, or the function's documented failure value where it
+//! has no error code to return, instead of being read.
+
+// Exported functions take raw pointers from C callers and validate them before use,
+// so marking them `unsafe` would add nothing on the C side.
+#![allow(clippy::not_unsafe_ptr_arg_deref)]
+
+mod arrow;
+#[cfg(feature = "boundary-cache")]
+mod cache;
+mod dissolve;
+mod formats;
+mod geometry;
+mod polyfill;
+#[cfg(feature = "python")]
+mod python;
+mod tiles;
+#[cfg(feature = "wasm")]
+mod wasm;
+
+use a5::core::serialization::MAX_RESOLUTION;
+pub use arrow::{ArrowArray, ArrowSchema};
+pub use polyfill::FillMode;
+pub use tiles::Tile;
+use std::collections::HashSet;
 use std::ffi::{CStr, CString};
 
+/// Stable error category carried by every result struct, so callers can branch
+/// on the kind of failure without parsing the error string.
+#[repr(C)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum A5ErrorCode {
+    None = 0,
+    InvalidResolution,
+    InvalidCell,
+    InvalidCoordinate,
+    OutOfBounds,
+    Internal,
+    /// A caller-allocated buffer was too small; the required length was written out.
+    BufferTooSmall,
+    /// A cell id string held something other than hex digits.
+    NotHex,
+    /// A cell id string had the wrong number of hex digits.
+    WrongLength,
+    /// An input array pointer was null although its length was not 0, or a
+    /// required output pointer was null; nothing was written.
+    NullPointer,
+}
+
+/// An error with the code chosen where it was raised. Errors from the `a5`
+/// crate arrive as bare messages and get their code from `From<String>`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Error {
+    pub code: A5ErrorCode,
+    pub message: String,
+}
+
+impl Error {
+    pub fn new(code: A5ErrorCode, message: impl Into<String>) -> Self {
+        Self { code, message: message.into() }
+    }
+}
+
+impl From<String> for Error {
+    fn from(message: String) -> Self {
+        Self { code: upstream_code(&message), message }
+    }
+}
+
+impl From<Error> for String {
+    fn from(e: Error) -> Self {
+        e.message
+    }
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+/// Splits an error into its code and the C string handed to the caller.
+pub fn map_error(e: impl Into<Error>) -> (A5ErrorCode, Option<CString>) {
+    let e = e.into();
+    (e.code, CString::new(e.message).ok())
+}
+
+/// Guesses the code of a message from the `a5` crate, which reports every
+/// failure as a plain string. Errors raised here carry their code directly.
+fn upstream_code(e: &str) -> A5ErrorCode {
+    let lower = e.to_lowercase();
+    if lower.contains("invalid cell") || lower.contains("origin") || lower.contains("hex") || lower.starts_with("s (") {
+        A5ErrorCode::InvalidCell
+    } else if lower.contains("coordinate") || lower.contains("polygon") {
+        A5ErrorCode::InvalidCoordinate
+    } else if lower.contains("resolution") {
+        A5ErrorCode::InvalidResolution
+    } else if lower.contains("out of bounds") {
+        A5ErrorCode::OutOfBounds
+    } else {
+        A5ErrorCode::Internal
+    }
+}
+
+/// Views the C array `ptr` of `len` elements as a slice. A null `ptr` is an
+/// empty slice when `len` is 0, which `from_raw_parts` would not accept, and
+/// an error otherwise.
+fn input_slice<'a, T>(ptr: *const T, len: usize) -> Result<&'a [T], Error> {
+    match (ptr.is_null(), len) {
+        (_, 0) => Ok(&[]),
+        (true, _) => Err(Error::new(A5ErrorCode::NullPointer, format!("null pointer passed for an array of length {}", len))),
+        (false, _) => Ok(unsafe { std::slice::from_raw_parts(ptr, len) }),
+    }
+}
+
+/// `input_slice` for an array the function updates in place.
+fn input_slice_mut<'a, T>(ptr: *mut T, len: usize) -> Result<&'a mut [T], Error> {
+    match (ptr.is_null(), len) {
+        (_, 0) => Ok(&mut []),
+        (true, _) => Err(Error::new(A5ErrorCode::NullPointer, format!("null pointer passed for an array of length {}", len))),
+        (false, _) => Ok(unsafe { std::slice::from_raw_parts_mut(ptr, len) }),
+    }
+}
+
+fn string_ptr(message: Option<CString>) -> *mut std::os::raw::c_char {
+    message.map_or(std::ptr::null_mut(), CString::into_raw)
+}
+
+/// Value returned by an exported function when its body panics.
+pub trait PanicDefault {
+    fn from_panic(message: String) -> Self;
+}
+
+/// Runs the body of an exported function, turning a panic into the return
+/// type's panic default so that unwinding never crosses the C boundary.
+pub fn guard<T: PanicDefault>(f: impl FnOnce() -> T) -> T {
+    match std::panic::catch_unwind(std::panic::AssertUnwindSafe(f)) {
+        Ok(value) => value,
+        Err(payload) => {
+            let detail = if let Some(s) = payload.downcast_ref::<&str>() {
+                s.to_string()
+            } else if let Some(s) = payload.downcast_ref::<String>() {
+                s.clone()
+            } else {
+                "unknown panic".to_string()
+            };
+            T::from_panic(format!("panic: {}", detail))
+        }
+    }
+}
+
+impl PanicDefault for () {
+    fn from_panic(_message: String) -> Self {}
+}
+
+impl PanicDefault for A5ErrorCode {
+    fn from_panic(_message: String) -> Self {
+        A5ErrorCode::Internal
+    }
+}
+
+impl PanicDefault for bool {
+    fn from_panic(_message: String) -> Self {
+        false
+    }
+}
+
+impl PanicDefault for i32 {
+    fn from_panic(_message: String) -> Self {
+        -1
+    }
+}
+
+impl PanicDefault for u32 {
+    fn from_panic(_message: String) -> Self {
+        u32::MAX
+    }
+}
+
+impl PanicDefault for u64 {
+    fn from_panic(_message: String) -> Self {
+        0
+    }
+}
+
+impl PanicDefault for usize {
+    fn from_panic(_message: String) -> Self {
+        0
+    }
+}
+
+impl PanicDefault for f64 {
+    fn from_panic(_message: String) -> Self {
+        f64::NAN
+    }
+}
+
+impl<T> PanicDefault for Result<T, Error> {
+    fn from_panic(message: String) -> Self {
+        Err(Error::new(A5ErrorCode::Internal, message))
+    }
+}
+
+impl<T> PanicDefault for *mut T {
+    fn from_panic(_message: String) -> Self {
+        std::ptr::null_mut()
+    }
+}
+
 #[repr(C)]
 pub struct ResultU64 {
     pub value: u64,
     pub error: *mut std::os::raw::c_char, // null if no error
+    pub code: A5ErrorCode,
+}
+
+impl ResultU64 {
+    pub fn ok(value: u64) -> Self {
+        Self { value, error: std::ptr::null_mut(), code: A5ErrorCode::None }
+    }
+
+    pub fn err(code: A5ErrorCode, message: impl Into<String>) -> Self {
+        Self { value: 0, error: string_ptr(CString::new(message.into()).ok()), code }
+    }
+}
+
+impl<E: Into<Error>> From<Result<u64, E>> for ResultU64 {
+    fn from(result: Result<u64, E>) -> Self {
+        match result {
+            Ok(value) => Self::ok(value),
+            Err(e) => {
+                let (code, message) = map_error(e);
+                Self { value: 0, error: string_ptr(message), code }
+            }
+        }
+    }
+}
+
+impl PanicDefault for ResultU64 {
+    fn from_panic(message: String) -> Self {
+        Self::err(A5ErrorCode::Internal, message)
+    }
 }
 
 #[repr(C)]
@@ -12,6 +274,25 @@ pub struct ResultLonLat {
     pub longitude: f64,
     pub latitude: f64,
     pub error: *mut std::os::raw::c_char, // null if no error
+    pub code: A5ErrorCode,
+}
+
+impl<E: Into<Error>> From<Result<a5::LonLat, E>> for ResultLonLat {
+    fn from(result: Result<a5::LonLat, E>) -> Self {
+        match result {
+            Ok(lonlat) => Self { longitude: lonlat.longitude.get(), latitude: lonlat.latitude.get(), error: std::ptr::null_mut(), code: A5ErrorCode::None },
+            Err(e) => {
+                let (code, message) = map_error(e);
+                Self { longitude: 0.0, latitude: 0.0, error: string_ptr(message), code }
+            }
+        }
+    }
+}
+
+impl PanicDefault for ResultLonLat {
+    fn from_panic(message: String) -> Self {
+        Self { longitude: 0.0, latitude: 0.0, error: string_ptr(CString::new(message).ok()), code: A5ErrorCode::Internal }
+    }
 }
 
 #[repr(C)]
@@ -19,6 +300,96 @@ pub struct ResultSpherical {
     pub theta: f64,
     pub phi: f64,
     pub error: *mut std::os::raw::c_char,
+    pub code: A5ErrorCode,
+}
+
+impl PanicDefault for ResultSpherical {
+    fn from_panic(message: String) -> Self {
+        Self { theta: 0.0, phi: 0.0, error: string_ptr(CString::new(message).ok()), code: A5ErrorCode::Internal }
+    }
+}
+
+#[repr(C)]
+pub struct ResultF64 {
+    pub value: f64,
+    pub error: *mut std::os::raw::c_char, // null if no error
+    pub code: A5ErrorCode,
+}
+
+impl<E: Into<Error>> From<Result<f64, E>> for ResultF64 {
+    fn from(result: Result<f64, E>) -> Self {
+        match result {
+            Ok(value) => Self { value, error: std::ptr::null_mut(), code: A5ErrorCode::None },
+            Err(e) => {
+                let (code, message) = map_error(e);
+                Self { value: f64::NAN, error: string_ptr(message), code }
+            }
+        }
+    }
+}
+
+impl PanicDefault for ResultF64 {
+    fn from_panic(message: String) -> Self {
+        Self { value: f64::NAN, error: string_ptr(CString::new(message).ok()), code: A5ErrorCode::Internal }
+    }
+}
+
+/// Unit of an area returned by `a5_cell_area_in`.
+#[repr(C)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum AreaUnit {
+    SquareMeters = 0,
+    SquareKilometers,
+    Hectares,
+    SquareMiles,
+}
+
+impl AreaUnit {
+    /// Square meters in one of this unit.
+    pub fn square_meters(self) -> f64 {
+        match self {
+            AreaUnit::SquareMeters => 1.0,
+            AreaUnit::SquareKilometers => 1.0e6,
+            AreaUnit::Hectares => 1.0e4,
+            AreaUnit::SquareMiles => 2_589_988.110_336,
+        }
+    }
+}
+
+/// Axis-aligned bounding box of a cell, in degrees.
+///
+/// When `wraps` is true the box crosses the antimeridian and `min_lon` is
+/// greater than `max_lon`: it covers `[min_lon, 180]` and `[-180, max_lon]`.
+#[repr(C)]
+pub struct BBox {
+    pub min_lon: f64,
+    pub min_lat: f64,
+    pub max_lon: f64,
+    pub max_lat: f64,
+    pub wraps: bool,
+    pub error: *mut std::os::raw::c_char, // null if no error
+    pub code: A5ErrorCode,
+}
+
+impl BBox {
+    fn new(min_lon: f64, min_lat: f64, max_lon: f64, max_lat: f64, wraps: bool) -> Self {
+        Self { min_lon, min_lat, max_lon, max_lat, wraps, error: std::ptr::null_mut(), code: A5ErrorCode::None }
+    }
+}
+
+impl<E: Into<Error>> From<Result<BBox, E>> for BBox {
+    fn from(result: Result<BBox, E>) -> Self {
+        result.unwrap_or_else(|e| {
+            let (code, message) = map_error(e);
+            Self { error: string_ptr(message), code, ..Self::new(0.0, 0.0, 0.0, 0.0, false) }
+        })
+    }
+}
+
+impl PanicDefault for BBox {
+    fn from_panic(message: String) -> Self {
+        Self { error: string_ptr(CString::new(message).ok()), code: A5ErrorCode::Internal, ..Self::new(0.0, 0.0, 0.0, 0.0, false) }
+    }
 }
 
 #[repr(C)]
@@ -40,240 +411,6210 @@ impl CellBoundaryOptions {
 }
 
 
-#[no_mangle]
-pub extern "C" fn a5_lon_lat_to_cell(longitude: f64, latitude: f64, resolution: i32) -> ResultU64 {
-    match a5::lonlat_to_cell(a5::LonLat::new(longitude, latitude), resolution) {
-        Ok(cell) => ResultU64 { value: cell, error: std::ptr::null_mut() },
-        Err(e) => {
-            let err_msg = std::ffi::CString::new(e.to_string()).unwrap();
-            ResultU64 { value: 0, error: err_msg.into_raw() }
-        }
+/// Options for `a5_cell_to_boundary_opts`.
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct BoundaryOptions {
+    /// Number of segments to split each edge into; 0 uses the default for the cell's resolution
+    pub segments: u32,
+    pub closed_ring: bool,
+}
+
+impl From<BoundaryOptions> for a5::core::cell::CellToBoundaryOptions {
+    fn from(options: BoundaryOptions) -> Self {
+        Self { closed_ring: options.closed_ring, segments: if options.segments == 0 { None } else { Some(options.segments.min(i32::MAX as u32) as i32) } }
     }
 }
 
-#[no_mangle]
-pub extern "C" fn a5_cell_to_parent(index: u64, parent_resolution: i32) -> ResultU64 {
-    match a5::cell_to_parent(index, Some(parent_resolution)) {
-        Ok(cell) => ResultU64 { value: cell, error: std::ptr::null_mut() },
-        Err(e) => {
-            let err_msg = std::ffi::CString::new(e.to_string()).unwrap();
-            ResultU64 { value: 0, error: err_msg.into_raw() }
-        }
+/// Checks that `resolution` lies within the resolutions A5 can index at.
+fn validate_resolution(resolution: i32) -> Result<(), Error> {
+    if (0..=MAX_RESOLUTION).contains(&resolution) {
+        Ok(())
+    } else {
+        Err(Error::new(A5ErrorCode::InvalidResolution, format!("resolution {} out of range [0, {}]", resolution, MAX_RESOLUTION)))
     }
 }
 
+/// Version of the `a5` crate this library is built against; keep in step with
+/// the requirement in Cargo.toml.
+const A5_CRATE_VERSION: &str = "0.7.1";
 
+/// Returns a static string such as `a5_rust 0.1.0 (a5 0.7.1)` naming this
+/// library's version and the `a5` version beneath it. The string is owned by
+/// the library and must not be freed.
 #[no_mangle]
-pub extern "C" fn a5_cell_area(resolution: i32) -> f64 {
-    a5::cell_area(resolution)
+pub extern "C" fn a5_version() -> *const std::os::raw::c_char {
+    static VERSION: std::sync::OnceLock<CString> = std::sync::OnceLock::new();
+    VERSION.get_or_init(|| CString::new(format!("a5_rust {} (a5 {})", env!("CARGO_PKG_VERSION"), A5_CRATE_VERSION)).unwrap()).as_ptr()
 }
 
+/// Returns a static string describing the loaded binary's target and build
+/// profile, such as `x86_64-linux release`, to tell which library was loaded.
+/// The string is owned by the library and must not be freed.
 #[no_mangle]
-pub extern "C" fn a5_cell_to_lon_lat(cell: u64) -> ResultLonLat {
-    match a5::cell_to_lonlat(cell) {
-        Ok(lonlat) => ResultLonLat { longitude: lonlat.longitude.get(), latitude: lonlat.latitude.get(), error: std::ptr::null_mut() },
-        Err(e) => {
-            let err_msg = std::ffi::CString::new(e.to_string()).unwrap();
-            ResultLonLat { longitude: 0.0, latitude: 0.0, error: err_msg.into_raw() }
-        }
-    }
+pub extern "C" fn a5_build_info() -> *const std::os::raw::c_char {
+    static BUILD_INFO: std::sync::OnceLock<CString> = std::sync::OnceLock::new();
+    let profile = if cfg!(debug_assertions) { "debug" } else { "release" };
+    BUILD_INFO.get_or_init(|| CString::new(format!("{}-{} {}", std::env::consts::ARCH, std::env::consts::OS, profile)).unwrap()).as_ptr()
 }
 
+/// Version of this library's C ABI, the layout of its `#[repr(C)]` types and
+/// the signatures of its exported functions. It is bumped on any change that
+/// would make code compiled against an older header misbehave: a struct
+/// field added, removed, reordered or retyped, an enum value renumbered, or an
+/// exported function changing or losing parameters. Adding new functions,
+/// types or trailing enum values does not bump it.
+///
+/// Version 2 added the `arena` flag to `CellArray` and `LonLatDegreesArray`.
+pub const A5_ABI_VERSION: u32 = 2;
+
+/// Returns the `A5_ABI_VERSION` the library was built with. A binding should
+/// compare it with the `A5_ABI_VERSION` in the header it was compiled
+/// against when loading the library, and refuse to run on a mismatch rather
+/// than misread the library's structs.
 #[no_mangle]
-pub extern "C" fn a5_get_num_cells(resolution: i32) -> u64 {
-    a5::get_num_cells(resolution)
+pub extern "C" fn a5_abi_version() -> u32 {
+    A5_ABI_VERSION
 }
 
+/// Returns the finest resolution supported, so callers can validate up front.
 #[no_mangle]
-pub extern "C" fn a5_get_resolution(index: u64) -> i32 {
-    a5::get_resolution(index)
+pub extern "C" fn a5_max_resolution() -> i32 {
+    MAX_RESOLUTION
 }
 
-#[repr(C)]
-#[derive(Debug, Copy, Clone)]
-pub struct LonLatDegrees {
-    pub lon: f64,
-    pub lat: f64,
+/// Checks that a coordinate is finite with latitude in [-90, 90].
+fn validate_lonlat(lon: f64, lat: f64) -> Result<(), Error> {
+    if lon.is_finite() && lat.is_finite() && lat.abs() <= 90.0 {
+        Ok(())
+    } else {
+        Err(Error::new(A5ErrorCode::InvalidCoordinate, format!("invalid coordinate ({}, {}): must be finite with latitude in [-90, 90]", lon, lat)))
+    }
 }
 
-#[repr(C)]
-pub struct LonLatDegreesArray {
-    pub data: *mut LonLatDegrees,        // pointer to array of LonLatDegrees
-    pub len: usize,               // length of the array
-    pub error: *mut std::os::raw::c_char, // null if no error
+/// Validated `a5::lonlat_to_cell`, with the longitude wrapped into
+/// [-180, 180) first.
+fn lonlat_to_cell(lon: f64, lat: f64, resolution: i32) -> Result<u64, Error> {
+    validate_lonlat(lon, lat)?;
+    let lon = (lon + 180.0).rem_euclid(360.0) - 180.0;
+    a5::lonlat_to_cell(a5::LonLat::new(lon, lat), resolution).map_err(Error::from)
 }
 
-#[repr(C)]
-pub struct CellArray {
-    pub data: *mut u64,        // pointer to array of cell IDs
-    pub len: usize,               // length of the array
-    pub error: *mut std::os::raw::c_char, // null if no error
+/// Returns the cell containing the point at `resolution`.
+///
+/// Any finite longitude is accepted and wrapped into [-180, 180), so 540 is
+/// the same as 180. A non-finite value or a latitude outside [-90, 90] gives
+/// `InvalidCoordinate` instead of being passed on to the grid.
+#[no_mangle]
+pub extern "C" fn a5_lon_lat_to_cell(longitude: f64, latitude: f64, resolution: i32) -> ResultU64 {
+    guard(|| {
+        validate_resolution(resolution)
+            .and_then(|_| lonlat_to_cell(longitude, latitude, resolution))
+            .into()
+    })
 }
 
+/// `a5_lon_lat_to_cell` that also reports how far the point lies from the
+/// center of its cell: writes the cell to `out_cell` and the great-circle
+/// distance in meters from the point to the cell's center, as from
+/// `a5_cell_to_lon_lat`, to `out_offset_m`: the error made by replacing the
+/// point with its cell. Returns `NullPointer` if either out
+/// pointer is null.
+#[no_mangle]
+pub extern "C" fn a5_lon_lat_to_cell_with_error(longitude: f64, latitude: f64, resolution: i32, out_cell: *mut u64, out_offset_m: *mut f64) -> A5ErrorCode {
+    guard(|| {
+        if out_cell.is_null() || out_offset_m.is_null() {
+            return A5ErrorCode::NullPointer;
+        }
+        let snapped = validate_resolution(resolution).and_then(|_| lonlat_to_cell(longitude, latitude, resolution)).and_then(|cell| {
+            let center = a5::cell_to_lonlat(cell)?;
+            Ok((cell, geometry::haversine_distance(longitude, latitude, center.longitude(), center.latitude())))
+        });
+        match snapped {
+            Ok((cell, offset)) => {
+                unsafe {
+                    *out_cell = cell;
+                    *out_offset_m = offset;
+                }
+                A5ErrorCode::None
+            }
+            Err(e) => map_error(e).0,
+        }
+    })
+}
 
-pub fn vec_result_to_c(result: Result<Vec<a5::LonLat>, String>) -> LonLatDegreesArray {
-    match result {
-        Ok(vec) => {
-            let degree_vec: Vec<LonLatDegrees> = vec.into_iter().map(|ll| LonLatDegrees { lon: ll.longitude.get(), lat: ll.latitude.get() }).collect();
-            let len = degree_vec.len();
+/// Sentinel resolution meaning "use the default" where a function accepts one.
+pub const A5_DEFAULT_RESOLUTION: i32 = -1;
 
-            let mut boxed_slice = degree_vec.into_boxed_slice(); // heap allocation
-            let data_ptr = boxed_slice.as_mut_ptr();
-            std::mem::forget(boxed_slice); // prevent Rust from freeing it
-            LonLatDegreesArray { data: data_ptr, len, error: std::ptr::null_mut() }
+/// Cell ID written into batch outputs for rows that could not be converted.
+/// This is the world cell, which is never produced for a real resolution.
+pub const A5_INVALID_CELL: u64 = 0;
+
+/// Minimum rows per rayon task, so that small batches are not split into tasks
+/// cheaper than their scheduling.
+#[cfg(feature = "parallel")]
+const PARALLEL_MIN_CHUNK: usize = 1024;
+
+/// Maps `f` over `items`, spread across rayon's thread pool when the
+/// `parallel` feature is enabled. The output order always matches the input.
+fn batch_map<T: Sync, U: Send>(items: &[T], f: impl Fn(&T) -> U + Sync + Send) -> Vec<U> {
+    #[cfg(feature = "parallel")]
+    {
+        use rayon::prelude::*;
+        items.par_iter().with_min_len(PARALLEL_MIN_CHUNK).map(f).collect()
+    }
+    #[cfg(not(feature = "parallel"))]
+    {
+        items.iter().map(f).collect()
+    }
+}
+
+/// Reads row `i` of an Arrow-style validity bitmap (bit `i % 8` of byte
+/// `i / 8`). A null bitmap means every row is present.
+fn row_present(validity: *const u8, i: usize) -> bool {
+    validity.is_null() || unsafe { *validity.add(i / 8) } & (1 << (i % 8)) != 0
+}
+
+/// Like `batch_map`, but rows that `in_validity` marks null are mapped to
+/// `None` without calling `f`.
+fn masked_batch_map<T: Sync, U: Send>(items: &[T], in_validity: *const u8, f: impl Fn(&T) -> Option<U> + Sync + Send) -> Vec<Option<U>> {
+    let rows: Vec<(&T, bool)> = items.iter().enumerate().map(|(i, item)| (item, row_present(in_validity, i))).collect();
+    batch_map(&rows, |&(item, present)| if present { f(item) } else { None })
+}
+
+/// Writes `valid` into the Arrow-style bitmap `out_validity`, which must hold
+/// at least `valid.len().div_ceil(8)` bytes. Does nothing if it is null.
+fn write_validity(out_validity: *mut u8, valid: impl ExactSizeIterator<Item = bool>) {
+    if out_validity.is_null() {
+        return;
+    }
+    let bytes = unsafe { std::slice::from_raw_parts_mut(out_validity, valid.len().div_ceil(8)) };
+    bytes.fill(0);
+    for (i, _) in valid.enumerate().filter(|&(_, v)| v) {
+        bytes[i / 8] |= 1 << (i % 8);
+    }
+}
+
+/// Sets the number of threads used by the batch functions when the library is
+/// built with the `parallel` feature. Must be called before the first batch
+/// call; afterwards, or without the feature, it returns false and changes
+/// nothing. Without a call, rayon reads `RAYON_NUM_THREADS` and otherwise uses
+/// one thread per core.
+#[no_mangle]
+pub extern "C" fn a5_set_thread_pool_size(n: usize) -> bool {
+    guard(|| {
+        #[cfg(feature = "parallel")]
+        {
+            rayon::ThreadPoolBuilder::new().num_threads(n).build_global().is_ok()
         }
-        Err(e) => {
-            let c_str = CString::new(e).unwrap();
-            LonLatDegreesArray { data: std::ptr::null_mut(), len: 0, error: c_str.into_raw() }
+        #[cfg(not(feature = "parallel"))]
+        {
+            let _ = n;
+            false
         }
-    }
+    })
 }
 
-pub fn cell_vec_result_to_c(result: Result<Vec<u64>, String>) -> CellArray {
-    match result {
-        Ok(vec) => {
-            let mut boxed_slice = vec.into_boxed_slice(); // heap allocation
-            let data_ptr = boxed_slice.as_mut_ptr();
-            let len = boxed_slice.len();
-            std::mem::forget(boxed_slice); // prevent Rust from freeing it
-            CellArray { data: data_ptr, len, error: std::ptr::null_mut() }
+/// Sets the number of cell boundaries `a5_cell_to_boundary` keeps when the
+/// library is built with the `boundary-cache` feature, evicting the least
+/// recently used beyond it. The cache starts at 0, disabled, and setting 0
+/// again empties it and restores the uncached path. Without the feature it
+/// returns false and changes nothing.
+#[no_mangle]
+pub extern "C" fn a5_set_boundary_cache_size(n: usize) -> bool {
+    guard(|| {
+        #[cfg(feature = "boundary-cache")]
+        {
+            let mut cache = cache::boundaries();
+            cache.resize(n);
+            if n == 0 {
+                cache.clear();
+            }
+            true
         }
-        Err(e) => {
-            let c_str = CString::new(e).unwrap();
-            CellArray { data: std::ptr::null_mut(), len: 0, error: c_str.into_raw() }
+        #[cfg(not(feature = "boundary-cache"))]
+        {
+            let _ = n;
+            false
         }
-    }
+    })
 }
 
+/// Empties the boundary cache, keeping its size; see
+/// `a5_set_boundary_cache_size`. Without the `boundary-cache` feature it does
+/// nothing.
+#[no_mangle]
+pub extern "C" fn a5_clear_boundary_cache() {
+    guard(|| {
+        #[cfg(feature = "boundary-cache")]
+        cache::boundaries().clear();
+    })
+}
 
+/// Sets up the library's lazily initialized global state up front: the `a5`
+/// crate's lookup tables, the version strings and, with the `parallel`
+/// feature, rayon's global thread pool, so that the first real call does not
+/// pay for them. Call `a5_set_thread_pool_size` before this if at all.
+///
+/// Calling it is optional, as every function initializes what it needs on
+/// first use. It is idempotent and takes no locks of its own, so it is safe
+/// from a library constructor or `DllMain` wrapper. Returns `Internal` only
+/// if setting up fails.
 #[no_mangle]
-pub extern "C" fn a5_free_lonlatdegrees_array(arr: LonLatDegreesArray) {
-    if !arr.data.is_null() {
-        unsafe {
-            // reconstruct the boxed slice and drop it
-            let _ = Box::from_raw(std::slice::from_raw_parts_mut(arr.data, arr.len));
+pub extern "C" fn a5_init() -> A5ErrorCode {
+    guard(|| {
+        a5_version();
+        a5_build_info();
+        let _ = res0_cells();
+        #[cfg(feature = "parallel")]
+        rayon::current_num_threads();
+        // One conversion each way builds the crate's origin and projection tables
+        let warmed = a5::lonlat_to_cell(a5::LonLat::new(0.0, 0.0), 1).and_then(|cell| a5::cell_to_boundary(cell, None));
+        if warmed.is_ok() {
+            A5ErrorCode::None
+        } else {
+            A5ErrorCode::Internal
         }
-    }
-    if !arr.error.is_null() {
-        unsafe { drop(CString::from_raw(arr.error)); }
-    }
+    })
 }
 
+/// Frees the caches the library builds up, including the calling thread's
+/// last error and the boundary cache's entries, though not its size, so that
+/// leak checkers such as valgrind see a clean exit. It is
+/// idempotent, and the library stays usable afterwards, rebuilding caches on
+/// demand. Rayon's global pool, the resolution 0 cells and the `a5` crate's
+/// fixed tables live until the process exits, which leak checkers report as
+/// still reachable rather than lost.
 #[no_mangle]
-pub extern "C" fn a5_free_cell_array(arr: CellArray) {
-    if !arr.data.is_null() {
-        unsafe {
-            // reconstruct the boxed slice and drop it
-            let _ = Box::from_raw(std::slice::from_raw_parts_mut(arr.data, arr.len));
+pub extern "C" fn a5_shutdown() {
+    guard(|| {
+        LAST_ERROR.with(|last| *last.borrow_mut() = None);
+        #[cfg(feature = "boundary-cache")]
+        cache::boundaries().clear();
+    })
+}
+
+/// Converts `len` coordinates to cells at `resolution` in a single call.
+///
+/// A row that fails to convert does not abort the batch: its slot is set to
+/// `A5_INVALID_CELL` and conversion continues; coordinates are validated as
+/// for `a5_lon_lat_to_cell`. The returned array always has `len` entries and
+/// must be freed with `a5_free_cell_array_ptr`.
+///
+/// `in_validity` and `out_validity` are optional Arrow-style bitmaps of `len`
+/// bits (bit `i % 8` of byte `i / 8`, set for valid rows). Rows that
+/// `in_validity` marks null are skipped and come out as `A5_INVALID_CELL`;
+/// `out_validity` is cleared for those and for rows that failed to convert.
+#[no_mangle]
+pub extern "C" fn a5_lon_lat_to_cell_batch(coords: *const LonLatDegrees, len: usize, resolution: i32, in_validity: *const u8, out_validity: *mut u8) -> CellArray {
+    guard(|| {
+        if let Err(e) = validate_resolution(resolution) {
+            return cell_vec_result_to_c(Err(e));
         }
-    }
-    if !arr.error.is_null() {
-        unsafe { drop(CString::from_raw(arr.error)); }
-    }
+        let coord_slice = match input_slice(coords, len) {
+            Ok([]) => return CellArray::empty(),
+            Ok(coords) => coords,
+            Err(e) => return cell_vec_result_to_c(Err(e)),
+        };
+        let cells = masked_batch_map(coord_slice, in_validity, |c| lonlat_to_cell(c.lon, c.lat, resolution).ok());
+        write_validity(out_validity, cells.iter().map(Option::is_some));
+        cell_vec_result_to_c(Ok(cells.into_iter().map(|cell| cell.unwrap_or(A5_INVALID_CELL)).collect()))
+    })
 }
 
+/// A handle for indexing a stream of points at one resolution, created by
+/// `a5_indexer_new` and freed by `a5_indexer_free`. The resolution is
+/// validated once at creation rather than on every point. The `a5` crate
+/// already keeps its projection tables per thread and derives its
+/// per-resolution scale with a single multiplication, so there is no further
+/// setup to hoist: results match `a5_lon_lat_to_cell` exactly, at the same
+/// cost per point. An indexer is read-only and may be shared between threads.
+pub struct A5Indexer {
+    resolution: i32,
+}
+
+/// Returns an indexer for `resolution`, or null for an invalid resolution.
 #[no_mangle]
-pub extern "C" fn a5_cell_to_boundary(cell_id: u64, options: CellBoundaryOptions) -> LonLatDegreesArray {
-    vec_result_to_c(a5::cell_to_boundary(cell_id, Some(a5::core::cell::CellToBoundaryOptions { closed_ring: options.closed_ring, segments: options.segments() })))
+pub extern "C" fn a5_indexer_new(resolution: i32) -> *mut A5Indexer {
+    guard(|| match validate_resolution(resolution) {
+        Ok(()) => Box::into_raw(Box::new(A5Indexer { resolution })),
+        Err(_) => std::ptr::null_mut(),
+    })
 }
 
+/// Frees an indexer. A null `indexer` is ignored.
 #[no_mangle]
-pub extern "C" fn a5_cell_to_children(index: u64, child_resolution: i32) -> CellArray {
-    match child_resolution {
-        r if r >= 0 && r < 31 => {
-            cell_vec_result_to_c(a5::cell_to_children(index, Some(child_resolution)))
+pub extern "C" fn a5_indexer_free(indexer: *mut A5Indexer) {
+    guard(|| {
+        if !indexer.is_null() {
+            unsafe { drop(Box::from_raw(indexer)) };
         }
-        _ => cell_vec_result_to_c(a5::cell_to_children(index, None)),
-    }
+    })
 }
 
+/// The cell containing the point at the indexer's resolution, with the same
+/// result and errors as `a5_lon_lat_to_cell`. A null `indexer` gives
+/// `NullPointer`.
 #[no_mangle]
-pub extern "C" fn a5_get_res0_cells() -> CellArray {
-    cell_vec_result_to_c(a5::get_res0_cells())
+pub extern "C" fn a5_indexer_index(indexer: *const A5Indexer, longitude: f64, latitude: f64) -> ResultU64 {
+    guard(|| match unsafe { indexer.as_ref() } {
+        Some(indexer) => lonlat_to_cell(longitude, latitude, indexer.resolution).into(),
+        None => ResultU64::err(A5ErrorCode::NullPointer, "null pointer: indexer"),
+    })
 }
 
+/// `a5_lon_lat_to_cell_batch` at the indexer's resolution, without validity
+/// bitmaps: rows that fail come out as `A5_INVALID_CELL`. A null `indexer`
+/// gives `NullPointer`.
 #[no_mangle]
-pub extern "C" fn a5_compact(cells: *const u64, len: usize) -> CellArray {
-    if cells.is_null() || len == 0 {
-        return CellArray { data: std::ptr::null_mut(), len: 0, error: std::ptr::null_mut() };
-    }
-    let cell_slice = unsafe { std::slice::from_raw_parts(cells, len) };
-    cell_vec_result_to_c(a5::compact(cell_slice))
+pub extern "C" fn a5_indexer_index_batch(indexer: *const A5Indexer, coords: *const LonLatDegrees, len: usize) -> CellArray {
+    guard(|| match unsafe { indexer.as_ref() } {
+        Some(indexer) => a5_lon_lat_to_cell_batch(coords, len, indexer.resolution, std::ptr::null(), std::ptr::null_mut()),
+        None => CellArray::err(A5ErrorCode::NullPointer, "null pointer: indexer"),
+    })
 }
 
+/// Returns the ancestor of `index` at `parent_resolution`:
+///
+/// - a coarser resolution gives the ancestor there;
+/// - the cell's own resolution gives the cell itself, so a target computed
+///   as "at most this coarse" needs no special case;
+/// - a finer resolution fails with `InvalidResolution`.
+///
+/// A resolution outside [0, 30] is `InvalidResolution` and an invalid cell
+/// `InvalidCell`, rather than the `a5` crate's world cell.
 #[no_mangle]
-pub extern "C" fn a5_uncompact(cells: *const u64, len: usize, target_resolution: i32) -> CellArray {
-    if cells.is_null() || len == 0 {
-        return CellArray { data: std::ptr::null_mut(), len: 0, error: std::ptr::null_mut() };
-    }
-    let cell_slice = unsafe { std::slice::from_raw_parts(cells, len) };
-    cell_vec_result_to_c(a5::uncompact(cell_slice, target_resolution))
+pub extern "C" fn a5_cell_to_parent(index: u64, parent_resolution: i32) -> ResultU64 {
+    guard(|| cell_to_parent(index, parent_resolution).into())
 }
 
-#[no_mangle]
-pub extern "C" fn a5_free_string(ptr: *mut std::os::raw::c_char) {
-    if !ptr.is_null() {
-        unsafe { drop(CString::from_raw(ptr)); }
-    }
+/// `a5::cell_to_parent` after checking the cell and the resolution.
+fn cell_to_parent(cell: u64, resolution: i32) -> Result<u64, Error> {
+    validate_cell(cell)?;
+    validate_resolution(resolution)?;
+    Ok(a5::cell_to_parent(cell, Some(resolution))?)
 }
 
+/// Returns the parent of `cell` one resolution up. A resolution 0 cell has no
+/// parent and gives an `InvalidResolution` error rather than the world cell.
 #[no_mangle]
-pub extern "C" fn a5_hex_to_u64(hex: *const std::os::raw::c_char) -> ResultU64 {
-    if hex.is_null() {
-        let err_msg = CString::new("hex string is null").unwrap();
-        return ResultU64 { value: 0, error: err_msg.into_raw() };
-    }
-    let c_str = unsafe { CStr::from_ptr(hex) };
-    let hex_str = match c_str.to_str() {
-        Ok(s) => s,
-        Err(e) => {
-            let err_msg = CString::new(e.to_string()).unwrap();
-            return ResultU64 { value: 0, error: err_msg.into_raw() };
-        }
-    };
-    match a5::hex_to_u64(hex_str) {
-        Ok(value) => ResultU64 { value, error: std::ptr::null_mut() },
-        Err(e) => {
-            let err_msg = CString::new(e.to_string()).unwrap();
-            ResultU64 { value: 0, error: err_msg.into_raw() }
-        }
+pub extern "C" fn a5_cell_to_immediate_parent(cell: u64) -> ResultU64 {
+    guard(|| immediate_parent(cell).into())
+}
+
+fn immediate_parent(cell: u64) -> Result<u64, Error> {
+    validate_cell(cell)?;
+    match a5::get_resolution(cell) {
+        0 => Err(Error::new(A5ErrorCode::InvalidResolution, format!("resolution 0 cell {:016x} has no parent", cell))),
+        _ => a5::cell_to_parent(cell, None).map_err(Error::from),
     }
 }
 
+/// Rolls each of `len` cells up to its own target resolution in one call:
+/// row `i` becomes the ancestor of `cells[i]` at `resolutions[i]`, as from
+/// `a5_cell_to_parent`, or with a null `resolutions` its immediate parent, as
+/// from `a5_cell_to_immediate_parent`.
+///
+/// A row that fails, such as an invalid cell or a target finer than the
+/// cell, does not abort the batch: its slot is set to `A5_INVALID_CELL`. The
+/// optional `in_validity` and `out_validity` bitmaps work as for
+/// `a5_lon_lat_to_cell_batch`. The returned array always has `len` entries
+/// and must be freed with `a5_free_cell_array_ptr`.
 #[no_mangle]
-pub extern "C" fn a5_u64_to_hex(value: u64) -> *mut std::os::raw::c_char {
-    let hex = a5::u64_to_hex(value);
-    CString::new(hex).unwrap().into_raw()
+pub extern "C" fn a5_cell_to_parent_batch(cells: *const u64, resolutions: *const i32, len: usize, in_validity: *const u8, out_validity: *mut u8) -> CellArray {
+    guard(|| {
+        let cells = match input_slice(cells, len) {
+            Ok([]) => return CellArray::empty(),
+            Ok(cells) => cells,
+            Err(e) => return cell_vec_result_to_c(Err(e)),
+        };
+        let targets = if resolutions.is_null() { None } else { Some(unsafe { std::slice::from_raw_parts(resolutions, len) }) };
+        let rows: Vec<(u64, Option<i32>)> = cells.iter().enumerate().map(|(i, &cell)| (cell, targets.map(|t| t[i]))).collect();
+        let parents = masked_batch_map(&rows, in_validity, |&(cell, target)| {
+            match target {
+                Some(resolution) => cell_to_parent(cell, resolution),
+                None => immediate_parent(cell),
+            }
+            .ok()
+        });
+        write_validity(out_validity, parents.iter().map(Option::is_some));
+        cell_vec_result_to_c(Ok(parents.into_iter().map(|parent| parent.unwrap_or(A5_INVALID_CELL)).collect()))
+    })
 }
 
+/// Returns every ancestor of `cell`, from resolution 0 up to but excluding the
+/// cell's own resolution. A resolution 0 cell yields an empty array.
 #[no_mangle]
-pub extern "C" fn a5_get_num_children(parent_res: i32, child_res: i32) -> usize {
-    a5::get_num_children(parent_res, child_res)
+pub extern "C" fn a5_cell_to_parent_chain(cell: u64) -> CellArray {
+    guard(|| {
+        let chain = validate_cell(cell).and_then(|_| {
+            (0..a5::get_resolution(cell))
+                .map(|resolution| a5::cell_to_parent(cell, Some(resolution)).map_err(Error::from))
+                .collect()
+        });
+        cell_vec_result_to_c(chain)
+    })
 }
 
+/// Average area in square meters of a cell at `resolution`, or NaN if the
+/// resolution is out of range.
 #[no_mangle]
-pub extern "C" fn a5_cell_to_spherical(cell: u64) -> ResultSpherical {
-    match a5::cell_to_spherical(cell) {
-        Ok(sph) => ResultSpherical { theta: sph.theta.get(), phi: sph.phi.get(), error: std::ptr::null_mut() },
-        Err(e) => {
-            let err_msg = CString::new(e.to_string()).unwrap();
-            ResultSpherical { theta: 0.0, phi: 0.0, error: err_msg.into_raw() }
-        }
-    }
+pub extern "C" fn a5_cell_area(resolution: i32) -> f64 {
+    guard(|| validate_resolution(resolution).map_or(f64::NAN, |_| a5::cell_area(resolution)))
 }
 
+/// Area in square meters of this particular cell, on the authalic sphere.
+///
+/// Unlike `a5_cell_area`, which is the average for a resolution, this measures
+/// the cell's own boundary: the spherical polygon through its boundary vertices,
+/// split into triangles about the cell center, each measured by its spherical
+/// excess (see `geometry::spherical_polygon_area`).
 #[no_mangle]
-pub extern "C" fn a5_spherical_cap(cell_id: u64, radius: f64) -> CellArray {
-    cell_vec_result_to_c(a5::spherical_cap(cell_id, radius))
+pub extern "C" fn a5_cell_exact_area(cell: u64) -> ResultF64 {
+    guard(|| exact_area(cell).into())
+}
+
+fn exact_area(cell: u64) -> Result<f64, Error> {
+    validate_cell(cell)?;
+    let center = a5::cell_to_lonlat(cell)?;
+    let ring = boundary_points(cell)?;
+    Ok(geometry::spherical_polygon_area((center.longitude(), center.latitude()), &ring))
 }
 
+/// Writes the area-weighted centroid of `cell` to `out_lon` and `out_lat` and
+/// its area in square meters to `out_area_m2`, from one boundary computation.
+///
+/// The area is `a5_cell_exact_area`. The centroid is the center of mass of
+/// the same spherical polygon, which differs slightly from the point
+/// `a5_cell_to_lon_lat` returns: that is the cell's generator point, from
+/// which A5 projects the cell, and does not in general sit at its centroid.
+/// Returns `NullPointer` if any out pointer is null.
 #[no_mangle]
-pub extern "C" fn a5_grid_disk(cell_id: u64, k: usize) -> CellArray {
-    cell_vec_result_to_c(a5::grid_disk(cell_id, k))
+pub extern "C" fn a5_cell_to_center_and_area(cell: u64, out_lon: *mut f64, out_lat: *mut f64, out_area_m2: *mut f64) -> A5ErrorCode {
+    guard(|| {
+        if out_lon.is_null() || out_lat.is_null() || out_area_m2.is_null() {
+            return A5ErrorCode::NullPointer;
+        }
+        let measured = validate_cell(cell).and_then(|_| {
+            let center = a5::cell_to_lonlat(cell)?;
+            let ring = boundary_points(cell)?;
+            Ok((geometry::spherical_polygon_centroid(&ring), geometry::spherical_polygon_area((center.longitude(), center.latitude()), &ring)))
+        });
+        match measured {
+            Ok(((lon, lat), area)) => {
+                unsafe {
+                    *out_lon = lon;
+                    *out_lat = lat;
+                    *out_area_m2 = area;
+                }
+                A5ErrorCode::None
+            }
+            Err(e) => map_error(e).0,
+        }
+    })
 }
 
+/// Writes the center of `cell`, as from `a5_cell_to_lon_lat`, projected to
+/// Web Mercator (EPSG:3857) meters to `out_x` and `out_y`. Latitudes beyond
+/// about ±85.05°, where the projection ends, are clamped to that limit.
+/// Returns `NullPointer` if either out pointer is null.
 #[no_mangle]
-pub extern "C" fn a5_grid_disk_vertex(cell_id: u64, k: usize) -> CellArray {
-    cell_vec_result_to_c(a5::grid_disk_vertex(cell_id, k))
+pub extern "C" fn a5_cell_to_center_mercator(cell: u64, out_x: *mut f64, out_y: *mut f64) -> A5ErrorCode {
+    guard(|| {
+        if out_x.is_null() || out_y.is_null() {
+            return A5ErrorCode::NullPointer;
+        }
+        match validate_cell(cell).and_then(|_| a5::cell_to_lonlat(cell).map_err(Error::from)) {
+            Ok(center) => {
+                let (x, y) = geometry::web_mercator(center.longitude(), center.latitude());
+                unsafe {
+                    *out_x = x;
+                    *out_y = y;
+                }
+                A5ErrorCode::None
+            }
+            Err(e) => map_error(e).0,
+        }
+    })
+}
+
+/// Local frame of a cell: the tangent plane at its center and the bounding
+/// box of its boundary in that plane, see `a5_lon_lat_to_cell_local`.
+struct CellFrame {
+    plane: geometry::TangentPlane,
+    min: geometry::Point,
+    size: geometry::Point,
 }
 
+impl CellFrame {
+    fn new(cell: u64) -> Result<Self, Error> {
+        validate_cell(cell)?;
+        let center = a5::cell_to_lonlat(cell)?;
+        let plane = geometry::TangentPlane::new((center.longitude(), center.latitude()));
+        let (mut min, mut max) = ((f64::INFINITY, f64::INFINITY), (f64::NEG_INFINITY, f64::NEG_INFINITY));
+        for p in Self::traced_boundary(cell)? {
+            let (x, y) = plane.project(p).ok_or_else(|| Error::new(A5ErrorCode::Internal, format!("cell {:016x} is too large for a local frame", cell)))?;
+            min = (min.0.min(x), min.1.min(y));
+            max = (max.0.max(x), max.1.max(y));
+        }
+        Ok(Self { plane, min, size: (max.0 - min.0, max.1 - min.1) })
+    }
+
+    fn traced_boundary(cell: u64) -> Result<Vec<geometry::Point>, Error> {
+        let options = a5::core::cell::CellToBoundaryOptions { closed_ring: false, segments: Some(32) };
+        Ok(a5::cell_to_boundary(cell, Some(options))?.iter().map(|p| (p.longitude(), p.latitude())).collect())
+    }
+
+    fn to_local(&self, lon: f64, lat: f64) -> Option<geometry::Point> {
+        let (x, y) = self.plane.project((lon, lat))?;
+        Some(((x - self.min.0) / self.size.0, (y - self.min.1) / self.size.1))
+    }
+
+    fn to_lonlat(&self, (u, v): geometry::Point) -> geometry::Point {
+        self.plane.unproject((self.min.0 + u * self.size.0, self.min.1 + v * self.size.1))
+    }
+}
+
+/// Finds the cell containing the point, as `a5_lon_lat_to_cell`, and writes
+/// it to `out_cell` with the point's local coordinates in it to `out_u` and
+/// `out_v`.
+///
+/// Local coordinates come from the gnomonic projection onto the plane that
+/// touches the sphere at the cell's center, as from `a5_cell_to_lon_lat`,
+/// with x pointing east and y north; at a pole x points toward longitude 90
+/// and y toward longitude 180 in the north or 0 in the south. `u` and `v` are
+/// x and y rescaled so the bounding box of the cell's boundary, traced with
+/// 32 segments per edge to follow its curve, spans 0 to 1: `u = 0` is its
+/// west side, `u = 1` its east side, `v = 0` its south side and `v = 1` its
+/// north side. The pentagon only fills part of that box, so points in the
+/// cell lie in `[0, 1]` but not every such `(u, v)` is in the cell. The
+/// lookup occasionally assigns a point lying just past an edge to the cell,
+/// which can put `u` or `v` a few percent outside `[0, 1]`.
+/// `a5_cell_local_to_lon_lat` is the inverse.
+///
+/// Returns `NullPointer` if any out pointer is null.
+#[no_mangle]
+pub extern "C" fn a5_lon_lat_to_cell_local(longitude: f64, latitude: f64, resolution: i32, out_cell: *mut u64, out_u: *mut f64, out_v: *mut f64) -> A5ErrorCode {
+    guard(|| {
+        if out_cell.is_null() || out_u.is_null() || out_v.is_null() {
+            return A5ErrorCode::NullPointer;
+        }
+        let located = validate_resolution(resolution).and_then(|_| lonlat_to_cell(longitude, latitude, resolution)).and_then(|cell| {
+            let local = CellFrame::new(cell)?.to_local(longitude, latitude).ok_or_else(|| Error::new(A5ErrorCode::OutOfBounds, format!("point ({}, {}) is out of bounds for the frame of cell {:016x}", longitude, latitude, cell)))?;
+            Ok((cell, local))
+        });
+        match located {
+            Ok((cell, (u, v))) => {
+                unsafe {
+                    *out_cell = cell;
+                    *out_u = u;
+                    *out_v = v;
+                }
+                A5ErrorCode::None
+            }
+            Err(e) => map_error(e).0,
+        }
+    })
+}
+
+/// Writes the point at local coordinates `(u, v)` in `cell`, as defined by
+/// `a5_lon_lat_to_cell_local`, to `out_lon` and `out_lat`. Values outside
+/// `[0, 1]` extend the frame past the cell's bounding box. Longitudes are in
+/// [-180, 180]. Returns `NullPointer` if either out pointer is null.
+#[no_mangle]
+pub extern "C" fn a5_cell_local_to_lon_lat(cell: u64, u: f64, v: f64, out_lon: *mut f64, out_lat: *mut f64) -> A5ErrorCode {
+    guard(|| {
+        if out_lon.is_null() || out_lat.is_null() {
+            return A5ErrorCode::NullPointer;
+        }
+        if !u.is_finite() || !v.is_finite() {
+            return A5ErrorCode::InvalidCoordinate;
+        }
+        match CellFrame::new(cell) {
+            Ok(frame) => {
+                let (lon, lat) = frame.to_lonlat((u, v));
+                unsafe {
+                    *out_lon = lon;
+                    *out_lat = lat;
+                }
+                A5ErrorCode::None
+            }
+            Err(e) => map_error(e).0,
+        }
+    })
+}
+
+/// Returns the open boundary of `cell`, as from `a5_cell_to_boundary` with
+/// default options, projected to Web Mercator (EPSG:3857) meters: each
+/// entry's `lon` holds x and `lat` holds y. Vertices beyond about ±85.05°
+/// are clamped to that latitude, so a polar cell comes out cut off flat at
+/// the top or bottom of the map. Free with `a5_free_lonlatdegrees_array_ptr`.
+#[no_mangle]
+pub extern "C" fn a5_cell_to_boundary_mercator(cell: u64) -> LonLatDegreesArray {
+    guard(|| {
+        let projected = boundary_points(cell).map(|ring| {
+            ring.into_iter()
+                .map(|(lon, lat)| {
+                    let (x, y) = geometry::web_mercator(lon, lat);
+                    a5::LonLat::new(x, y)
+                })
+                .collect()
+        });
+        vec_result_to_c(projected)
+    })
+}
+
+/// Perimeter in meters of `cell`, on the authalic sphere.
+///
+/// A5 edges are straight on the dodecahedron face, not great circles, so they
+/// bow slightly on the sphere. Each edge is densified to 8 times the segment
+/// count `a5_cell_to_boundary` uses by default before the great-circle lengths
+/// of the pieces are summed, so the result does not underestimate the edges
+/// with chords and densifying further changes it negligibly.
+#[no_mangle]
+pub extern "C" fn a5_cell_perimeter(cell: u64) -> ResultF64 {
+    guard(|| {
+        validate_cell(cell)
+            .and_then(|_| {
+                let segments = 1 << ((6 - a5::get_resolution(cell)).max(0) + 3);
+                let options = a5::core::cell::CellToBoundaryOptions { closed_ring: true, segments: Some(segments) };
+                let ring = a5::cell_to_boundary(cell, Some(options))?;
+                Ok(ring.windows(2).map(|pair| geometry::haversine_distance(pair[0].longitude(), pair[0].latitude(), pair[1].longitude(), pair[1].latitude())).sum())
+            })
+            .into()
+    })
+}
+
+/// Average area of a cell at `resolution`, expressed in `unit`.
+#[no_mangle]
+pub extern "C" fn a5_cell_area_in(resolution: i32, unit: AreaUnit) -> ResultF64 {
+    guard(|| validate_resolution(resolution).map(|_| a5::cell_area(resolution) / unit.square_meters()).into())
+}
+
+/// Writes the average cell area in square meters, as from `a5_cell_area`,
+/// for resolutions 0 upward into `out`, index `i` holding resolution `i`,
+/// stopping at `max_len` entries or at `a5_max_resolution`. Returns the number
+/// written: 31 for a large enough buffer, 0 if `out` is null.
+#[no_mangle]
+pub extern "C" fn a5_cell_area_table(out: *mut f64, max_len: usize) -> usize {
+    guard(|| {
+        if out.is_null() {
+            return 0;
+        }
+        let len = max_len.min(MAX_RESOLUTION as usize + 1);
+        let out = unsafe { std::slice::from_raw_parts_mut(out, len) };
+        for (resolution, slot) in out.iter_mut().enumerate() {
+            *slot = a5::cell_area(resolution as i32);
+        }
+        len
+    })
+}
+
+/// Area of a regular pentagon with unit edges, `5 / (4 tan 36°)`.
+const PENTAGON_AREA_PER_SQUARE_EDGE: f64 = 1.720_477_400_588_966_6;
+
+/// Typical edge length in meters of a cell at `resolution`: the edge of a
+/// regular pentagon with the average area from `a5_cell_area`. Real cells
+/// are irregular, so their edges vary around it. NaN if the resolution is out
+/// of range.
+#[no_mangle]
+pub extern "C" fn a5_cell_edge_length(resolution: i32) -> f64 {
+    guard(|| (a5_cell_area(resolution) / PENTAGON_AREA_PER_SQUARE_EDGE).sqrt())
+}
+
+/// The resolution whose average cell area, as from `a5_cell_area`, is
+/// closest to `target_area_m2` by ratio, so 2 km² and 8 km² are equally far
+/// from 4 km². Returns -1 for a target that is not positive and finite, or
+/// lies beyond the resolutions: nearer by ratio to the whole sphere than to a
+/// resolution 0 cell, or to a quarter of a resolution 30 cell than to a whole
+/// one.
+#[no_mangle]
+pub extern "C" fn a5_resolution_for_area(target_area_m2: f64) -> i32 {
+    guard(|| {
+        if !(target_area_m2.is_finite() && target_area_m2 > 0.0) {
+            return -1;
+        }
+        let target = target_area_m2.ln();
+        let world = (12.0 * a5::cell_area(0)).ln();
+        let beyond = (a5::cell_area(30) / 4.0).ln();
+        let (resolution, distance) = (0..=30).map(|r| (r, (a5::cell_area(r).ln() - target).abs())).min_by(|a, b| a.1.total_cmp(&b.1)).unwrap();
+        if (world - target).abs() < distance || (beyond - target).abs() < distance {
+            return -1;
+        }
+        resolution
+    })
+}
+
+/// The resolution whose typical edge length, as from `a5_cell_edge_length`,
+/// is closest to `target_edge_m` by ratio. Returns -1 in the same cases as
+/// `a5_resolution_for_area`.
+#[no_mangle]
+pub extern "C" fn a5_resolution_for_edge_length(target_edge_m: f64) -> i32 {
+    guard(|| if target_edge_m > 0.0 { a5_resolution_for_area(target_edge_m * target_edge_m * PENTAGON_AREA_PER_SQUARE_EDGE) } else { -1 })
+}
+
+/// Center of `cell` for the batch functions, which, unlike
+/// `a5_cell_to_lon_lat`, reject the world cell `A5_INVALID_CELL` and other
+/// invalid ids so failed rows of `a5_lon_lat_to_cell_batch` stay invalid.
+fn valid_center(cell: u64) -> Option<a5::LonLat> {
+    validate_cell(cell).and_then(|_| a5::cell_to_lonlat(cell).map_err(Error::from)).ok()
+}
+
+#[no_mangle]
+pub extern "C" fn a5_cell_to_lon_lat(cell: u64) -> ResultLonLat {
+    guard(|| a5::cell_to_lonlat(cell).into())
+}
+
+/// Converts `len` cells to their center coordinates in a single call.
+///
+/// Invalid cells, including `A5_INVALID_CELL` from a failed row of
+/// `a5_lon_lat_to_cell_batch`, do not abort the batch: their slot is set to
+/// NaN for both `lon` and `lat`. The returned array always has `len` entries and must be
+/// freed with `a5_free_lonlatdegrees_array_ptr`.
+///
+/// `in_validity` and `out_validity` are optional validity bitmaps, as for
+/// `a5_lon_lat_to_cell_batch`; null input rows come out as NaN.
+#[no_mangle]
+pub extern "C" fn a5_cell_to_lon_lat_batch(cells: *const u64, len: usize, in_validity: *const u8, out_validity: *mut u8) -> LonLatDegreesArray {
+    guard(|| {
+        let cell_slice = match input_slice(cells, len) {
+            Ok([]) => return LonLatDegreesArray::empty(),
+            Ok(cells) => cells,
+            Err(e) => return vec_result_to_c(Err(e)),
+        };
+        let centers = masked_batch_map(cell_slice, in_validity, |&cell| valid_center(cell));
+        write_validity(out_validity, centers.iter().map(Option::is_some));
+        vec_result_to_c(Ok(centers.into_iter().map(|c| c.unwrap_or(a5::LonLat::new(f64::NAN, f64::NAN))).collect()))
+    })
+}
+
+/// Exports the centers of `len` cells through the Arrow C Data Interface as a
+/// nullable Struct array `{lon: float64, lat: float64}`, with invalid cells
+/// marked null in the struct's validity bitmap. Rows that the optional
+/// `in_validity` bitmap marks null are skipped and come out null as well.
+///
+/// `out_array` and `out_schema` must point to uninitialized structs, which the
+/// consumer then owns: each must be freed by calling its own `release`
+/// callback. Returns `NullPointer` without touching them if either pointer
+/// is null.
+#[no_mangle]
+pub extern "C" fn a5_cell_to_lon_lat_arrow(cells: *const u64, len: usize, in_validity: *const u8, out_array: *mut ArrowArray, out_schema: *mut ArrowSchema) -> A5ErrorCode {
+    guard(|| {
+        if out_array.is_null() || out_schema.is_null() {
+            return A5ErrorCode::NullPointer;
+        }
+        let cell_slice = match input_slice(cells, len) {
+            Ok(cells) => cells,
+            Err(e) => return map_error(e).0,
+        };
+        let centers = masked_batch_map(cell_slice, in_validity, |&cell| valid_center(cell));
+        let valid: Vec<bool> = centers.iter().map(Option::is_some).collect();
+        let lon = centers.iter().map(|c| c.map_or(f64::NAN, |c| c.longitude())).collect();
+        let lat = centers.iter().map(|c| c.map_or(f64::NAN, |c| c.latitude())).collect();
+
+        let schema = arrow::schema("+s", "", arrow::ARROW_FLAG_NULLABLE, vec![arrow::schema("g", "lon", 0, Vec::new()), arrow::schema("g", "lat", 0, Vec::new())]);
+        let array = arrow::struct_array(&valid, vec![arrow::f64_array(lon), arrow::f64_array(lat)]);
+        unsafe {
+            out_schema.write(schema);
+            out_array.write(array);
+        }
+        A5ErrorCode::None
+    })
+}
+
+/// Exports the boundaries of `len` cells through the Arrow C Data Interface as
+/// one nullable `List<Struct<lon: float64, lat: float64>>` array: row `i` is
+/// the open vertex ring of `cells[i]`, as from `a5_cell_to_boundary_oriented`
+/// wound counter-clockwise, and invalid cells are null rows. `out_array` and
+/// `out_schema` work as for `a5_cell_to_lon_lat_arrow`. A batch with more
+/// vertices than 32-bit list offsets can address gives `OutOfBounds`.
+#[no_mangle]
+pub extern "C" fn a5_cells_to_boundaries_arrow(cells: *const u64, len: usize, out_array: *mut ArrowArray, out_schema: *mut ArrowSchema) -> A5ErrorCode {
+    guard(|| {
+        if out_array.is_null() || out_schema.is_null() {
+            return A5ErrorCode::NullPointer;
+        }
+        let cell_slice = match input_slice(cells, len) {
+            Ok(cells) => cells,
+            Err(e) => return map_error(e).0,
+        };
+        let rings = batch_map(cell_slice, |&cell| oriented_boundary(cell, true).ok());
+        let (mut offsets, mut lon, mut lat) = (vec![0i32], Vec::new(), Vec::new());
+        for ring in rings.iter().flatten() {
+            lon.extend(ring.iter().map(|p| p.0));
+            lat.extend(ring.iter().map(|p| p.1));
+        }
+        if i32::try_from(lon.len()).is_err() {
+            return A5ErrorCode::OutOfBounds;
+        }
+        for ring in &rings {
+            offsets.push(offsets[offsets.len() - 1] + ring.as_ref().map_or(0, |ring| ring.len() as i32));
+        }
+        let valid: Vec<bool> = rings.iter().map(Option::is_some).collect();
+
+        let vertex = |name| arrow::schema("+s", name, 0, vec![arrow::schema("g", "lon", 0, Vec::new()), arrow::schema("g", "lat", 0, Vec::new())]);
+        let schema = arrow::schema("+l", "", arrow::ARROW_FLAG_NULLABLE, vec![vertex("item")]);
+        let vertices = arrow::struct_array(&vec![true; lon.len()], vec![arrow::f64_array(lon), arrow::f64_array(lat)]);
+        let array = arrow::list_array(&valid, offsets, vertices);
+        unsafe {
+            out_schema.write(schema);
+            out_array.write(array);
+        }
+        A5ErrorCode::None
+    })
+}
+
+/// Structure-of-arrays variant of `a5_cell_to_lon_lat_batch` that writes into
+/// caller-allocated buffers, so nothing needs to be freed afterwards.
+///
+/// `out_lon` and `out_lat` must each hold `len` values. `out_valid` is optional;
+/// when non-null it must hold `len` flags and is set to false for invalid cells.
+/// Rows that the optional `in_validity` bitmap marks null are skipped and
+/// treated as invalid. Invalid rows are written as NaN. Returns the number of
+/// rows that converted.
+#[no_mangle]
+pub extern "C" fn a5_cell_to_lon_lat_batch_soa(cells: *const u64, len: usize, in_validity: *const u8, out_lon: *mut f64, out_lat: *mut f64, out_valid: *mut bool) -> usize {
+    guard(|| {
+        let Ok(cell_slice) = input_slice(cells, len) else {
+            return 0;
+        };
+        if out_lon.is_null() || out_lat.is_null() || len == 0 {
+            return 0;
+        }
+        let lon_slice = unsafe { std::slice::from_raw_parts_mut(out_lon, len) };
+        let lat_slice = unsafe { std::slice::from_raw_parts_mut(out_lat, len) };
+        let mut valid_slice = if out_valid.is_null() { None } else { Some(unsafe { std::slice::from_raw_parts_mut(out_valid, len) }) };
+
+        let mut converted = 0;
+        for (i, &cell) in cell_slice.iter().enumerate() {
+            let center = if row_present(in_validity, i) { valid_center(cell) } else { None };
+            let ok = match center {
+                Some(lonlat) => {
+                    lon_slice[i] = lonlat.longitude.get();
+                    lat_slice[i] = lonlat.latitude.get();
+                    converted += 1;
+                    true
+                }
+                None => {
+                    lon_slice[i] = f64::NAN;
+                    lat_slice[i] = f64::NAN;
+                    false
+                }
+            };
+            if let Some(valid) = valid_slice.as_mut() {
+                valid[i] = ok;
+            }
+        }
+        converted
+    })
+}
+
+#[no_mangle]
+pub extern "C" fn a5_get_num_cells(resolution: i32) -> u64 {
+    guard(|| a5::get_num_cells(resolution))
+}
+
+/// Returns true if `cell` is a canonically encoded A5 cell.
+///
+/// The value must decode to a valid origin and resolution, and re-encoding the
+/// decoded cell must reproduce it exactly, which rejects stray bits outside the
+/// fields used by its resolution. The world cell (0) is not a valid cell.
+#[no_mangle]
+pub extern "C" fn a5_is_valid_cell(cell: u64) -> bool {
+    guard(|| {
+        if a5::get_resolution(cell) < 0 {
+            return false;
+        }
+        match a5::core::serialization::deserialize(cell) {
+            Ok(decoded) => a5::core::serialization::serialize(&decoded) == Ok(cell),
+            Err(_) => false,
+        }
+    })
+}
+
+/// Checks that `cell` is a valid cell, see `a5_is_valid_cell`.
+fn validate_cell(cell: u64) -> Result<(), Error> {
+    if a5_is_valid_cell(cell) {
+        Ok(())
+    } else {
+        Err(Error::new(A5ErrorCode::InvalidCell, format!("invalid cell {:016x}", cell)))
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn a5_get_resolution(index: u64) -> i32 {
+    guard(|| a5::get_resolution(index))
+}
+
+/// Locates the top field of a valid cell id, returning the bit it starts at
+/// and its value, see `a5_cell_get_digit` for the layout.
+fn top_field(cell: u64) -> Result<(u32, u64), Error> {
+    validate_cell(cell)?;
+    if a5::get_resolution(cell) < 30 {
+        return Ok((58, cell >> 58));
+    }
+    Ok(match cell.trailing_zeros() {
+        0 => (59, cell >> 59),
+        2 => (61, (cell >> 61) + 32),
+        _ => (63, (cell >> 63) + 40),
+    })
+}
+
+/// The resolution of `cell` as encoded by the position of its marker bit, see
+/// `a5_cell_get_digit`. Returns `u32::MAX` for an invalid cell.
+#[no_mangle]
+pub extern "C" fn a5_cell_get_resolution_bits(cell: u64) -> u32 {
+    guard(|| match validate_cell(cell) {
+        Ok(()) => a5::get_resolution(cell) as u32,
+        Err(_) => u32::MAX,
+    })
+}
+
+/// The face (0 to 11) of `cell`, the resolution 0 cell it lies in. Returns
+/// `u32::MAX` for an invalid cell.
+#[no_mangle]
+pub extern "C" fn a5_cell_get_base(cell: u64) -> u32 {
+    guard(|| match top_field(cell) {
+        Ok((_, top)) if a5::get_resolution(cell) == 0 => top as u32,
+        Ok((_, top)) => (top / 5) as u32,
+        Err(_) => u32::MAX,
+    })
+}
+
+/// The digit choosing the subdivision of `cell`'s ancestor at resolution
+/// `level - 1` that leads to `cell`, for `level` from 1 to the cell's
+/// resolution: at level 1 the quintant (0 to 4) of the face, and from level 2
+/// the Hilbert digit (0 to 3) at that resolution. Digits are read from the id
+/// bits, so they come in the id's own order: the quintant is numbered from the
+/// face's first quintant, and Hilbert digits follow the curve rather than
+/// fixed positions. Together with `a5_cell_get_base` and the resolution they
+/// determine the cell; the layout is described below.
+///
+/// A cell at resolution `r` is, from the most significant bit, a 6-bit top
+/// field, then the Hilbert digits for levels 2 to `r` at 2 bits each, then a
+/// single 1 bit marking the resolution, then zeros. The top field is the face
+/// at resolution 0 and `5 * face + quintant` above it. The marker is bit 57 at
+/// resolution 0, bit 56 at resolution 1 and bit `59 - 2 * r` above that, so
+/// resolution 1 leaves bit 57 clear. Resolution 30 has no
+/// room for the full marker, so its top field shrinks: a marker of `...1`
+/// follows a 5-bit field holding 0 to 31, `...100` a 3-bit field holding the
+/// value minus 32, and `...10000` a 1-bit field holding the value minus 40.
+///
+/// Returns -1 for an invalid cell or a level outside 1 to its resolution.
+#[no_mangle]
+pub extern "C" fn a5_cell_get_digit(cell: u64, level: i32) -> i32 {
+    guard(|| match top_field(cell) {
+        Ok(_) if level < 1 || level > a5::get_resolution(cell) => -1,
+        Ok((_, top)) if level == 1 => (top % 5) as i32,
+        Ok((shift, _)) => ((cell >> (shift - 2 * (level as u32 - 1))) & 3) as i32,
+        Err(_) => -1,
+    })
+}
+
+/// Returns true if `descendant` lies strictly within `ancestor`'s subtree, so
+/// that taking parents of `descendant` reaches `ancestor`. The test compares
+/// the face, quintant and Hilbert digits of `ancestor`'s levels read from the
+/// ids, see `a5_cell_get_digit`, without enumerating children. A cell is not
+/// its own ancestor, so equal cells give false, as do a `descendant` at the
+/// same or a coarser resolution than `ancestor` and invalid cells.
+#[no_mangle]
+pub extern "C" fn a5_is_ancestor_of(ancestor: u64, descendant: u64) -> bool {
+    guard(|| {
+        let (Ok((shift, top)), Ok((inner_shift, inner_top))) = (top_field(ancestor), top_field(descendant)) else {
+            return false;
+        };
+        let resolution = a5::get_resolution(ancestor);
+        if resolution >= a5::get_resolution(descendant) {
+            return false;
+        }
+        if resolution == 0 {
+            return inner_top / 5 == top;
+        }
+        let bits = 2 * (resolution as u32 - 1);
+        let digits = |cell: u64, shift: u32| if bits == 0 { 0 } else { (cell >> (shift - bits)) & ((1 << bits) - 1) };
+        inner_top == top && digits(descendant, inner_shift) == digits(ancestor, shift)
+    })
+}
+
+/// Whether the point lies in `cell`. Membership is decided by the same lookup
+/// as `a5_lon_lat_to_cell`, so a point on a shared edge or vertex belongs to
+/// exactly one cell. Returns false for an invalid cell or coordinate.
+#[no_mangle]
+pub extern "C" fn a5_cell_contains_point(cell: u64, longitude: f64, latitude: f64) -> bool {
+    guard(|| {
+        validate_cell(cell).is_ok() && lonlat_to_cell(longitude, latitude, a5::get_resolution(cell)).is_ok_and(|found| found == cell)
+    })
+}
+
+/// SplitMix64, a small seedable generator; sequences are fixed by the seed on
+/// every platform, which is what reproducible fixtures need.
+struct SplitMix64(u64);
+
+impl SplitMix64 {
+    fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// Uniform in [0, 1).
+    fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+}
+
+/// Returns a random valid cell at `resolution`, the same one for the same
+/// `seed`. Every cell is equally likely, and as A5 cells have equal area this
+/// is also uniform over the sphere. Returns `A5_INVALID_CELL` for an invalid
+/// resolution.
+///
+/// At resolution 30 the A5 encoding has no room for cells in the last twenty
+/// quintants, so only cells from the first forty are returned there.
+#[no_mangle]
+pub extern "C" fn a5_random_cell(resolution: i32, seed: u64) -> u64 {
+    guard(|| {
+        use a5::core::origin::{get_origins, quintant_to_segment};
+        if validate_resolution(resolution).is_err() {
+            return A5_INVALID_CELL;
+        }
+        let mut rng = SplitMix64(seed);
+        let origins = get_origins();
+        // Redraw ids that do not encode a cell at this resolution, see above
+        loop {
+            let origin_id = (rng.next_u64() % origins.len() as u64) as u8;
+            let cell = if resolution == 0 {
+                a5::A5Cell { origin_id, segment: 0, s: 0, resolution }
+            } else {
+                let (segment, _) = quintant_to_segment((rng.next_u64() % 5) as usize, &origins[origin_id as usize]);
+                // 4^(resolution - 1) Hilbert indices per quintant, which at most fills 58 bits
+                let s = if resolution < 2 { 0 } else { rng.next_u64() & ((1u64 << (2 * (resolution - 1))) - 1) };
+                a5::A5Cell { origin_id, segment, s, resolution }
+            };
+            if let Ok(cell) = a5::core::serialization::serialize(&cell) {
+                if a5::get_resolution(cell) == resolution && a5_is_valid_cell(cell) {
+                    return cell;
+                }
+            }
+        }
+    })
+}
+
+/// Most bounding box samples `a5_sample_point_in_cell` draws before giving up.
+const SAMPLE_ATTEMPTS: usize = 10_000;
+
+/// Writes a random point inside `cell` to `out_lon` and `out_lat`, the same
+/// one for the same `seed`; it always satisfies `a5_cell_contains_point`.
+///
+/// Points are drawn uniformly by area over the cell's bounding box and
+/// rejected until one lands in the cell, so the accepted point is uniform
+/// over the cell too. A cell fills well over a third of its box, so a few
+/// draws usually suffice. Returns `NullPointer` if either out pointer is
+/// null.
+#[no_mangle]
+pub extern "C" fn a5_sample_point_in_cell(cell: u64, seed: u64, out_lon: *mut f64, out_lat: *mut f64) -> A5ErrorCode {
+    guard(|| {
+        if out_lon.is_null() || out_lat.is_null() {
+            return A5ErrorCode::NullPointer;
+        }
+        let bbox = match cell_bbox(cell) {
+            Ok(bbox) => bbox,
+            Err(e) => return map_error(e).0,
+        };
+        let max_lon = if bbox.wraps { bbox.max_lon + 360.0 } else { bbox.max_lon };
+        let (sin_min, sin_max) = (bbox.min_lat.to_radians().sin(), bbox.max_lat.to_radians().sin());
+        let mut rng = SplitMix64(seed);
+        for _ in 0..SAMPLE_ATTEMPTS {
+            let lon = bbox.min_lon + rng.next_f64() * (max_lon - bbox.min_lon);
+            let lon = (lon + 180.0).rem_euclid(360.0) - 180.0;
+            let lat = (sin_min + rng.next_f64() * (sin_max - sin_min)).asin().to_degrees();
+            if a5_cell_contains_point(cell, lon, lat) {
+                unsafe {
+                    *out_lon = lon;
+                    *out_lat = lat;
+                }
+                return A5ErrorCode::None;
+            }
+        }
+        A5ErrorCode::Internal
+    })
+}
+
+/// Returns the resolution of `index`, or an `InvalidCell` error if it is not a
+/// valid cell, so that resolution 0 can be told apart from bad input.
+#[no_mangle]
+pub extern "C" fn a5_get_resolution_checked(index: u64) -> ResultU64 {
+    guard(|| validate_cell(index).map(|_| a5::get_resolution(index) as u64).into())
+}
+
+// The `_simple` functions below report failures through a per-thread last
+// error instead of an error string in the returned struct, in the style of
+// sqlite3_errmsg: they return only the error code, write their value through
+// an out pointer, and leave the detail for `a5_last_error_message`. Returning
+// nothing by value but an int-sized code, they map directly onto marshallers
+// such as .NET P/Invoke `[Out]` parameters and cgo.
+
+thread_local! {
+    static LAST_ERROR: std::cell::RefCell<Option<CString>> = const { std::cell::RefCell::new(None) };
+}
+
+/// Records the outcome of a `_simple` call on the calling thread, writing the
+/// value to `out` on success. A success clears the last error without
+/// allocating. A null `out` fails with `NullPointer`.
+fn finish_simple<T>(result: Result<T, Error>, out: *mut T) -> A5ErrorCode {
+    let (code, message) = match result {
+        _ if out.is_null() => (A5ErrorCode::NullPointer, CString::new("null pointer: output").ok()),
+        Ok(value) => {
+            unsafe { *out = value };
+            (A5ErrorCode::None, None)
+        }
+        Err(e) => map_error(e),
+    };
+    LAST_ERROR.with(|last| *last.borrow_mut() = message);
+    code
+}
+
+/// Returns the error message of the last failed `_simple` call on the calling
+/// thread, or null if that thread's last `_simple` call succeeded or it has
+/// made none. The string is owned by the library and stays valid until the
+/// thread's next `_simple` call; do not free it.
+#[no_mangle]
+pub extern "C" fn a5_last_error_message() -> *const std::os::raw::c_char {
+    LAST_ERROR.with(|last| last.borrow().as_ref().map_or(std::ptr::null(), |message| message.as_ptr()))
+}
+
+/// `a5_lon_lat_to_cell` writing the cell to `out`; see `a5_last_error_message`.
+#[no_mangle]
+pub extern "C" fn a5_lon_lat_to_cell_simple(longitude: f64, latitude: f64, resolution: i32, out: *mut u64) -> A5ErrorCode {
+    finish_simple(guard(|| validate_resolution(resolution).and_then(|_| lonlat_to_cell(longitude, latitude, resolution))), out)
+}
+
+/// `a5_cell_to_lon_lat` writing the center to `out`; see `a5_last_error_message`.
+#[no_mangle]
+pub extern "C" fn a5_cell_to_lon_lat_simple(cell: u64, out: *mut LonLatDegrees) -> A5ErrorCode {
+    let center = guard(|| a5::cell_to_lonlat(cell).map(|lonlat| LonLatDegrees { lon: lonlat.longitude(), lat: lonlat.latitude() }).map_err(Error::from));
+    finish_simple(center, out)
+}
+
+/// `a5_cell_to_lon_lat_simple` with the center split into `out_lon` and
+/// `out_lat`, for callers that cannot pass a struct; see
+/// `a5_last_error_message`.
+#[no_mangle]
+pub extern "C" fn a5_cell_to_lon_lat_out(cell: u64, out_lon: *mut f64, out_lat: *mut f64) -> A5ErrorCode {
+    let mut center = LonLatDegrees { lon: f64::NAN, lat: f64::NAN };
+    let out = if out_lon.is_null() || out_lat.is_null() { std::ptr::null_mut() } else { &mut center as *mut LonLatDegrees };
+    let code = a5_cell_to_lon_lat_simple(cell, out);
+    if code == A5ErrorCode::None {
+        unsafe {
+            *out_lon = center.lon;
+            *out_lat = center.lat;
+        }
+    }
+    code
+}
+
+/// `a5_cell_to_parent` writing the parent to `out`; see `a5_last_error_message`.
+#[no_mangle]
+pub extern "C" fn a5_cell_to_parent_simple(cell: u64, parent_resolution: i32, out: *mut u64) -> A5ErrorCode {
+    finish_simple(guard(|| cell_to_parent(cell, parent_resolution)), out)
+}
+
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct LonLatDegrees {
+    pub lon: f64,
+    pub lat: f64,
+}
+
+#[repr(C)]
+pub struct LonLatDegreesArray {
+    pub data: *mut LonLatDegrees,        // pointer to array of LonLatDegrees
+    pub len: usize,               // length of the array
+    pub error: *mut std::os::raw::c_char, // null if no error
+    pub code: A5ErrorCode,
+    pub arena: bool,              // owned by an A5Arena, see a5_arena_new
+}
+
+impl LonLatDegreesArray {
+    pub fn empty() -> Self {
+        Self { data: std::ptr::null_mut(), len: 0, error: std::ptr::null_mut(), code: A5ErrorCode::None, arena: false }
+    }
+
+    pub fn err(code: A5ErrorCode, message: impl Into<String>) -> Self {
+        Self { data: std::ptr::null_mut(), len: 0, error: string_ptr(CString::new(message.into()).ok()), code, arena: false }
+    }
+}
+
+impl PanicDefault for LonLatDegreesArray {
+    fn from_panic(message: String) -> Self {
+        Self::err(A5ErrorCode::Internal, message)
+    }
+}
+
+#[repr(C)]
+pub struct CellArray {
+    pub data: *mut u64,        // pointer to array of cell IDs
+    pub len: usize,               // length of the array
+    pub error: *mut std::os::raw::c_char, // null if no error
+    pub code: A5ErrorCode,
+    pub arena: bool,              // owned by an A5Arena, see a5_arena_new
+}
+
+impl CellArray {
+    pub fn empty() -> Self {
+        Self { data: std::ptr::null_mut(), len: 0, error: std::ptr::null_mut(), code: A5ErrorCode::None, arena: false }
+    }
+
+    pub fn err(code: A5ErrorCode, message: impl Into<String>) -> Self {
+        Self { data: std::ptr::null_mut(), len: 0, error: string_ptr(CString::new(message.into()).ok()), code, arena: false }
+    }
+}
+
+impl PanicDefault for CellArray {
+    fn from_panic(message: String) -> Self {
+        Self::err(A5ErrorCode::Internal, message)
+    }
+}
+
+#[repr(C)]
+pub struct TileArray {
+    pub data: *mut Tile,        // pointer to array of tiles
+    pub len: usize,               // length of the array
+    pub error: *mut std::os::raw::c_char, // null if no error
+    pub code: A5ErrorCode,
+}
+
+impl<E: Into<Error>> From<Result<Vec<Tile>, E>> for TileArray {
+    fn from(result: Result<Vec<Tile>, E>) -> Self {
+        match result {
+            Ok(vec) if vec.is_empty() => Self { data: std::ptr::null_mut(), len: 0, error: std::ptr::null_mut(), code: A5ErrorCode::None },
+            Ok(vec) => {
+                let mut boxed_slice = vec.into_boxed_slice();
+                let data_ptr = boxed_slice.as_mut_ptr();
+                let len = boxed_slice.len();
+                std::mem::forget(boxed_slice);
+                Self { data: data_ptr, len, error: std::ptr::null_mut(), code: A5ErrorCode::None }
+            }
+            Err(e) => {
+                let (code, message) = map_error(e);
+                Self { data: std::ptr::null_mut(), len: 0, error: string_ptr(message), code }
+            }
+        }
+    }
+}
+
+impl PanicDefault for TileArray {
+    fn from_panic(message: String) -> Self {
+        Self { data: std::ptr::null_mut(), len: 0, error: string_ptr(CString::new(message).ok()), code: A5ErrorCode::Internal }
+    }
+}
+
+
+pub fn vec_result_to_c(result: Result<Vec<a5::LonLat>, Error>) -> LonLatDegreesArray {
+    match result {
+        Ok(vec) if vec.is_empty() => LonLatDegreesArray::empty(),
+        Ok(vec) => {
+            let degree_vec: Vec<LonLatDegrees> = vec.into_iter().map(|ll| LonLatDegrees { lon: ll.longitude.get(), lat: ll.latitude.get() }).collect();
+            let len = degree_vec.len();
+
+            let mut boxed_slice = degree_vec.into_boxed_slice(); // heap allocation
+            let data_ptr = boxed_slice.as_mut_ptr();
+            std::mem::forget(boxed_slice); // prevent Rust from freeing it
+            LonLatDegreesArray { data: data_ptr, len, error: std::ptr::null_mut(), code: A5ErrorCode::None, arena: false }
+        }
+        Err(e) => {
+            let (code, message) = map_error(e);
+            LonLatDegreesArray { data: std::ptr::null_mut(), len: 0, error: string_ptr(message), code, arena: false }
+        }
+    }
+}
+
+pub fn cell_vec_result_to_c(result: Result<Vec<u64>, Error>) -> CellArray {
+    match result {
+        Ok(vec) if vec.is_empty() => CellArray::empty(),
+        Ok(vec) => {
+            let mut boxed_slice = vec.into_boxed_slice(); // heap allocation
+            let data_ptr = boxed_slice.as_mut_ptr();
+            let len = boxed_slice.len();
+            std::mem::forget(boxed_slice); // prevent Rust from freeing it
+            CellArray { data: data_ptr, len, error: std::ptr::null_mut(), code: A5ErrorCode::None, arena: false }
+        }
+        Err(e) => {
+            let (code, message) = map_error(e);
+            CellArray { data: std::ptr::null_mut(), len: 0, error: string_ptr(message), code, arena: false }
+        }
+    }
+}
+
+
+/// Frees a `LonLatDegreesArray` passed by value. Prefer
+/// `a5_free_lonlatdegrees_array_ptr`: a copy freed here cannot be marked as
+/// freed, so freeing it twice is undefined behavior.
+#[deprecated(note = "use a5_free_lonlatdegrees_array_ptr, which makes a second free a no-op")]
+#[no_mangle]
+pub extern "C" fn a5_free_lonlatdegrees_array(mut arr: LonLatDegreesArray) {
+    guard(|| release_lonlatdegrees_array(&mut arr))
+}
+
+/// Frees the array `arr` points to, then sets its `data` and `error` to null
+/// and `len` to 0, so that freeing the same array again is a harmless no-op.
+/// A null `arr` is ignored, as is an array owned by an `A5Arena`, which
+/// `a5_arena_free` releases.
+#[no_mangle]
+pub extern "C" fn a5_free_lonlatdegrees_array_ptr(arr: *mut LonLatDegreesArray) {
+    guard(|| {
+        if let Some(arr) = unsafe { arr.as_mut() } {
+            release_lonlatdegrees_array(arr);
+        }
+    })
+}
+
+fn release_lonlatdegrees_array(arr: &mut LonLatDegreesArray) {
+    if arr.arena {
+        return;
+    }
+    if !arr.data.is_null() {
+        unsafe {
+            // reconstruct the boxed slice and drop it
+            let _ = Box::from_raw(std::ptr::slice_from_raw_parts_mut(arr.data, arr.len));
+        }
+    }
+    if !arr.error.is_null() {
+        unsafe { drop(CString::from_raw(arr.error)); }
+    }
+    (arr.data, arr.len, arr.error) = (std::ptr::null_mut(), 0, std::ptr::null_mut());
+}
+
+/// Frees a `CellArray` passed by value. Prefer `a5_free_cell_array_ptr`: a
+/// copy freed here cannot be marked as freed, so freeing it twice is undefined
+/// behavior.
+#[deprecated(note = "use a5_free_cell_array_ptr, which makes a second free a no-op")]
+#[no_mangle]
+pub extern "C" fn a5_free_cell_array(mut arr: CellArray) {
+    guard(|| release_cell_array(&mut arr))
+}
+
+/// Frees the array `arr` points to, then sets its `data` and `error` to null
+/// and `len` to 0, so that freeing the same array again is a harmless no-op.
+/// A null `arr` is ignored, as is an array owned by an `A5Arena`, which
+/// `a5_arena_free` releases.
+#[no_mangle]
+pub extern "C" fn a5_free_cell_array_ptr(arr: *mut CellArray) {
+    guard(|| {
+        if let Some(arr) = unsafe { arr.as_mut() } {
+            release_cell_array(arr);
+        }
+    })
+}
+
+fn release_cell_array(arr: &mut CellArray) {
+    if arr.arena {
+        return;
+    }
+    if !arr.data.is_null() {
+        unsafe {
+            // reconstruct the boxed slice and drop it
+            let _ = Box::from_raw(std::ptr::slice_from_raw_parts_mut(arr.data, arr.len));
+        }
+    }
+    if !arr.error.is_null() {
+        unsafe { drop(CString::from_raw(arr.error)); }
+    }
+    (arr.data, arr.len, arr.error) = (std::ptr::null_mut(), 0, std::ptr::null_mut());
+}
+
+/// Frees the array `arr` points to, as `a5_free_cell_array_ptr` does for
+/// cells. A null `arr` is ignored.
+#[no_mangle]
+pub extern "C" fn a5_free_tile_array_ptr(arr: *mut TileArray) {
+    guard(|| {
+        if let Some(arr) = unsafe { arr.as_mut() } {
+            if !arr.data.is_null() {
+                unsafe { drop(Box::from_raw(std::ptr::slice_from_raw_parts_mut(arr.data, arr.len))) };
+            }
+            if !arr.error.is_null() {
+                unsafe { drop(CString::from_raw(arr.error)) };
+            }
+            (arr.data, arr.len, arr.error) = (std::ptr::null_mut(), 0, std::ptr::null_mut());
+        }
+    })
+}
+
+/// Owner of the arrays returned by the `_arena` functions, so that a batch of
+/// results is released by a single `a5_arena_free` rather than one free per
+/// array. Arena arrays have `arena` set, and the `a5_free_*_ptr` functions
+/// leave them untouched, so code written for heap arrays may still call them.
+/// Create one with `a5_arena_new`.
+///
+/// This is shared ownership rather than arena allocation: each result is
+/// still its own heap allocation, made exactly as for the plain function, and
+/// the arena only records it. It saves the caller the per-array frees, not the
+/// allocator calls.
+#[derive(Default)]
+pub struct A5Arena {
+    cells: Vec<CellArray>,
+    points: Vec<LonLatDegreesArray>,
+}
+
+impl A5Arena {
+    /// Takes ownership of a heap array, returning a copy marked as the arena's.
+    fn adopt_cells(&mut self, arr: CellArray) -> CellArray {
+        let adopted = CellArray { data: arr.data, len: arr.len, error: arr.error, code: arr.code, arena: true };
+        self.cells.push(arr);
+        adopted
+    }
+
+    fn adopt_points(&mut self, arr: LonLatDegreesArray) -> LonLatDegreesArray {
+        let adopted = LonLatDegreesArray { data: arr.data, len: arr.len, error: arr.error, code: arr.code, arena: true };
+        self.points.push(arr);
+        adopted
+    }
+}
+
+impl Drop for A5Arena {
+    fn drop(&mut self) {
+        self.cells.iter_mut().for_each(release_cell_array);
+        self.points.iter_mut().for_each(release_lonlatdegrees_array);
+    }
+}
+
+/// Runs `f` and hands its array to `arena`, or gives `NullPointer` without
+/// running it for a null `arena`.
+fn in_arena_cells(arena: *mut A5Arena, f: impl FnOnce() -> CellArray) -> CellArray {
+    match unsafe { arena.as_mut() } {
+        Some(arena) => arena.adopt_cells(f()),
+        None => CellArray::err(A5ErrorCode::NullPointer, "null pointer: arena"),
+    }
+}
+
+fn in_arena_points(arena: *mut A5Arena, f: impl FnOnce() -> LonLatDegreesArray) -> LonLatDegreesArray {
+    match unsafe { arena.as_mut() } {
+        Some(arena) => arena.adopt_points(f()),
+        None => LonLatDegreesArray::err(A5ErrorCode::NullPointer, "null pointer: arena"),
+    }
+}
+
+/// Returns a new, empty arena, to be released with `a5_arena_free`.
+#[no_mangle]
+pub extern "C" fn a5_arena_new() -> *mut A5Arena {
+    guard(|| Box::into_raw(Box::default()))
+}
+
+/// Frees `arena` along with every array it holds, error strings included.
+/// Arrays returned into it must not be used afterwards. A null `arena` is
+/// ignored.
+#[no_mangle]
+pub extern "C" fn a5_arena_free(arena: *mut A5Arena) {
+    guard(|| {
+        if !arena.is_null() {
+            unsafe { drop(Box::from_raw(arena)) };
+        }
+    })
+}
+
+/// `a5_cell_to_children` with the result held by `arena`.
+#[no_mangle]
+pub extern "C" fn a5_cell_to_children_arena(arena: *mut A5Arena, index: u64, child_resolution: i32) -> CellArray {
+    guard(|| in_arena_cells(arena, || a5_cell_to_children(index, child_resolution)))
+}
+
+/// `a5_grid_disk` with the result held by `arena`.
+#[no_mangle]
+pub extern "C" fn a5_grid_disk_arena(arena: *mut A5Arena, cell_id: u64, k: i32) -> CellArray {
+    guard(|| in_arena_cells(arena, || a5_grid_disk(cell_id, k)))
+}
+
+/// `a5_cell_to_neighbors` with the result held by `arena`.
+#[no_mangle]
+pub extern "C" fn a5_cell_to_neighbors_arena(arena: *mut A5Arena, cell: u64) -> CellArray {
+    guard(|| in_arena_cells(arena, || a5_cell_to_neighbors(cell)))
+}
+
+/// `a5_compact` with the result held by `arena`.
+#[no_mangle]
+pub extern "C" fn a5_compact_arena(arena: *mut A5Arena, cells: *const u64, len: usize) -> CellArray {
+    guard(|| in_arena_cells(arena, || a5_compact(cells, len)))
+}
+
+/// `a5_uncompact` with the result held by `arena`.
+#[no_mangle]
+pub extern "C" fn a5_uncompact_arena(arena: *mut A5Arena, cells: *const u64, len: usize, target_resolution: i32) -> CellArray {
+    guard(|| in_arena_cells(arena, || a5_uncompact(cells, len, target_resolution)))
+}
+
+/// `a5_polygon_to_cells` with the result held by `arena`.
+#[no_mangle]
+pub extern "C" fn a5_polygon_to_cells_arena(arena: *mut A5Arena, ring: *const LonLatDegrees, ring_len: usize, resolution: i32, mode: FillMode) -> CellArray {
+    guard(|| in_arena_cells(arena, || a5_polygon_to_cells(ring, ring_len, resolution, mode)))
+}
+
+/// `a5_cell_to_boundary` with the result held by `arena`.
+#[no_mangle]
+pub extern "C" fn a5_cell_to_boundary_arena(arena: *mut A5Arena, cell_id: u64, options: CellBoundaryOptions) -> LonLatDegreesArray {
+    guard(|| in_arena_points(arena, || a5_cell_to_boundary(cell_id, options)))
+}
+
+/// Returns the boundary of `cell_id`. Vertices run counter-clockwise seen
+/// from above, the orientation GeoJSON asks of exterior rings; longitudes
+/// are not wrapped, so a cell crossing the antimeridian can run past ±180.
+/// See `a5_cell_to_boundary_oriented` to pick the winding.
+///
+/// With the `boundary-cache` feature and a cache size set, see
+/// `a5_set_boundary_cache_size`, boundaries at the default segment count are
+/// copied out of the cache, which holds exactly what the uncached path
+/// returns; an explicit segment count always recomputes.
+#[no_mangle]
+pub extern "C" fn a5_cell_to_boundary(cell_id: u64, options: CellBoundaryOptions) -> LonLatDegreesArray {
+    guard(|| {
+        #[cfg(feature = "boundary-cache")]
+        if options.segments().is_none() {
+            if let Some(ring) = cache::closed_boundary(cell_id) {
+                // The closed ring repeats its final vertex at the start
+                return vec_result_to_c(ring.map(|ring| if options.closed_ring { ring.to_vec() } else { ring[1..].to_vec() }));
+            }
+        }
+        vec_result_to_c(a5::cell_to_boundary(cell_id, Some(a5::core::cell::CellToBoundaryOptions { closed_ring: options.closed_ring, segments: options.segments() })).map_err(Error::from))
+    })
+}
+
+/// Returns the open boundary of `cell`, as from `a5_cell_to_boundary` with
+/// default options, wound counter-clockwise when `ccw` is set, for GeoJSON
+/// exterior rings, and clockwise otherwise, as some renderers expect. The
+/// winding is measured on the ring with the antimeridian and poles unwrapped,
+/// so it holds for every cell, and the ring is reversed if it is wrong.
+#[no_mangle]
+pub extern "C" fn a5_cell_to_boundary_oriented(cell: u64, ccw: bool) -> LonLatDegreesArray {
+    guard(|| vec_result_to_c(oriented_boundary(cell, ccw).map(|ring| ring.into_iter().map(|(lon, lat)| a5::LonLat::new(lon, lat)).collect())))
+}
+
+fn oriented_boundary(cell: u64, ccw: bool) -> Result<Vec<geometry::Point>, Error> {
+    let mut ring = boundary_points(cell)?;
+    if (geometry::signed_area(&geometry::planar_ring(&ring)) > 0.0) != ccw {
+        ring.reverse();
+    }
+    Ok(ring)
+}
+
+/// Writes the center of `cell`, as from `a5_cell_to_lon_lat`, to `out_lon` and
+/// `out_lat`, and its open boundary, as from `a5_cell_to_boundary` with default
+/// options, to `out_vertices`, so a join needs one call per cell. On failure
+/// the centers are left untouched and `out_vertices` gets an empty array
+/// carrying the error; free it with `a5_free_lonlatdegrees_array_ptr` either
+/// way. Returns `NullPointer`, writing nothing, if any out pointer is null.
+#[no_mangle]
+pub extern "C" fn a5_cell_to_lon_lat_all(cell: u64, out_lon: *mut f64, out_lat: *mut f64, out_vertices: *mut LonLatDegreesArray) -> A5ErrorCode {
+    guard(|| {
+        if out_lon.is_null() || out_lat.is_null() || out_vertices.is_null() {
+            return A5ErrorCode::NullPointer;
+        }
+        let located = boundary_points(cell).and_then(|ring| Ok((a5::cell_to_lonlat(cell)?, ring.into_iter().map(|(lon, lat)| a5::LonLat::new(lon, lat)).collect())));
+        let vertices = match located {
+            Ok((center, boundary)) => {
+                unsafe {
+                    *out_lon = center.longitude();
+                    *out_lat = center.latitude();
+                }
+                vec_result_to_c(Ok(boundary))
+            }
+            Err(e) => vec_result_to_c(Err(e)),
+        };
+        let code = vertices.code;
+        unsafe { out_vertices.write(vertices) };
+        code
+    })
+}
+
+/// Boundary of a cell split at the antimeridian, from
+/// `a5_cell_to_boundary_split`. Free it with `a5_free_split_boundary_ptr`.
+#[repr(C)]
+pub struct SplitBoundary {
+    /// The whole boundary, or when `split` is set its part on one side of the
+    /// antimeridian
+    pub first: LonLatDegreesArray,
+    /// The part on the other side, empty unless `split` is set
+    pub second: LonLatDegreesArray,
+    pub split: bool,
+    pub error: *mut std::os::raw::c_char, // null if no error
+    pub code: A5ErrorCode,
+}
+
+impl SplitBoundary {
+    fn err(code: A5ErrorCode, message: Option<CString>) -> Self {
+        Self { first: LonLatDegreesArray::empty(), second: LonLatDegreesArray::empty(), split: false, error: string_ptr(message), code }
+    }
+}
+
+impl PanicDefault for SplitBoundary {
+    fn from_panic(message: String) -> Self {
+        Self::err(A5ErrorCode::Internal, CString::new(message).ok())
+    }
+}
+
+/// Returns the boundary of `cell` as one ring, or as two when it crosses the
+/// antimeridian, each cut at ±180 with every longitude in [-180, 180], so it
+/// renders without stretching across the map. `split` says which happened.
+///
+/// Rings are open and counter-clockwise, with a pole vertex expanded to points
+/// along the pole's latitude, exactly as the polygons of
+/// `a5_cells_to_geojson`.
+#[no_mangle]
+pub extern "C" fn a5_cell_to_boundary_split(cell: u64) -> SplitBoundary {
+    guard(|| {
+        let ring_to_c = |ring: &[geometry::Point]| vec_result_to_c(Ok(ring.iter().map(|&(lon, lat)| a5::LonLat::new(lon, lat)).collect()));
+        match cell_parts(cell) {
+            Ok(parts) => SplitBoundary {
+                first: ring_to_c(&parts[0]),
+                second: parts.get(1).map_or_else(LonLatDegreesArray::empty, |ring| ring_to_c(ring)),
+                split: parts.len() > 1,
+                error: std::ptr::null_mut(),
+                code: A5ErrorCode::None,
+            },
+            Err(e) => {
+                let (code, message) = map_error(e);
+                SplitBoundary::err(code, message)
+            }
+        }
+    })
+}
+
+/// Frees a `SplitBoundary` passed by value. Prefer
+/// `a5_free_split_boundary_ptr`: a copy freed here cannot be marked as freed,
+/// so freeing it twice is undefined behavior.
+#[deprecated(note = "use a5_free_split_boundary_ptr, which makes a second free a no-op")]
+#[no_mangle]
+pub extern "C" fn a5_free_split_boundary(mut boundary: SplitBoundary) {
+    guard(|| release_split_boundary(&mut boundary))
+}
+
+/// Frees both rings and the error of the boundary `boundary` points to, then
+/// nulls them, so that freeing the same boundary again is a harmless no-op.
+/// A null `boundary` is ignored.
+#[no_mangle]
+pub extern "C" fn a5_free_split_boundary_ptr(boundary: *mut SplitBoundary) {
+    guard(|| {
+        if let Some(boundary) = unsafe { boundary.as_mut() } {
+            release_split_boundary(boundary);
+        }
+    })
+}
+
+fn release_split_boundary(boundary: &mut SplitBoundary) {
+    release_lonlatdegrees_array(&mut boundary.first);
+    release_lonlatdegrees_array(&mut boundary.second);
+    if !boundary.error.is_null() {
+        unsafe { drop(CString::from_raw(boundary.error)) };
+    }
+    boundary.error = std::ptr::null_mut();
+}
+
+/// Boundary of `cell` as a planar ring, built by `geometry::planar_ring`.
+fn cell_ring(cell: u64) -> Result<Vec<geometry::Point>, Error> {
+    let options = a5::core::cell::CellToBoundaryOptions { closed_ring: false, segments: None };
+    let raw: Vec<geometry::Point> = a5::cell_to_boundary(cell, Some(options))?.iter().map(|p| (p.longitude(), p.latitude())).collect();
+    Ok(geometry::planar_ring(&raw))
+}
+
+fn cell_bbox(cell: u64) -> Result<BBox, Error> {
+    validate_cell(cell)?;
+    let ring = cell_ring(cell)?;
+    let (mut min_lon, mut min_lat, mut max_lon, mut max_lat) = (f64::INFINITY, f64::INFINITY, f64::NEG_INFINITY, f64::NEG_INFINITY);
+    for &(lon, lat) in &ring {
+        min_lon = min_lon.min(lon);
+        max_lon = max_lon.max(lon);
+        min_lat = min_lat.min(lat);
+        max_lat = max_lat.max(lat);
+    }
+    // Ring longitudes are unwrapped, so they may leave [-180, 180] for a cell
+    // crossing the antimeridian, and span all of them for a cell around a pole
+    let wrap = |lon: f64| ((lon + 180.0).rem_euclid(360.0)) - 180.0;
+    if max_lon - min_lon >= 360.0 {
+        Ok(BBox::new(-180.0, min_lat, 180.0, max_lat, false))
+    } else if min_lon < -180.0 || max_lon > 180.0 {
+        Ok(BBox::new(wrap(min_lon), min_lat, wrap(max_lon), max_lat, true))
+    } else {
+        Ok(BBox::new(min_lon, min_lat, max_lon, max_lat, false))
+    }
+}
+
+/// Boundary of `cell` as counter-clockwise rings within [-180, 180], split in
+/// two when the cell crosses the antimeridian. Rings are open.
+fn cell_parts(cell: u64) -> Result<Vec<Vec<geometry::Point>>, Error> {
+    validate_cell(cell)?;
+    let ring = cell_ring(cell)?;
+    Ok(geometry::split_antimeridian(&ring).into_iter().map(geometry::counter_clockwise).collect())
+}
+
+/// Encodes `cells` as a GeoJSON FeatureCollection with one Feature per cell.
+///
+/// Exterior rings are counter-clockwise per the right-hand rule, and a cell
+/// crossing the antimeridian becomes a MultiPolygon split at ±180. When
+/// `include_id` is set each Feature carries the cell hex id in
+/// `properties.a5`. Returns null if any cell is invalid; otherwise free the
+/// string with `a5_free_cstring`.
+#[no_mangle]
+pub extern "C" fn a5_cells_to_geojson(cells: *const u64, len: usize, include_id: bool) -> *mut std::os::raw::c_char {
+    guard(|| {
+        let Ok(cells) = input_slice(cells, len) else {
+            return std::ptr::null_mut();
+        };
+        let features: Result<Vec<_>, Error> = cells.iter().map(|&cell| cell_parts(cell).map(|parts| (cell, parts))).collect();
+        match features {
+            Ok(features) => string_ptr(CString::new(formats::geojson_feature_collection(&features, include_id)).ok()),
+            Err(_) => std::ptr::null_mut(),
+        }
+    })
+}
+
+/// Encodes `cells` as CSV: a header row, then one row per cell with its hex
+/// id in column `a5`, followed by `center_lon,center_lat` when
+/// `include_center` is set and `resolution` when `include_resolution` is set.
+/// Centers are as from `a5_cell_to_lon_lat`, written at full precision; rows
+/// end in `\n`. Returns null if any cell is invalid; otherwise free the string
+/// with `a5_free_cstring`.
+#[no_mangle]
+pub extern "C" fn a5_cells_to_csv(cells: *const u64, len: usize, include_center: bool, include_resolution: bool) -> *mut std::os::raw::c_char {
+    guard(|| {
+        let Ok(cells) = input_slice(cells, len) else {
+            return std::ptr::null_mut();
+        };
+        let centers: Result<Vec<geometry::Point>, Error> = cells
+            .iter()
+            .map(|&cell| validate_cell(cell).and_then(|_| if include_center { a5::cell_to_lonlat(cell).map(|c| (c.longitude(), c.latitude())).map_err(Error::from) } else { Ok((0.0, 0.0)) }))
+            .collect();
+        match centers {
+            Ok(centers) => string_ptr(CString::new(formats::cells_csv(cells, include_center.then_some(&centers[..]), include_resolution)).ok()),
+            Err(_) => std::ptr::null_mut(),
+        }
+    })
+}
+
+/// Encodes `cells` as a compact MessagePack blob for caching, writing its size
+/// in bytes to `out_len`. The ids are sorted, then stored as varint gaps
+/// between neighbours, so clustered sets such as polyfill output take one or
+/// two bytes per cell rather than 8; duplicates are kept. The blob is a 3 item
+/// array: the cell count, a shift `s`, then a `bin` of unsigned LEB128 gaps
+/// between the ids shifted right by `s`, the first measured from 0. Returns
+/// null and writes 0 if any cell is invalid.
+/// Decode with `a5_msgpack_to_cells` and free with `a5_free_bytes`.
+#[no_mangle]
+pub extern "C" fn a5_cells_to_msgpack(cells: *const u64, len: usize, out_len: *mut usize) -> *mut u8 {
+    guard(|| {
+        let checked = input_slice(cells, len).and_then(|cells| {
+            let mut sorted = cells.to_vec();
+            sorted.sort_unstable();
+            sorted.iter().try_for_each(|&cell| validate_cell(cell))?;
+            Ok(sorted)
+        });
+        let (ptr, len) = match checked {
+            Ok(sorted) => {
+                let bytes = formats::cells_msgpack(&sorted).into_boxed_slice();
+                let len = bytes.len();
+                (Box::into_raw(bytes) as *mut u8, len)
+            }
+            Err(_) => (std::ptr::null_mut(), 0),
+        };
+        if !out_len.is_null() {
+            unsafe { *out_len = len; }
+        }
+        ptr
+    })
+}
+
+/// Decodes a blob from `a5_cells_to_msgpack` back into its sorted cells.
+/// Malformed input or a decoded id that is not a valid cell fails with
+/// `InvalidCell`.
+#[no_mangle]
+pub extern "C" fn a5_msgpack_to_cells(bytes: *const u8, len: usize) -> CellArray {
+    guard(|| {
+        cell_vec_result_to_c(input_slice(bytes, len).and_then(formats::msgpack_cells).and_then(|cells| {
+            cells.iter().try_for_each(|&cell| validate_cell(cell))?;
+            Ok(cells)
+        }))
+    })
+}
+
+/// Compresses `cells` into a bare byte stream for column storage, writing its
+/// size in bytes to `out_len`. The ids are sorted and deduplicated, then
+/// stored as varint gaps like `a5_cells_to_msgpack` but without any framing:
+/// one byte holding the shift, then the gaps. An empty set compresses to an
+/// empty stream, returned as null with `out_len` 0. Returns null and writes 0
+/// if any cell is invalid. Restore with `a5_decompress_cells` and free with
+/// `a5_free_bytes`.
+#[no_mangle]
+pub extern "C" fn a5_compress_cells(cells: *const u64, len: usize, out_len: *mut usize) -> *mut u8 {
+    guard(|| {
+        let checked = input_slice(cells, len).and_then(|cells| {
+            let mut sorted = cells.to_vec();
+            sorted.sort_unstable();
+            sorted.dedup();
+            sorted.iter().try_for_each(|&cell| validate_cell(cell))?;
+            Ok(sorted)
+        });
+        let (ptr, len) = match checked {
+            Ok(sorted) if sorted.is_empty() => (std::ptr::null_mut(), 0),
+            Ok(sorted) => {
+                let bytes = formats::compress_cells(&sorted).into_boxed_slice();
+                let len = bytes.len();
+                (Box::into_raw(bytes) as *mut u8, len)
+            }
+            Err(_) => (std::ptr::null_mut(), 0),
+        };
+        if !out_len.is_null() {
+            unsafe { *out_len = len; }
+        }
+        ptr
+    })
+}
+
+/// Restores the sorted cells from an `a5_compress_cells` stream. A malformed
+/// stream or a decoded id that is not a valid cell fails with `InvalidCell`.
+#[no_mangle]
+pub extern "C" fn a5_decompress_cells(bytes: *const u8, len: usize) -> CellArray {
+    guard(|| {
+        cell_vec_result_to_c(input_slice(bytes, len).and_then(formats::decompress_cells).and_then(|cells| {
+            cells.iter().try_for_each(|&cell| validate_cell(cell))?;
+            Ok(cells)
+        }))
+    })
+}
+
+/// Frees a byte buffer returned by this library, such as from
+/// `a5_cells_to_msgpack`; `len` must be the length it reported.
+#[no_mangle]
+pub extern "C" fn a5_free_bytes(ptr: *mut u8, len: usize) {
+    a5_free_wkb(ptr, len)
+}
+
+/// Dissolves `cells` into the outline of their union, returned as a GeoJSON
+/// MultiPolygon with one polygon per connected region and a hole for every
+/// gap inside one. Shared edges are merged, so only the silhouette remains.
+///
+/// Cells may mix resolutions, e.g. after `a5_compact_cells`; they are first
+/// expanded to the finest resolution present, since A5 children do not nest
+/// exactly inside their parents and coarse edges would not meet fine ones.
+/// An expansion past `A5_DESCENDANT_LIMIT` cells is refused. Exterior rings
+/// are counter-clockwise and holes clockwise, per the right-hand rule. Unlike
+/// `a5_cells_to_geojson`, rings are not split at the antimeridian: their
+/// longitudes run on contiguously past ±180. Gaps in a set that reaches
+/// around the sphere become holes in a polygon covering the whole
+/// [-180, 180] x [-90, 90] plane.
+///
+/// Returns null if any cell is invalid or the expansion is too large;
+/// otherwise free the string with `a5_free_cstring`.
+#[no_mangle]
+pub extern "C" fn a5_cells_to_multipolygon(cells: *const u64, len: usize) -> *mut std::os::raw::c_char {
+    guard(|| {
+        let Ok(cells) = input_slice(cells, len) else {
+            return std::ptr::null_mut();
+        };
+        match cells_outline(cells) {
+            Ok(polygons) => {
+                let mut json = String::new();
+                formats::write_geojson_multipolygon(&mut json, &polygons);
+                string_ptr(CString::new(json).ok())
+            }
+            Err(_) => std::ptr::null_mut(),
+        }
+    })
+}
+
+/// Outline of the union of `cells` as polygons of an outer ring and its holes,
+/// with exterior rings counter-clockwise and holes clockwise.
+fn cells_outline(cells: &[u64]) -> Result<Vec<Vec<Vec<geometry::Point>>>, Error> {
+    cells.iter().try_for_each(|&cell| validate_cell(cell))?;
+    let finest = cells.iter().map(|&cell| a5::get_resolution(cell)).max().unwrap_or(0);
+    let mut cells = uncompact_cells(cells, finest)?;
+    cells.sort_unstable();
+    cells.dedup();
+    let rings: Vec<Vec<geometry::Point>> = cells.iter().map(|&cell| boundary_points(cell)).collect::<Result<_, _>>()?;
+    Ok(dissolve::dissolve(&rings).into_iter().map(|polygon| {
+        polygon.into_iter().enumerate().map(|(i, ring)| {
+            let mut ring = geometry::counter_clockwise(ring);
+            if i > 0 {
+                ring.reverse();
+            }
+            ring
+        }).collect()
+    }).collect())
+}
+
+/// Returns the cell boundary with the edge segment count and ring closure
+/// taken from `options`.
+#[no_mangle]
+pub extern "C" fn a5_cell_to_boundary_opts(cell: u64, options: BoundaryOptions) -> LonLatDegreesArray {
+    guard(|| vec_result_to_c(validate_cell(cell).and_then(|_| a5::cell_to_boundary(cell, Some(options.into())).map_err(Error::from))))
+}
+
+/// Largest number of vertices `a5_cell_to_boundary_densified` returns.
+pub const A5_DENSIFY_POINT_LIMIT: u64 = 10_000_000;
+
+/// Returns the open boundary of `cell`, as from `a5_cell_to_boundary` with
+/// default options, with `points_per_edge` vertices inserted evenly along the
+/// great circle between each pair of consecutive vertices, including the
+/// closing pair. Zero returns the boundary unchanged. A request for more than
+/// `A5_DENSIFY_POINT_LIMIT` vertices gives `OutOfBounds`.
+#[no_mangle]
+pub extern "C" fn a5_cell_to_boundary_densified(cell: u64, points_per_edge: u32) -> LonLatDegreesArray {
+    guard(|| {
+        let densified = boundary_points(cell).and_then(|ring| {
+            let steps = points_per_edge as u64 + 1;
+            if ring.len() as u64 * steps > A5_DENSIFY_POINT_LIMIT {
+                return Err(Error::new(A5ErrorCode::OutOfBounds, format!("boundary out of bounds: needs more than {} vertices", A5_DENSIFY_POINT_LIMIT)));
+            }
+            let mut points = Vec::with_capacity(ring.len() * steps as usize);
+            for (i, &start) in ring.iter().enumerate() {
+                points.push(start);
+                // Cell vertices are never antipodal, so every edge has a path
+                if let Some((_, along)) = geometry::geodesic(start, ring[(i + 1) % ring.len()]) {
+                    points.extend((1..steps).map(|k| along(k as f64 / steps as f64)));
+                }
+            }
+            Ok(points.into_iter().map(|(lon, lat)| a5::LonLat::new(lon, lat)).collect())
+        });
+        vec_result_to_c(densified)
+    })
+}
+
+/// Copies `values` into the caller's buffer `out` of capacity `cap`, always
+/// writing the number of values to `out_len`.
+fn write_into<T: Copy>(values: &[T], out: *mut T, cap: usize, out_len: *mut usize) -> A5ErrorCode {
+    if !out_len.is_null() {
+        unsafe { *out_len = values.len(); }
+    }
+    if values.len() > cap {
+        return A5ErrorCode::BufferTooSmall;
+    }
+    if !values.is_empty() {
+        if out.is_null() {
+            return A5ErrorCode::BufferTooSmall;
+        }
+        unsafe { std::ptr::copy_nonoverlapping(values.as_ptr(), out, values.len()); }
+    }
+    A5ErrorCode::None
+}
+
+/// Writes the closed cell boundary into the caller-owned buffer `out`, which
+/// holds `cap` vertices, so no allocation has to be freed afterwards.
+///
+/// `out_len` receives the vertex count. If `cap` is too small nothing is
+/// written to `out` and `BufferTooSmall` is returned with the required count
+/// in `out_len`.
+#[no_mangle]
+pub extern "C" fn a5_cell_to_boundary_into(cell: u64, out: *mut LonLatDegrees, cap: usize, out_len: *mut usize) -> A5ErrorCode {
+    guard(|| {
+        let boundary = validate_cell(cell).and_then(|_| a5::cell_to_boundary(cell, None).map_err(Error::from));
+        match boundary {
+            Ok(points) => {
+                let vertices: Vec<LonLatDegrees> = points.iter().map(|p| LonLatDegrees { lon: p.longitude(), lat: p.latitude() }).collect();
+                write_into(&vertices, out, cap, out_len)
+            }
+            Err(e) => map_error(e).0,
+        }
+    })
+}
+
+/// Resolves `child_resolution` for `cell`, with `A5_DEFAULT_RESOLUTION` meaning
+/// the next resolution, and returns it along with the number of children there.
+fn children_size(cell: u64, child_resolution: i32) -> Result<(i32, usize), Error> {
+    validate_cell(cell)?;
+    let resolution = a5::get_resolution(cell);
+    let target = if child_resolution == A5_DEFAULT_RESOLUTION { resolution + 1 } else { child_resolution };
+    validate_resolution(target)?;
+    if target < resolution {
+        return Err(Error::new(A5ErrorCode::InvalidResolution, format!("child resolution {} is coarser than cell resolution {}", target, resolution)));
+    }
+    // Counted here rather than with `a5::get_num_children`, whose division of
+    // cell totals loses the low digits of a face's count at fine resolutions
+    let quintants = if resolution == 0 && target > 0 { 5 } else { 1 };
+    let levels = target - resolution.max(1).min(target);
+    Ok((target, quintants * 4usize.pow(levels as u32)))
+}
+
+/// Returns how many children `cell` has at `child_resolution` without
+/// computing them, e.g. to size the buffer for `a5_cell_to_children_into`.
+///
+/// A5 subdivides each resolution 0 face into 5 quintants and every finer cell
+/// into 4, whatever its shape, so the count depends only on the two resolutions.
+#[no_mangle]
+pub extern "C" fn a5_cell_to_children_size(cell: u64, child_resolution: i32) -> ResultU64 {
+    guard(|| children_size(cell, child_resolution).map(|(_, count)| count as u64).into())
+}
+
+/// Returns how many descendants `cell` has at `resolution`, which must be
+/// finer than the cell's own; otherwise the error is `InvalidResolution`.
+///
+/// Every A5 cell is a pentagon and each subdivides the same way, 5 quintants
+/// per face and 4 children per cell below that, so no cell's subtree needs
+/// special counting and this is `a5_cell_to_children_size` restricted to
+/// strictly finer resolutions.
+#[no_mangle]
+pub extern "C" fn a5_cell_descendant_count(cell: u64, resolution: i32) -> ResultU64 {
+    guard(|| descendant_count(cell, resolution).into())
+}
+
+fn descendant_count(cell: u64, resolution: i32) -> Result<u64, Error> {
+    validate_cell(cell)?;
+    match a5::get_resolution(cell) {
+        own if resolution <= own => Err(Error::new(A5ErrorCode::InvalidResolution, format!("resolution {} is not finer than cell resolution {}", resolution, own))),
+        _ => children_size(cell, resolution).map(|(_, count)| count as u64),
+    }
+}
+
+/// Largest number of cells `a5_cell_to_descendants` returns.
+pub const A5_DESCENDANT_LIMIT: u64 = 100_000_000;
+
+/// Rejects a result of `total` cells past `A5_DESCENDANT_LIMIT` before any of
+/// it is allocated.
+fn limit_descendants(total: u64) -> Result<(), Error> {
+    if total > A5_DESCENDANT_LIMIT {
+        return Err(Error::new(A5ErrorCode::OutOfBounds, format!("descendants out of bounds: {} cells exceed the limit of {}", total, A5_DESCENDANT_LIMIT)));
+    }
+    Ok(())
+}
+
+/// Returns every descendant of `cell` from the next resolution down to
+/// `max_resolution`, which must be finer than the cell's own. Levels come
+/// coarse to fine, each complete before the next, as for building a tile
+/// pyramid; within a level, cells are in the order of `a5_cell_to_children`.
+/// More than `A5_DESCENDANT_LIMIT` cells give `OutOfBounds`.
+#[no_mangle]
+pub extern "C" fn a5_cell_to_descendants(cell: u64, max_resolution: i32) -> CellArray {
+    guard(|| {
+        let descendants = descendant_count(cell, max_resolution).and_then(|_| {
+            let levels = a5::get_resolution(cell) + 1..=max_resolution;
+            let total = levels.clone().map(|resolution| descendant_count(cell, resolution)).sum::<Result<u64, Error>>()?;
+            limit_descendants(total)?;
+            let mut cells = Vec::with_capacity(total as usize);
+            for resolution in levels {
+                for_each_child(cell, resolution, &mut |child| {
+                    cells.push(child);
+                    true
+                })?;
+            }
+            Ok(cells)
+        });
+        cell_vec_result_to_c(descendants)
+    })
+}
+
+/// Callback invoked once per cell by the `_foreach` functions with the caller's
+/// `ctx`; return false to stop early. It must not unwind: a panic or C++
+/// exception escaping it is undefined behavior.
+pub type A5CellCallback = extern "C" fn(ctx: *mut std::os::raw::c_void, cell: u64) -> bool;
+
+/// Children of a cell below resolution `target`: the segments they lie in and,
+/// within each segment, the run of `count` Hilbert indices starting at `first`.
+struct ChildLayout {
+    parent: a5::A5Cell,
+    target: i32,
+    segments: std::ops::Range<usize>,
+    first: u64,
+    count: u64,
+}
+
+fn child_layout(cell: u64, target: i32) -> Result<ChildLayout, Error> {
+    use a5::core::serialization::FIRST_HILBERT_RESOLUTION;
+    let parent = a5::core::serialization::deserialize(cell)?;
+    let segments = if parent.resolution == 0 { 0..5 } else { parent.segment..parent.segment + 1 };
+    // Each Hilbert level splits a cell's `s` into 4 children
+    let diff = target - parent.resolution.max(FIRST_HILBERT_RESOLUTION - 1);
+    let (first, count) = if diff > 0 { (parent.s << (2 * diff), 1u64 << (2 * diff)) } else { (parent.s, 1) };
+    Ok(ChildLayout { parent, target, segments, first, count })
+}
+
+impl ChildLayout {
+    fn child(&self, segment: usize, i: u64) -> Result<u64, Error> {
+        a5::core::serialization::serialize(&a5::A5Cell { origin_id: self.parent.origin_id, segment, s: self.first + i, resolution: self.target }).map_err(Error::from)
+    }
+}
+
+/// Calls `f` with each child of `cell` at the resolution chosen by
+/// `children_size`, in the order `a5::cell_to_children` returns them, without
+/// collecting them. Stops as soon as `f` returns false.
+fn for_each_child(cell: u64, child_resolution: i32, f: &mut dyn FnMut(u64) -> bool) -> Result<(), Error> {
+    let (target, _) = children_size(cell, child_resolution)?;
+    if target == a5::get_resolution(cell) {
+        f(cell);
+        return Ok(());
+    }
+    let layout = child_layout(cell, target)?;
+    for segment in layout.segments.clone() {
+        for i in 0..layout.count {
+            if !f(layout.child(segment, i)?) {
+                return Ok(());
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Returns the immediate child of `cell` at `child_index`, in the order
+/// `a5_cell_to_children` lists them, without building the list. A resolution
+/// 0 cell has 5 children, one per quintant, and every finer cell 4, so valid
+/// indices are `0..a5_cell_to_children_size(cell, A5_DEFAULT_RESOLUTION)`;
+/// a larger index fails with `OutOfBounds`. A resolution 30 cell has no
+/// children and fails with `InvalidResolution`.
+#[no_mangle]
+pub extern "C" fn a5_cell_child(cell: u64, child_index: u32) -> ResultU64 {
+    guard(|| {
+        children_size(cell, A5_DEFAULT_RESOLUTION)
+            .and_then(|(target, count)| {
+                if child_index as usize >= count {
+                    return Err(Error::new(A5ErrorCode::OutOfBounds, format!("child index {} out of bounds for {} children", child_index, count)));
+                }
+                let layout = child_layout(cell, target)?;
+                let index = child_index as u64;
+                layout.child(layout.segments.start + (index / layout.count) as usize, index % layout.count)
+            })
+            .into()
+    })
+}
+
+/// Returns the smallest and largest id of the descendants of `cell` at
+/// `resolution`. Below resolution 30 a cell id is its quintant, then its
+/// Hilbert index, then a marker bit, so within a quintant the descendants are
+/// consecutive ids at `resolution`; a resolution 0 cell spans 5 quintants,
+/// which are checked to be numbered consecutively.
+fn cell_range(cell: u64, resolution: i32) -> Result<(u64, u64), Error> {
+    let (target, _) = children_size(cell, resolution)?;
+    if target == a5::core::serialization::MAX_RESOLUTION {
+        return Err(Error::new(A5ErrorCode::InvalidResolution, format!("cell ids at resolution {} use a variable-length quintant prefix, so descendants are not contiguous", target)));
+    }
+    if target == a5::get_resolution(cell) {
+        return Ok((cell, cell));
+    }
+    let layout = child_layout(cell, target)?;
+    let mut runs = layout.segments.clone().map(|segment| Ok((layout.child(segment, 0)?, layout.child(segment, layout.count - 1)?))).collect::<Result<Vec<_>, Error>>()?;
+    runs.sort_unstable();
+    let quintant = |id: u64| id >> a5::core::serialization::HILBERT_START_BIT;
+    if runs.windows(2).any(|pair| quintant(pair[1].0) != quintant(pair[0].1) + 1) {
+        return Err(Error::new(A5ErrorCode::InvalidResolution, format!("descendants of {:x} at resolution {} are not contiguous", cell, target)));
+    }
+    Ok((runs[0].0, runs[runs.len() - 1].1))
+}
+
+/// Writes the smallest and largest id of the descendants of `cell` at
+/// `resolution` to `out_min` and `out_max`, so that a column of cells at that
+/// resolution can be filtered with `cell BETWEEN out_min AND out_max`: the ids
+/// at `resolution` in that range are exactly the descendants. Ids of cells at
+/// other resolutions can also fall in the range, so the column must hold a
+/// single resolution.
+///
+/// At resolution 30 the id layout interleaves quintants, so no such range
+/// exists and the call fails with `InvalidResolution`. Returns
+/// `NullPointer` if either out pointer is null.
+#[no_mangle]
+pub extern "C" fn a5_cell_to_range(cell: u64, resolution: i32, out_min: *mut u64, out_max: *mut u64) -> A5ErrorCode {
+    guard(|| {
+        if out_min.is_null() || out_max.is_null() {
+            return A5ErrorCode::NullPointer;
+        }
+        match cell_range(cell, resolution) {
+            Ok((min, max)) => {
+                unsafe {
+                    *out_min = min;
+                    *out_max = max;
+                }
+                A5ErrorCode::None
+            }
+            Err(e) => map_error(e).0,
+        }
+    })
+}
+
+/// Streams the children of `cell` at `child_resolution` through `cb`, in the
+/// same order as `a5_cell_to_children`, without ever holding the full set.
+/// Pass `A5_DEFAULT_RESOLUTION` for the immediate children. Iteration stops
+/// early, still returning `None`, once `cb` returns false.
+#[no_mangle]
+pub extern "C" fn a5_cell_to_children_foreach(cell: u64, child_resolution: i32, ctx: *mut std::os::raw::c_void, cb: A5CellCallback) -> A5ErrorCode {
+    guard(|| match for_each_child(cell, child_resolution, &mut |child| cb(ctx, child)) {
+        Ok(()) => A5ErrorCode::None,
+        Err(e) => map_error(e).0,
+    })
+}
+
+/// Writes the children of `cell` at `child_resolution` into the caller-owned
+/// buffer `out`, which holds `cap` cells. Pass `A5_DEFAULT_RESOLUTION` for the
+/// immediate children.
+///
+/// The child count is known before any child is computed, so an undersized
+/// buffer is reported as `BufferTooSmall`, with the required count in `out_len`,
+/// without doing the work.
+#[no_mangle]
+pub extern "C" fn a5_cell_to_children_into(cell: u64, child_resolution: i32, out: *mut u64, cap: usize, out_len: *mut usize) -> A5ErrorCode {
+    guard(|| {
+        let (target, count) = match children_size(cell, child_resolution) {
+            Ok(size) => size,
+            Err(e) => return map_error(e).0,
+        };
+        if count > cap {
+            if !out_len.is_null() {
+                unsafe { *out_len = count; }
+            }
+            return A5ErrorCode::BufferTooSmall;
+        }
+        let children = a5::cell_to_children(cell, Some(target));
+        match children {
+            Ok(children) => write_into(&children, out, cap, out_len),
+            Err(e) => map_error(e).0,
+        }
+    })
+}
+
+/// Open boundary of `cell` exactly as `a5_cell_to_boundary` computes it.
+fn boundary_points(cell: u64) -> Result<Vec<geometry::Point>, Error> {
+    validate_cell(cell)?;
+    let options = a5::core::cell::CellToBoundaryOptions { closed_ring: false, segments: None };
+    Ok(a5::cell_to_boundary(cell, Some(options))?.iter().map(|p| (p.longitude(), p.latitude())).collect())
+}
+
+/// Returns the cell boundary as a WKT `POLYGON((lon lat, ...))` with the ring
+/// closed. Coordinates are written at full precision so they parse back
+/// exactly. Returns null for an invalid cell; otherwise free the string with
+/// `a5_free_cstring`.
+#[no_mangle]
+pub extern "C" fn a5_cell_to_wkt(cell: u64) -> *mut std::os::raw::c_char {
+    guard(|| match boundary_points(cell) {
+        Ok(ring) => string_ptr(CString::new(formats::wkt_polygon(&ring)).ok()),
+        Err(_) => std::ptr::null_mut(),
+    })
+}
+
+/// Returns the cell boundary as a little-endian OGC WKB Polygon with one
+/// closed ring, writing its size in bytes to `out_len`.
+///
+/// This is plain WKB without an SRID; coordinates are WGS84 longitude and
+/// latitude. Longitudes are unwrapped around the cell center, so a cell
+/// crossing the antimeridian stays a single valid ring with values past ±180.
+/// Returns null and writes 0 for an invalid cell. Free with `a5_free_wkb`.
+#[no_mangle]
+pub extern "C" fn a5_cell_to_wkb(cell: u64, out_len: *mut usize) -> *mut u8 {
+    guard(|| {
+        let (ptr, len) = match boundary_points(cell) {
+            Ok(ring) => {
+                let bytes = formats::wkb_polygon(&ring).into_boxed_slice();
+                let len = bytes.len();
+                (Box::into_raw(bytes) as *mut u8, len)
+            }
+            Err(_) => (std::ptr::null_mut(), 0),
+        };
+        if !out_len.is_null() {
+            unsafe { *out_len = len; }
+        }
+        ptr
+    })
+}
+
+/// Frees a buffer returned by `a5_cell_to_wkb`; `len` must be the length it reported.
+#[no_mangle]
+pub extern "C" fn a5_free_wkb(ptr: *mut u8, len: usize) {
+    guard(|| {
+        if !ptr.is_null() {
+            unsafe { drop(Box::from_raw(std::ptr::slice_from_raw_parts_mut(ptr, len))); }
+        }
+    })
+}
+
+/// Boundary of `cell` as a boxed interleaved `[lon0, lat0, ...]` buffer of
+/// `T`, writing its length to `out_len`; null and 0 for an invalid cell.
+fn boundary_flat<T>(cell: u64, closed: bool, out_len: *mut usize, convert: impl Fn(f64) -> T) -> *mut T {
+    let boundary = validate_cell(cell).and_then(|_| a5::cell_to_boundary(cell, Some(a5::core::cell::CellToBoundaryOptions { closed_ring: closed, segments: None })).map_err(Error::from));
+    let (ptr, len) = match boundary {
+        Ok(points) => {
+            let flat: Box<[T]> = points.iter().flat_map(|p| [convert(p.longitude()), convert(p.latitude())]).collect();
+            let len = flat.len();
+            (Box::into_raw(flat) as *mut T, len)
+        }
+        Err(_) => (std::ptr::null_mut(), 0),
+    };
+    if !out_len.is_null() {
+        unsafe { *out_len = len; }
+    }
+    ptr
+}
+
+/// Returns the cell boundary as interleaved `[lon0, lat0, lon1, lat1, ...]`,
+/// writing the number of `f64`s to `out_len`. With `closed` set the first
+/// vertex is repeated at the end. Returns null and writes 0 for an invalid
+/// cell. Free with `a5_free_f64_array`.
+#[no_mangle]
+pub extern "C" fn a5_cell_to_boundary_flat(cell: u64, closed: bool, out_len: *mut usize) -> *mut f64 {
+    guard(|| boundary_flat(cell, closed, out_len, |v| v))
+}
+
+/// `a5_cell_to_boundary_flat` in `f32`, ready for a GPU vertex buffer. Free
+/// with `a5_free_f32_array`.
+///
+/// An `f32` keeps about 7 significant digits, so coordinates carry errors of
+/// up to about a meter near ±180. That is plenty for drawing cells down to
+/// around resolution 20, whose edges are several meters long, but not for
+/// analysis; use the `f64` boundary for that.
+#[no_mangle]
+pub extern "C" fn a5_cell_to_boundary_f32(cell: u64, closed: bool, out_len: *mut usize) -> *mut f32 {
+    guard(|| boundary_flat(cell, closed, out_len, |v| v as f32))
+}
+
+/// Frees a buffer returned by `a5_cell_to_boundary_flat`; `len` must be the
+/// number of `f64`s it reported.
+#[no_mangle]
+pub extern "C" fn a5_free_f64_array(ptr: *mut f64, len: usize) {
+    guard(|| {
+        if !ptr.is_null() {
+            unsafe { drop(Box::from_raw(std::ptr::slice_from_raw_parts_mut(ptr, len))); }
+        }
+    })
+}
+
+/// Simplifies the `len` points at `points`, such as a ring from
+/// `a5_cells_to_multipolygon`, with Douglas-Peucker on the sphere: no dropped
+/// point lies more than `tolerance_meters` from the great-circle path through
+/// the kept ones. The first and last points are always kept, so a closed ring
+/// stays closed, and a closed ring keeps at least four distinct vertices if it
+/// had them.
+///
+/// Writes the number of points returned to `out_len`. Returns null and writes
+/// 0 if `points` is null with a nonzero `len`, a coordinate is invalid or the
+/// tolerance is negative or not finite. Free with `a5_free_lonlatdegrees`.
+#[no_mangle]
+pub extern "C" fn a5_simplify_ring(points: *const LonLatDegrees, len: usize, tolerance_meters: f64, out_len: *mut usize) -> *mut LonLatDegrees {
+    guard(|| {
+        let input = input_slice(points, len).ok().filter(|input| tolerance_meters >= 0.0 && tolerance_meters.is_finite() && input.iter().all(|p| validate_lonlat(p.lon, p.lat).is_ok()));
+        let (ptr, len) = if let Some(input) = input {
+            let ring: Vec<geometry::Point> = input.iter().map(|p| (p.lon, p.lat)).collect();
+            let simplified: Box<[LonLatDegrees]> = geometry::simplify(&ring, tolerance_meters).into_iter().map(|(lon, lat)| LonLatDegrees { lon, lat }).collect();
+            let len = simplified.len();
+            (Box::into_raw(simplified) as *mut LonLatDegrees, len)
+        } else {
+            (std::ptr::null_mut(), 0)
+        };
+        if !out_len.is_null() {
+            unsafe { *out_len = len; }
+        }
+        ptr
+    })
+}
+
+/// Frees a buffer returned by `a5_simplify_ring`; `len` must be the number of
+/// points it reported.
+#[no_mangle]
+pub extern "C" fn a5_free_lonlatdegrees(ptr: *mut LonLatDegrees, len: usize) {
+    guard(|| {
+        if !ptr.is_null() {
+            unsafe { drop(Box::from_raw(std::ptr::slice_from_raw_parts_mut(ptr, len))); }
+        }
+    })
+}
+
+/// Frees a buffer returned by `a5_cell_to_boundary_f32`; `len` must be the
+/// number of `f32`s it reported.
+#[no_mangle]
+pub extern "C" fn a5_free_f32_array(ptr: *mut f32, len: usize) {
+    guard(|| {
+        if !ptr.is_null() {
+            unsafe { drop(Box::from_raw(std::ptr::slice_from_raw_parts_mut(ptr, len))); }
+        }
+    })
+}
+
+/// Returns the bounding box of the cell boundary. A resolution 0 cell around a
+/// pole spans all longitudes; finer cells meeting at a pole extend up to it.
+#[no_mangle]
+pub extern "C" fn a5_cell_to_bbox(cell: u64) -> BBox {
+    guard(|| cell_bbox(cell).into())
+}
+
+/// Returns the sorted cells at `resolution` covering the polygon `ring`, either
+/// those whose center lies inside it or all that intersect it, per `mode`.
+///
+/// The ring is a single outer boundary with no holes. It may be open or closed
+/// and wound either way, and may cross the antimeridian, but must not encircle
+/// a pole. Edges are straight lines in longitude/latitude degrees. Free the
+/// result with `a5_free_cell_array_ptr`.
+#[no_mangle]
+pub extern "C" fn a5_polygon_to_cells(ring: *const LonLatDegrees, ring_len: usize, resolution: i32, mode: FillMode) -> CellArray {
+    guard(|| {
+        let cells = validate_resolution(resolution)
+            .and_then(|_| input_slice(ring, ring_len))
+            .and_then(polyfill::Polygon::new)
+            .and_then(|polygon| polyfill::polygon_to_cells(&polygon, resolution, mode));
+        cell_vec_result_to_c(cells)
+    })
+}
+
+/// Covers the polygon `ring` with cells of mixed resolution: coarse cells
+/// over its interior and cells at `max_resolution` along its boundary, never
+/// coarser than `min_resolution`. The result is what compacting the
+/// `Intersects` fill at `max_resolution` with `a5_compact_cells_to` and floor
+/// `min_resolution` would give, but the fine interior is never computed:
+/// descending from the resolution 0 cells, a cell whose descendants must all
+/// lie inside the polygon is kept as it is.
+///
+/// The ring follows the rules of `a5_polygon_to_cells`. The output is sorted.
+/// `min_resolution` finer than `max_resolution` fails with
+/// `InvalidResolution`.
+#[no_mangle]
+pub extern "C" fn a5_polygon_to_cells_adaptive(ring: *const LonLatDegrees, ring_len: usize, min_resolution: i32, max_resolution: i32) -> CellArray {
+    guard(|| {
+        let cells = validate_resolution(min_resolution)
+            .and_then(|_| validate_resolution(max_resolution))
+            .and_then(|_| if min_resolution > max_resolution { Err(Error::new(A5ErrorCode::InvalidResolution, format!("min resolution {} is finer than max resolution {}", min_resolution, max_resolution))) } else { Ok(()) })
+            .and_then(|_| input_slice(ring, ring_len))
+            .and_then(polyfill::Polygon::new)
+            .and_then(|polygon| polyfill::adaptive_cells(&polygon, min_resolution, max_resolution))
+            .and_then(|cells| compact_cells_to(&cells, min_resolution));
+        cell_vec_result_to_c(cells)
+    })
+}
+
+/// Writes to `out_fraction` the share of the area of `cell` that the polygon
+/// `ring` covers, in [0, 1]: 1 for a cell wholly inside, 0 for one wholly
+/// outside. Meant for area-weighted interpolation of polygon values onto
+/// cells. The ring follows the rules of `a5_polygon_to_cells`, and its
+/// intersection with the cell is measured on the sphere like
+/// `a5_cell_exact_area`. Returns `NullPointer` if `out_fraction` is null.
+#[no_mangle]
+pub extern "C" fn a5_cell_polygon_overlap(cell: u64, ring: *const LonLatDegrees, ring_len: usize, out_fraction: *mut f64) -> A5ErrorCode {
+    guard(|| {
+        if out_fraction.is_null() {
+            return A5ErrorCode::NullPointer;
+        }
+        match input_slice(ring, ring_len).and_then(polyfill::Polygon::new).and_then(|polygon| polyfill::cell_overlap_fraction(&polygon, cell)) {
+            Ok(fraction) => {
+                unsafe { *out_fraction = fraction };
+                A5ErrorCode::None
+            }
+            Err(e) => map_error(e).0,
+        }
+    })
+}
+
+/// Returns the sorted cells at `resolution` intersecting the longitude/latitude
+/// box, e.g. to cover a map viewport. By convention `min_lon > max_lon` means
+/// the box wraps across the antimeridian, and a box 360 degrees wide covers
+/// every longitude. This is an `Intersects` fill of the box as a polygon, so
+/// it is pruned the same way.
+#[no_mangle]
+pub extern "C" fn a5_bbox_to_cells(min_lon: f64, min_lat: f64, max_lon: f64, max_lat: f64, resolution: i32) -> CellArray {
+    guard(|| cell_vec_result_to_c(validate_resolution(resolution).and_then(|_| polyfill::bbox_to_cells(min_lon, min_lat, max_lon, max_lat, resolution))))
+}
+
+/// Whether the boundary of `cell` overlaps the longitude/latitude box, with
+/// the box convention of `a5_bbox_to_cells`. The cell's own polygon is tested,
+/// not its bounding box, so a cell whose box only grazes a corner of the query
+/// is rejected; it is exactly the test `a5_bbox_to_cells` applies. Returns
+/// false for an invalid cell or box.
+#[no_mangle]
+pub extern "C" fn a5_cell_intersects_bbox(cell: u64, min_lon: f64, min_lat: f64, max_lon: f64, max_lat: f64) -> bool {
+    guard(|| polyfill::cell_intersects_bbox(cell, min_lon, min_lat, max_lon, max_lat).unwrap_or(false))
+}
+
+/// Deepest web map zoom accepted by `a5_cell_to_covering_tiles` and
+/// `a5_tile_to_cells`; tile coordinates then still fit in 32 bits.
+pub const A5_MAX_TILE_ZOOM: u32 = 30;
+
+/// Returns the sorted XYZ tiles at `zoom` that the boundary of `cell`
+/// overlaps, for serving cells through a slippy-map tile cache. A cell
+/// across the antimeridian gets tiles from both ends of each row, and
+/// cells beyond Web Mercator's ±85.05° limit fall in the top or bottom row,
+/// where the projection clamps them. `cell` is among the `a5_tile_to_cells`
+/// of every tile returned, at its own resolution. A zoom past
+/// `A5_MAX_TILE_ZOOM`, or one so deep that the cell spans over a million
+/// tiles, gives `OutOfBounds`. Free with `a5_free_tile_array_ptr`.
+#[no_mangle]
+pub extern "C" fn a5_cell_to_covering_tiles(cell: u64, zoom: u32) -> TileArray {
+    guard(|| tiles::cell_to_tiles(cell, zoom).into())
+}
+
+/// Returns the sorted cells at `resolution` that overlap the XYZ tile `z/x/y`,
+/// as `a5_bbox_to_cells` would for the tile's bounds. The top and bottom rows
+/// reach the poles, since Web Mercator clamps what lies beyond them into
+/// those rows. For TMS numbering pass `y` as `2^z - 1 - y`. A tile outside the
+/// grid of its zoom gives `OutOfBounds`.
+#[no_mangle]
+pub extern "C" fn a5_tile_to_cells(z: u32, x: u32, y: u32, resolution: i32) -> CellArray {
+    guard(|| cell_vec_result_to_c(validate_resolution(resolution).and_then(|_| tiles::tile_to_cells(Tile { z, x, y }, resolution))))
+}
+
+/// Streams the cells that `a5_polygon_to_cells` would return through `cb` as
+/// they are found, without collecting them. Cells arrive in no particular
+/// order, though each is reported exactly once. Iteration stops early, still
+/// returning `None`, once `cb` returns false.
+#[no_mangle]
+pub extern "C" fn a5_polygon_to_cells_foreach(ring: *const LonLatDegrees, ring_len: usize, resolution: i32, mode: FillMode, ctx: *mut std::os::raw::c_void, cb: A5CellCallback) -> A5ErrorCode {
+    guard(|| {
+        let walk = validate_resolution(resolution)
+            .and_then(|_| input_slice(ring, ring_len))
+            .and_then(polyfill::Polygon::new)
+            .and_then(|polygon| polyfill::for_each_cell(&polygon, resolution, mode, &mut |cell| cb(ctx, cell)));
+        match walk {
+            Ok(_) => A5ErrorCode::None,
+            Err(e) => map_error(e).0,
+        }
+    })
+}
+
+/// Returns the sorted cells at `resolution` whose center lies inside `outer`
+/// and inside none of the holes.
+///
+/// The holes are passed as one flattened vertex buffer, `holes`, split into
+/// `num_holes` rings by the lengths in `hole_lens`. Every ring follows the
+/// rules of `a5_polygon_to_cells`. Free the result with `a5_free_cell_array_ptr`.
+#[no_mangle]
+pub extern "C" fn a5_polygon_with_holes_to_cells(outer: *const LonLatDegrees, outer_len: usize, holes: *const LonLatDegrees, hole_lens: *const usize, num_holes: usize, resolution: i32) -> CellArray {
+    guard(|| {
+        let rings = input_slice(outer, outer_len).and_then(|outer| {
+            let hole_lens = input_slice(hole_lens, num_holes)?;
+            let vertices = input_slice(holes, hole_lens.iter().sum())?;
+            let mut rings = Vec::with_capacity(num_holes);
+            let mut start = 0;
+            for &len in hole_lens {
+                rings.push(&vertices[start..start + len]);
+                start += len;
+            }
+            Ok((outer, rings))
+        });
+        let cells = validate_resolution(resolution)
+            .and(rings)
+            .and_then(|(outer, rings)| polyfill::Polygon::with_holes(outer, &rings))
+            .and_then(|polygon| polyfill::polygon_to_cells(&polygon, resolution, FillMode::Center));
+        cell_vec_result_to_c(cells)
+    })
+}
+
+/// Pass `A5_DEFAULT_RESOLUTION` as `child_resolution` for the immediate children.
+/// Any other value is forwarded as the target resolution, so an out-of-range
+/// value is reported as an error rather than silently falling back. More than
+/// `A5_DESCENDANT_LIMIT` children give `OutOfBounds`; count them first with
+/// `a5_cell_to_children_size`.
+///
+/// The order is fixed: quintant by quintant, and by ascending id within each.
+/// Below a resolution 0 cell that is its face's quintant order, which starts
+/// at the face's first quintant rather than the lowest id, so the array is
+/// not always sorted; from resolution 1 on it is. `a5_cell_child` indexes
+/// this order. Use `a5_sort_cells` for numeric order.
+#[no_mangle]
+pub extern "C" fn a5_cell_to_children(index: u64, child_resolution: i32) -> CellArray {
+    guard(|| cell_vec_result_to_c(cell_to_children(index, child_resolution)))
+}
+
+/// `a5::cell_to_children` after checking the cell, the resolution and the
+/// number of children against `A5_DESCENDANT_LIMIT`.
+fn cell_to_children(cell: u64, child_resolution: i32) -> Result<Vec<u64>, Error> {
+    let (target, count) = children_size(cell, child_resolution)?;
+    limit_descendants(count as u64)?;
+    Ok(a5::cell_to_children(cell, Some(target))?)
+}
+
+/// The twelve resolution 0 cells, computed on first use. The set never
+/// changes, so it is kept for the life of the process; the `OnceLock` makes
+/// threads racing on the first call compute it once and all see that copy.
+pub(crate) fn res0_cells() -> Result<&'static [u64], Error> {
+    static RES0_CELLS: std::sync::OnceLock<Result<Vec<u64>, String>> = std::sync::OnceLock::new();
+    RES0_CELLS.get_or_init(a5::get_res0_cells).as_deref().map_err(|e| Error::from(e.clone()))
+}
+
+/// Returns a copy of the resolution 0 cells; see `a5_get_res0_cell_at` to
+/// read them without allocating.
+#[no_mangle]
+pub extern "C" fn a5_get_res0_cells() -> CellArray {
+    guard(|| cell_vec_result_to_c(res0_cells().map(<[u64]>::to_vec)))
+}
+
+/// Number of resolution 0 cells, the bound for `a5_get_res0_cell_at`.
+#[no_mangle]
+pub extern "C" fn a5_get_res0_cells_count() -> usize {
+    guard(|| res0_cells().map_or(0, <[u64]>::len))
+}
+
+/// Returns resolution 0 cell `i`, in the order of `a5_get_res0_cells`, or an
+/// `OutOfBounds` error if `i` is not below `a5_get_res0_cells_count`.
+#[no_mangle]
+pub extern "C" fn a5_get_res0_cell_at(i: usize) -> ResultU64 {
+    guard(|| {
+        res0_cells()
+            .and_then(|cells| cells.get(i).copied().ok_or_else(|| Error::new(A5ErrorCode::OutOfBounds, format!("index {} out of bounds for {} cells", i, cells.len()))))
+            .into()
+    })
+}
+
+#[no_mangle]
+pub extern "C" fn a5_compact(cells: *const u64, len: usize) -> CellArray {
+    guard(|| {
+        let cell_slice = match input_slice(cells, len) {
+            Ok([]) => return CellArray::empty(),
+            Ok(cells) => cells,
+            Err(e) => return cell_vec_result_to_c(Err(e)),
+        };
+        cell_vec_result_to_c(a5::compact(cell_slice).map_err(Error::from))
+    })
+}
+
+/// Validates `cells`, drops duplicates and any cell already covered by an
+/// ancestor in the set, then merges complete sibling groups. The resolution 0
+/// cells are never merged into the world cell.
+fn compact_cells(cells: &[u64]) -> Result<Vec<u64>, Error> {
+    let set: HashSet<u64> = cells.iter().copied().collect();
+    for &cell in &set {
+        validate_cell(cell)?;
+    }
+    let mut disjoint = Vec::with_capacity(set.len());
+    for &cell in &set {
+        let covered = (0..a5::get_resolution(cell)).any(|r| a5::cell_to_parent(cell, Some(r)).is_ok_and(|ancestor| set.contains(&ancestor)));
+        if !covered {
+            disjoint.push(cell);
+        }
+    }
+    let compacted = a5::compact(&disjoint)?;
+    if compacted.contains(&a5::WORLD_CELL) {
+        return res0_cells().map(<[u64]>::to_vec);
+    }
+    Ok(compacted)
+}
+
+/// Compacts a set of cells into the fewest cells covering exactly the same
+/// area, replacing every complete group of siblings with their parent,
+/// recursively. Input may contain duplicates and mixed resolutions, including
+/// cells nested inside others; the output is sorted and deduplicated.
+#[no_mangle]
+pub extern "C" fn a5_compact_cells(cells: *const u64, len: usize) -> CellArray {
+    guard(|| {
+        let cell_slice = match input_slice(cells, len) {
+            Ok([]) => return CellArray::empty(),
+            Ok(cells) => cells,
+            Err(e) => return cell_vec_result_to_c(Err(e)),
+        };
+        cell_vec_result_to_c(compact_cells(cell_slice))
+    })
+}
+
+/// Compacts like `a5_compact_cells` but never past `min_resolution`: merging
+/// stops at cells of that resolution, and input cells coarser than it are
+/// expanded to it, so every output cell is at `min_resolution` or finer. With
+/// `min_resolution` 0 this is full compaction. The output is sorted, and an
+/// expansion past `A5_DESCENDANT_LIMIT` cells gives `OutOfBounds`.
+#[no_mangle]
+pub extern "C" fn a5_compact_cells_to(cells: *const u64, len: usize, min_resolution: i32) -> CellArray {
+    guard(|| {
+        if let Err(e) = validate_resolution(min_resolution) {
+            return cell_vec_result_to_c(Err(e));
+        }
+        let cell_slice = match input_slice(cells, len) {
+            Ok([]) => return CellArray::empty(),
+            Ok(cells) => cells,
+            Err(e) => return cell_vec_result_to_c(Err(e)),
+        };
+        cell_vec_result_to_c(compact_cells_to(cell_slice, min_resolution))
+    })
+}
+
+/// `compact_cells`, then expanding any cell coarser than `min_resolution` to it.
+fn compact_cells_to(cells: &[u64], min_resolution: i32) -> Result<Vec<u64>, Error> {
+    let (coarse, mut cells): (Vec<u64>, Vec<u64>) = compact_cells(cells)?.into_iter().partition(|&cell| a5::get_resolution(cell) < min_resolution);
+    limit_descendants(cells.len() as u64 + uncompacted_count(&coarse, min_resolution)?)?;
+    cells.extend(a5::uncompact(&coarse, min_resolution)?);
+    cells.sort_unstable();
+    Ok(cells)
+}
+
+/// How many cells `cells` expand to at `target_resolution`, after checking
+/// each cell and the resolution.
+fn uncompacted_count(cells: &[u64], target_resolution: i32) -> Result<u64, Error> {
+    validate_resolution(target_resolution)?;
+    cells.iter().try_fold(0u64, |total, &cell| Ok(total.saturating_add(children_size(cell, target_resolution)?.1 as u64)))
+}
+
+/// `a5::uncompact` after checking the cells and that the result stays within
+/// `A5_DESCENDANT_LIMIT`, so an oversized request fails instead of aborting
+/// on allocation.
+fn uncompact_cells(cells: &[u64], target_resolution: i32) -> Result<Vec<u64>, Error> {
+    limit_descendants(uncompacted_count(cells, target_resolution)?)?;
+    Ok(a5::uncompact(cells, target_resolution)?)
+}
+
+/// Expands every cell down to `target_resolution`, returning the sorted and
+/// deduplicated cells at that resolution. A cell already finer than the
+/// target fails the whole call with an error rather than being skipped, and
+/// more than `A5_DESCENDANT_LIMIT` cells give `OutOfBounds`.
+#[no_mangle]
+pub extern "C" fn a5_uncompact_cells(cells: *const u64, len: usize, target_resolution: i32) -> CellArray {
+    guard(|| {
+        if let Err(e) = validate_resolution(target_resolution) {
+            return cell_vec_result_to_c(Err(e));
+        }
+        let cell_slice = match input_slice(cells, len) {
+            Ok([]) => return CellArray::empty(),
+            Ok(cells) => cells,
+            Err(e) => return cell_vec_result_to_c(Err(e)),
+        };
+        let expanded = uncompact_cells(cell_slice, target_resolution).map(|mut cells| {
+            cells.sort_unstable();
+            cells.dedup();
+            cells
+        });
+        cell_vec_result_to_c(expanded)
+    })
+}
+
+/// Sorts `len` cells in place by numeric id. Below resolution 30 a cell id is
+/// its face, quintant and Hilbert index, so this is face by face Hilbert
+/// order, keeping nearby ids nearby on the ground. A null `cells` with a
+/// nonzero `len` gives `NullPointer` and sorts nothing.
+#[no_mangle]
+pub extern "C" fn a5_sort_cells(cells: *mut u64, len: usize) -> A5ErrorCode {
+    guard(|| match input_slice_mut(cells, len) {
+        Ok(cells) => {
+            cells.sort_unstable();
+            A5ErrorCode::None
+        }
+        Err(e) => map_error(e).0,
+    })
+}
+
+/// Sorts `len` cells in place as `a5_sort_cells` does and moves each distinct
+/// id to the front once, returning how many there are; entries past that are
+/// left in an unspecified order. A null `cells` with a nonzero `len` returns
+/// `usize::MAX`, which no real count reaches.
+#[no_mangle]
+pub extern "C" fn a5_dedup_cells(cells: *mut u64, len: usize) -> usize {
+    guard(|| {
+        let Ok(cells) = input_slice_mut(cells, len) else {
+            return usize::MAX;
+        };
+        cells.sort_unstable();
+        let mut unique = 0;
+        for i in 0..cells.len() {
+            if unique == 0 || cells[i] != cells[unique - 1] {
+                cells[unique] = cells[i];
+                unique += 1;
+            }
+        }
+        unique
+    })
+}
+
+/// Expands every cell down to `target_resolution`, in the `a5` crate's order
+/// and keeping duplicates; `a5_uncompact_cells` sorts and deduplicates.
+/// Invalid cells and resolutions are errors, and more than
+/// `A5_DESCENDANT_LIMIT` cells give `OutOfBounds`.
+#[no_mangle]
+pub extern "C" fn a5_uncompact(cells: *const u64, len: usize, target_resolution: i32) -> CellArray {
+    guard(|| {
+        let cell_slice = match input_slice(cells, len) {
+            Ok([]) => return CellArray::empty(),
+            Ok(cells) => cells,
+            Err(e) => return cell_vec_result_to_c(Err(e)),
+        };
+        cell_vec_result_to_c(uncompact_cells(cell_slice, target_resolution))
+    })
+}
+
+/// Frees a single string returned by this library: the `error` of any result
+/// struct, or the strings from `a5_cell_to_hex_string`, `a5_u64_to_hex`,
+/// `a5_cells_to_geojson` and `a5_cell_to_wkt`. Null is ignored, so an error
+/// can be freed on its own when a result carries no data.
+#[no_mangle]
+pub extern "C" fn a5_free_cstring(s: *mut std::os::raw::c_char) {
+    guard(|| {
+        if !s.is_null() {
+            unsafe { drop(CString::from_raw(s)); }
+        }
+    })
+}
+
+/// Same as `a5_free_cstring`, kept for existing callers.
+#[no_mangle]
+pub extern "C" fn a5_free_string(ptr: *mut std::os::raw::c_char) {
+    a5_free_cstring(ptr)
+}
+
+/// Borrows a nul-terminated C string argument as UTF-8.
+/// Borrows a C string; null is `NullPointer` and non-UTF-8 `InvalidCell`.
+fn str_from_c<'a>(ptr: *const std::os::raw::c_char, what: &str) -> Result<&'a str, Error> {
+    if ptr.is_null() {
+        return Err(Error::new(A5ErrorCode::NullPointer, format!("{} is null", what)));
+    }
+    unsafe { CStr::from_ptr(ptr) }.to_str().map_err(|e| Error::new(A5ErrorCode::InvalidCell, e.to_string()))
+}
+
+#[no_mangle]
+pub extern "C" fn a5_hex_to_u64(hex: *const std::os::raw::c_char) -> ResultU64 {
+    guard(|| match str_from_c(hex, "hex string") {
+        Ok(hex_str) => a5::hex_to_u64(hex_str).into(),
+        Err(e) => ResultU64::err(e.code, e.message),
+    })
+}
+
+/// Parses a cell id written as at most 16 hex digits, with an optional `0x` prefix.
+fn parse_hex_cell(s: &str) -> Result<u64, String> {
+    let digits = s.strip_prefix("0x").or_else(|| s.strip_prefix("0X")).unwrap_or(s);
+    if digits.is_empty() {
+        return Err(format!("invalid hex cell id '{}': no digits", s));
+    }
+    if digits.len() > 16 {
+        return Err(format!("invalid hex cell id '{}': more than 16 digits", s));
+    }
+    if !digits.bytes().all(|b| b.is_ascii_hexdigit()) {
+        return Err(format!("invalid hex cell id '{}': non-hex character", s));
+    }
+    u64::from_str_radix(digits, 16).map_err(|e| format!("invalid hex cell id '{}': {}", s, e))
+}
+
+/// Formats `cell` as a lowercase, zero-padded 16 character hex string.
+/// Free the result with `a5_free_cstring`.
+#[no_mangle]
+pub extern "C" fn a5_cell_to_hex_string(cell: u64) -> *mut std::os::raw::c_char {
+    guard(|| string_ptr(CString::new(format!("{:016x}", cell)).ok()))
+}
+
+/// Describes `cell` for diagnostics as `A5[res=2, face=3, path=4/1]`, its
+/// resolution, face and the digits from `a5_cell_get_digit` for each level,
+/// or `A5[invalid: 0x...]` with the hex id for an invalid cell. The format is
+/// meant for people and may change. Free the result with `a5_free_cstring`.
+#[no_mangle]
+pub extern "C" fn a5_cell_to_debug_string(cell: u64) -> *mut std::os::raw::c_char {
+    guard(|| {
+        let text = if a5_is_valid_cell(cell) {
+            let resolution = a5::get_resolution(cell);
+            let path: Vec<String> = (1..=resolution).map(|level| a5_cell_get_digit(cell, level).to_string()).collect();
+            format!("A5[res={}, face={}, path={}]", resolution, a5_cell_get_base(cell), path.join("/"))
+        } else {
+            format!("A5[invalid: 0x{:016x}]", cell)
+        };
+        string_ptr(CString::new(text).ok())
+    })
+}
+
+/// Parses a hex cell id as produced by `a5_cell_to_hex_string`. A leading `0x`
+/// is accepted; empty, overlong or non-hex input is `InvalidCell` and a null
+/// string `NullPointer`.
+#[no_mangle]
+pub extern "C" fn a5_hex_string_to_cell(s: *const std::os::raw::c_char) -> ResultU64 {
+    guard(|| match str_from_c(s, "hex string") {
+        Ok(s) => match parse_hex_cell(s) {
+            Ok(cell) => ResultU64::ok(cell),
+            Err(e) => ResultU64::err(A5ErrorCode::InvalidCell, e),
+        },
+        Err(e) => ResultU64::err(e.code, e.message),
+    })
+}
+
+/// Checks a hex cell id strictly, telling apart why it is rejected:
+/// `NullPointer` for a null string, `NotHex` for a non-UTF-8 string or any
+/// character that is not a hex digit,
+/// `WrongLength` unless there are exactly 16 digits, as
+/// `a5_cell_to_hex_string` writes, and `InvalidCell` for a well-formed id
+/// that is not a cell. A leading `0x` is allowed. Returns `None` for a valid
+/// cell id.
+#[no_mangle]
+pub extern "C" fn a5_validate_hex_string(s: *const std::os::raw::c_char) -> A5ErrorCode {
+    guard(|| {
+        if s.is_null() {
+            return A5ErrorCode::NullPointer;
+        }
+        let Ok(s) = str_from_c(s, "hex string") else {
+            return A5ErrorCode::NotHex;
+        };
+        let digits = s.strip_prefix("0x").or_else(|| s.strip_prefix("0X")).unwrap_or(s);
+        if !digits.bytes().all(|b| b.is_ascii_hexdigit()) {
+            A5ErrorCode::NotHex
+        } else if digits.len() != 16 {
+            A5ErrorCode::WrongLength
+        } else if !parse_hex_cell(digits).is_ok_and(|cell| a5_is_valid_cell(cell)) {
+            A5ErrorCode::InvalidCell
+        } else {
+            A5ErrorCode::None
+        }
+    })
+}
+
+fn parse_decimal_cell(s: &str) -> Result<u64, String> {
+    if s.is_empty() {
+        return Err("invalid decimal cell id '': no digits".to_string());
+    }
+    if !s.bytes().all(|b| b.is_ascii_digit()) {
+        return Err(format!("invalid decimal cell id '{}': non-digit character", s));
+    }
+    s.parse::<u64>().map_err(|_| format!("invalid decimal cell id '{}': exceeds {}", s, u64::MAX))
+}
+
+/// Formats `cell` as a base 10 string, for environments that cannot carry a
+/// full 64-bit integer. Free the result with `a5_free_cstring`.
+#[no_mangle]
+pub extern "C" fn a5_cell_to_decimal_string(cell: u64) -> *mut std::os::raw::c_char {
+    guard(|| string_ptr(CString::new(cell.to_string()).ok()))
+}
+
+/// Parses a base 10 cell id as produced by `a5_cell_to_decimal_string`. Only
+/// ASCII digits are accepted; empty input and values above `u64::MAX` are
+/// rejected as `InvalidCell` rather than wrapped; a null string is
+/// `NullPointer`.
+#[no_mangle]
+pub extern "C" fn a5_decimal_string_to_cell(s: *const std::os::raw::c_char) -> ResultU64 {
+    guard(|| match str_from_c(s, "decimal string") {
+        Ok(s) => match parse_decimal_cell(s) {
+            Ok(cell) => ResultU64::ok(cell),
+            Err(e) => ResultU64::err(A5ErrorCode::InvalidCell, e),
+        },
+        Err(e) => ResultU64::err(e.code, e.message),
+    })
+}
+
+/// Writes `cell` as 8 big-endian bytes into `out`, so that the byte strings of
+/// cells sort in the same order as their numeric ids. Does nothing if `out`
+/// is null.
+#[no_mangle]
+pub extern "C" fn a5_cell_to_bytes(cell: u64, out: *mut u8) {
+    guard(|| {
+        if !out.is_null() {
+            unsafe { std::ptr::copy_nonoverlapping(cell.to_be_bytes().as_ptr(), out, 8); }
+        }
+    })
+}
+
+/// Reads a cell from the 8 big-endian bytes at `bytes`, as written by
+/// `a5_cell_to_bytes`. Returns `A5_INVALID_CELL` if `bytes` is null.
+#[no_mangle]
+pub extern "C" fn a5_cell_from_bytes(bytes: *const u8) -> u64 {
+    guard(|| {
+        if bytes.is_null() {
+            return A5_INVALID_CELL;
+        }
+        let mut buf = [0u8; 8];
+        unsafe { std::ptr::copy_nonoverlapping(bytes, buf.as_mut_ptr(), 8); }
+        u64::from_be_bytes(buf)
+    })
+}
+
+#[no_mangle]
+pub extern "C" fn a5_u64_to_hex(value: u64) -> *mut std::os::raw::c_char {
+    guard(|| {
+        let hex = a5::u64_to_hex(value);
+        CString::new(hex).unwrap().into_raw()
+    })
+}
+
+#[no_mangle]
+pub extern "C" fn a5_get_num_children(parent_res: i32, child_res: i32) -> usize {
+    guard(|| a5::get_num_children(parent_res, child_res))
+}
+
+#[no_mangle]
+pub extern "C" fn a5_cell_to_spherical(cell: u64) -> ResultSpherical {
+    guard(|| {
+        match a5::cell_to_spherical(cell) {
+            Ok(sph) => ResultSpherical { theta: sph.theta.get(), phi: sph.phi.get(), error: std::ptr::null_mut(), code: A5ErrorCode::None },
+            Err(e) => {
+                let (code, message) = map_error(e);
+                ResultSpherical { theta: 0.0, phi: 0.0, error: string_ptr(message), code }
+            }
+        }
+    })
+}
+
+#[no_mangle]
+pub extern "C" fn a5_spherical_cap(cell_id: u64, radius: f64) -> CellArray {
+    guard(|| cell_vec_result_to_c(a5::spherical_cap(cell_id, radius).map_err(Error::from)))
+}
+
+/// Great-circle distance in meters between two points in degrees, measured on
+/// the authalic sphere of radius 6 371 007.2 m that A5 cells are equal-area on.
+#[no_mangle]
+pub extern "C" fn a5_haversine_distance(lon1: f64, lat1: f64, lon2: f64, lat2: f64) -> f64 {
+    guard(|| geometry::haversine_distance(lon1, lat1, lon2, lat2))
+}
+
+/// Great-circle distance in meters between the centers of two cells, as given by
+/// `a5_cell_to_lon_lat`. Returns NaN if either cell is invalid.
+#[no_mangle]
+pub extern "C" fn a5_great_circle_distance_cells(a: u64, b: u64) -> f64 {
+    guard(|| {
+        if !a5_is_valid_cell(a) || !a5_is_valid_cell(b) {
+            return f64::NAN;
+        }
+        match (a5::cell_to_lonlat(a), a5::cell_to_lonlat(b)) {
+            (Ok(p), Ok(q)) => geometry::haversine_distance(p.longitude(), p.latitude(), q.longitude(), q.latitude()),
+            _ => f64::NAN,
+        }
+    })
+}
+
+/// Initial compass bearing in degrees clockwise from true north, in
+/// [0, 360), along the great circle from the first point to the second. NaN
+/// if the points coincide, are antipodal or are not finite.
+#[no_mangle]
+pub extern "C" fn a5_bearing(lon1: f64, lat1: f64, lon2: f64, lat2: f64) -> f64 {
+    guard(|| geometry::initial_bearing(lon1, lat1, lon2, lat2))
+}
+
+/// `a5_bearing` from the center of `from` to the center of `to`. NaN if
+/// either cell is invalid or they are the same cell.
+#[no_mangle]
+pub extern "C" fn a5_cell_bearing(from: u64, to: u64) -> f64 {
+    guard(|| {
+        if !a5_is_valid_cell(from) || !a5_is_valid_cell(to) {
+            return f64::NAN;
+        }
+        match (a5::cell_to_lonlat(from), a5::cell_to_lonlat(to)) {
+            (Ok(p), Ok(q)) => geometry::initial_bearing(p.longitude(), p.latitude(), q.longitude(), q.latitude()),
+            _ => f64::NAN,
+        }
+    })
+}
+
+/// Checks that a grid distance argument is non-negative and at most
+/// `A5_GRID_DISTANCE_LIMIT`, since every walk costs time and memory in `k`.
+fn validate_k(k: i32) -> Result<usize, Error> {
+    let steps = usize::try_from(k).map_err(|_| Error::new(A5ErrorCode::OutOfBounds, format!("k {} out of bounds: must be >= 0", k)))?;
+    if steps as u64 > A5_GRID_DISTANCE_LIMIT {
+        return Err(Error::new(A5ErrorCode::OutOfBounds, format!("k {} out of bounds: exceeds {} steps", k, A5_GRID_DISTANCE_LIMIT)));
+    }
+    Ok(steps)
+}
+
+/// Returns every cell within `k` edge-steps of `cell_id`, including the origin.
+///
+/// Traversal crosses quintant and dodecahedron face boundaries seamlessly, so a
+/// disk spanning two faces has the same shape as one inside a face. The result
+/// is sorted and compacted: groups of siblings that are fully inside the disk
+/// are replaced by their parent, so uncompact to the origin's resolution for a
+/// uniform set. `k = 0` returns the origin; a negative `k`, or one beyond
+/// `A5_GRID_DISTANCE_LIMIT`, is an error.
+#[no_mangle]
+pub extern "C" fn a5_grid_disk(cell_id: u64, k: i32) -> CellArray {
+    guard(|| {
+        let disk = validate_cell(cell_id).and_then(|_| validate_k(k)).and_then(|k| grid_disk(cell_id, k));
+        cell_vec_result_to_c(disk)
+    })
+}
+
+/// The compacted disk of `a5_grid_disk`. The `a5` traversal has no neighbors
+/// below its first Hilbert resolution, so disks of resolution 0 and 1 cells
+/// are walked ring by ring over `edge_neighbors` instead.
+fn grid_disk(cell: u64, k: usize) -> Result<Vec<u64>, Error> {
+    if a5::get_resolution(cell) >= a5::core::serialization::FIRST_HILBERT_RESOLUTION {
+        return Ok(a5::grid_disk(cell, k)?);
+    }
+    let mut disk = HashSet::from([cell]);
+    let (mut previous, mut frontier) = (HashSet::new(), HashSet::from([cell]));
+    for _ in 0..k {
+        let next = next_ring(&previous, &frontier)?;
+        if next.is_empty() {
+            break;
+        }
+        disk.extend(&next);
+        previous = std::mem::replace(&mut frontier, next);
+    }
+    compact_cells(&disk.into_iter().collect::<Vec<_>>())
+}
+
+/// Writes the number of cells in the disk `a5_grid_disk` returns, counted at
+/// the origin's resolution, to `out_count` and the sum of their
+/// `a5_cell_exact_area`s in square meters to `out_area_m2`, enumerating the
+/// disk once. A5 cells are all pentagons of nearly equal area, so the sum is
+/// close to the count times `a5_cell_area`, but it tracks the small
+/// differences between cells. `k` is limited as for `a5_grid_ring`, beyond
+/// which `OutOfBounds` is returned. Returns `NullPointer` if either out
+/// pointer is null.
+#[no_mangle]
+pub extern "C" fn a5_grid_disk_area(origin: u64, k: i32, out_area_m2: *mut f64, out_count: *mut u64) -> A5ErrorCode {
+    guard(|| {
+        if out_area_m2.is_null() || out_count.is_null() {
+            return A5ErrorCode::NullPointer;
+        }
+        let measured = validate_cell(origin).and_then(|_| validate_k(k)).and_then(|k| {
+            let disk = a5::uncompact(&grid_disk(origin, k)?, a5::get_resolution(origin))?;
+            let area = disk.iter().map(|&cell| exact_area(cell)).sum::<Result<f64, Error>>()?;
+            Ok((area, disk.len() as u64))
+        });
+        match measured {
+            Ok((area, count)) => {
+                unsafe {
+                    *out_area_m2 = area;
+                    *out_count = count;
+                }
+                A5ErrorCode::None
+            }
+            Err(e) => map_error(e).0,
+        }
+    })
+}
+
+/// Edge neighbors of a resolution 0 or 1 cell, which the `a5` traversal does not
+/// cover, derived from the adjacency of the dodecahedron faces and quintants.
+fn coarse_neighbors(cell: u64) -> Result<Vec<u64>, Error> {
+    use a5::core::face_adjacency::FACE_ADJACENCY;
+    use a5::core::origin::{get_origins, quintant_to_segment, segment_to_quintant};
+    use a5::core::serialization::{deserialize, serialize};
+
+    let decoded = deserialize(cell)?;
+    let origins = get_origins();
+    let origin = &origins[decoded.origin_id as usize];
+    let to_cell = |origin_id: u8, segment: usize| {
+        serialize(&a5::A5Cell { origin_id, segment, s: 0, resolution: decoded.resolution })
+    };
+
+    let mut neighbors = Vec::new();
+    if decoded.resolution == 0 {
+        for &(adjacent_origin, _) in &FACE_ADJACENCY[origin.id as usize] {
+            neighbors.push(to_cell(adjacent_origin, 0)?);
+        }
+    } else {
+        // A quintant borders the quintants on either side and, across its base
+        // edge, a quintant of the adjacent face.
+        let (quintant, _) = segment_to_quintant(decoded.segment, origin);
+        for side in [(quintant + 1) % 5, (quintant + 4) % 5] {
+            neighbors.push(to_cell(origin.id, quintant_to_segment(side, origin).0)?);
+        }
+        let (adjacent_origin, adjacent_quintant) = FACE_ADJACENCY[origin.id as usize][quintant];
+        let adjacent = &origins[adjacent_origin as usize];
+        neighbors.push(to_cell(adjacent_origin, quintant_to_segment(adjacent_quintant, adjacent).0)?);
+    }
+    neighbors.sort_unstable();
+    neighbors.dedup();
+    Ok(neighbors)
+}
+
+/// Returns the cells sharing an edge with `cell`, sorted by cell id.
+///
+/// A5 has no hexagons: every cell at resolution 0 and from resolution 2 on is a
+/// pentagon with exactly five edge neighbors, including cells touching a
+/// dodecahedron vertex. Resolution 1 cells are the triangular quintants of a
+/// face and have three.
+#[no_mangle]
+pub extern "C" fn a5_cell_to_neighbors(cell: u64) -> CellArray {
+    guard(|| cell_vec_result_to_c(validate_cell(cell).and_then(|_| edge_neighbors(cell))))
+}
+
+/// Returns the edge neighbors of `cell`, the same cells as
+/// `a5_cell_to_neighbors`, in clockwise order: sorted by the initial bearing
+/// from the cell's center to each neighbor's center, measured clockwise from
+/// north, so position 0 is the first neighbor at or clockwise of due north.
+/// Each neighbor shares exactly one edge with the cell, so position `i` also
+/// names the `i`-th edge clockwise, and a direction index maps to a neighbor.
+///
+/// A5 has no hexagons, so there is no missing edge to place: a pentagon fills
+/// all five positions, and a resolution 1 quintant its three. The resolution
+/// 0 cell centered on the north pole measures bearings as though facing the
+/// pole from its center's meridian.
+#[no_mangle]
+pub extern "C" fn a5_cell_to_neighbors_ordered(cell: u64) -> CellArray {
+    guard(|| {
+        let ordered = validate_cell(cell).and_then(|_| {
+            let center = a5::cell_to_lonlat(cell)?;
+            let mut neighbors = edge_neighbors(cell)?
+                .into_iter()
+                .map(|neighbor| {
+                    let other = a5::cell_to_lonlat(neighbor)?;
+                    Ok((geometry::initial_bearing(center.longitude(), center.latitude(), other.longitude(), other.latitude()), neighbor))
+                })
+                .collect::<Result<Vec<(f64, u64)>, Error>>()?;
+            neighbors.sort_unstable_by(|a, b| a.0.total_cmp(&b.0).then(a.1.cmp(&b.1)));
+            Ok(neighbors.into_iter().map(|(_, neighbor)| neighbor).collect())
+        });
+        cell_vec_result_to_c(ordered)
+    })
+}
+
+/// Returns true if `cell` is a pentagon. Unlike hexagonal grids, A5 has no
+/// hexagons: every cell at resolution 0 and from resolution 2 on is a
+/// pentagon, including those touching a dodecahedron vertex, so this is only
+/// false for the triangular resolution 1 quintants and for invalid cells.
+/// Neighbor counts follow from it: five edge neighbors for a pentagon, three
+/// for a quintant.
+#[no_mangle]
+pub extern "C" fn a5_is_pentagon(cell: u64) -> bool {
+    guard(|| a5_is_valid_cell(cell) && a5::get_resolution(cell) != 1)
+}
+
+/// Returns which of the 12 dodecahedron faces, numbered 0 to 11 like the
+/// resolution 0 cells, `cell` lies in.
+#[no_mangle]
+pub extern "C" fn a5_cell_to_face(cell: u64) -> ResultU64 {
+    guard(|| validate_cell(cell).and_then(|_| a5::core::serialization::deserialize(cell).map_err(Error::from)).map(|decoded| decoded.origin_id as u64).into())
+}
+
+/// Returns the sorted pentagon cells at `resolution`, as `a5_is_pentagon`
+/// defines them. Since every A5 cell other than a quintant is a pentagon, this
+/// is every cell at the resolution, `a5_get_num_cells(resolution)` of them,
+/// and nothing at resolution 1. More than `A5_DESCENDANT_LIMIT` cells, past
+/// resolution 12, give `OutOfBounds`; use `a5_get_num_cells` to count them,
+/// or `a5_uncompact` on part of the sphere.
+#[no_mangle]
+pub extern "C" fn a5_get_pentagon_cells(resolution: i32) -> CellArray {
+    guard(|| {
+        let cells = validate_resolution(resolution).and_then(|_| match resolution {
+            1 => Ok(Vec::new()),
+            _ if a5::get_num_cells(resolution) > A5_DESCENDANT_LIMIT => {
+                Err(Error::new(A5ErrorCode::OutOfBounds, format!("pentagon cells out of bounds: {} cells exceed the limit of {}", a5::get_num_cells(resolution), A5_DESCENDANT_LIMIT)))
+            }
+            _ => a5::uncompact(res0_cells()?, resolution).map(|mut cells| {
+                cells.sort_unstable();
+                cells
+            }).map_err(Error::from),
+        });
+        cell_vec_result_to_c(cells)
+    })
+}
+
+fn edge_neighbors(cell: u64) -> Result<Vec<u64>, Error> {
+    if a5::get_resolution(cell) < a5::core::serialization::FIRST_HILBERT_RESOLUTION {
+        coarse_neighbors(cell)
+    } else {
+        Ok(a5::traversal::global_neighbors::get_global_cell_neighbors(cell, true))
+    }
+}
+
+/// Returns true if `a` and `b` are distinct cells of the same resolution that
+/// share an edge. Cells touching only at a corner are not neighbors, matching
+/// `a5_cell_to_neighbors`. Mismatched resolutions, identical cells and invalid
+/// cells all give false rather than an error.
+///
+/// Cells whose centers are too far apart to touch are rejected before any
+/// neighbor is computed: neighbor centers are never more than about 1.3 times
+/// the cell edge scale `sqrt(area)` apart, and the cut-off allows twice that.
+#[no_mangle]
+pub extern "C" fn a5_are_neighbors(a: u64, b: u64) -> bool {
+    guard(|| {
+        if a == b || !a5_is_valid_cell(a) || !a5_is_valid_cell(b) || a5::get_resolution(a) != a5::get_resolution(b) {
+            return false;
+        }
+        let resolution = a5::get_resolution(a);
+        if resolution >= a5::core::serialization::FIRST_HILBERT_RESOLUTION {
+            let (Ok(p), Ok(q)) = (a5::cell_to_lonlat(a), a5::cell_to_lonlat(b)) else {
+                return false;
+            };
+            if geometry::haversine_distance(p.longitude(), p.latitude(), q.longitude(), q.latitude()) > 2.0 * a5::cell_area(resolution).sqrt() {
+                return false;
+            }
+        }
+        edge_neighbors(a).is_ok_and(|neighbors| neighbors.contains(&b))
+    })
+}
+
+/// A cell's position on the triangular lattice A5 lays over each quintant,
+/// where every cell is one triangle: `row` counts rows out from the face
+/// center and `col` runs along a row. Returns the decoded cell too.
+fn lattice_position(cell: u64) -> Result<(a5::A5Cell, i32, i32), Error> {
+    use a5::core::origin::{get_origins, segment_to_quintant};
+    use a5::core::serialization::{deserialize, FIRST_HILBERT_RESOLUTION};
+    validate_cell(cell)?;
+    let decoded = deserialize(cell)?;
+    if decoded.resolution < FIRST_HILBERT_RESOLUTION {
+        return Err(Error::new(A5ErrorCode::InvalidResolution, format!("resolution {} cells have no local grid; it starts at resolution {}", decoded.resolution, FIRST_HILBERT_RESOLUTION)));
+    }
+    let (_, orientation) = segment_to_quintant(decoded.segment, &get_origins()[decoded.origin_id as usize]);
+    let hilbert_resolution = (decoded.resolution - FIRST_HILBERT_RESOLUTION + 1) as usize;
+    let anchor = a5::lattice::s_to_anchor(decoded.s, hilbert_resolution, orientation);
+    let triple = a5::lattice::anchor_to_triple(&anchor);
+    Ok((decoded, triple.y, triple.x - triple.z))
+}
+
+/// Writes the local grid coordinates of `cell` relative to `origin`, which
+/// must be at the same resolution, to `out_i` and `out_j`. The origin is at
+/// `(0, 0)`; `i` runs along the lattice rows and `j` across them.
+///
+/// Local coordinates come from the Hilbert lattice inside one quintant (a
+/// fifth of a dodecahedron face), so they are only defined when `cell` lies
+/// in the same quintant as `origin`. Any other cell, even an edge neighbor
+/// across the quintant boundary, gives `OutOfBounds`, as do resolution 0 and
+/// 1, which have no lattice (`InvalidResolution`). Returns `NullPointer`
+/// if either out pointer is null.
+#[no_mangle]
+pub extern "C" fn a5_cell_to_local_ij(origin: u64, cell: u64, out_i: *mut i32, out_j: *mut i32) -> A5ErrorCode {
+    guard(|| {
+        if out_i.is_null() || out_j.is_null() {
+            return A5ErrorCode::NullPointer;
+        }
+        let local = lattice_position(origin).and_then(|(o, o_row, o_col)| {
+            let (c, row, col) = lattice_position(cell)?;
+            if c.resolution != o.resolution {
+                return Err(Error::new(A5ErrorCode::InvalidResolution, format!("resolution mismatch: cells are at resolution {} and {}", o.resolution, c.resolution)));
+            }
+            if (c.origin_id, c.segment) != (o.origin_id, o.segment) {
+                return Err(Error::new(A5ErrorCode::OutOfBounds, "cell out of bounds: outside the quintant of the local grid"));
+            }
+            Ok((col - o_col, row - o_row))
+        });
+        match local {
+            Ok((i, j)) => {
+                unsafe {
+                    *out_i = i;
+                    *out_j = j;
+                }
+                A5ErrorCode::None
+            }
+            Err(e) => map_error(e).0,
+        }
+    })
+}
+
+/// Returns the cell at local grid coordinates `(i, j)` relative to `origin`,
+/// the inverse of `a5_cell_to_local_ij`. Coordinates falling outside the
+/// origin's quintant give `OutOfBounds`.
+#[no_mangle]
+pub extern "C" fn a5_local_ij_to_cell(origin: u64, i: i32, j: i32) -> ResultU64 {
+    guard(|| {
+        lattice_position(origin).and_then(|(o, o_row, o_col)| {
+            use a5::core::origin::{get_origins, segment_to_quintant};
+            let (row, col) = (o_row.checked_add(j), o_col.checked_add(i));
+            let hilbert_resolution = (o.resolution - a5::core::serialization::FIRST_HILBERT_RESOLUTION + 1) as usize;
+            let out_of_bounds = || Error::new(A5ErrorCode::OutOfBounds, format!("local ij ({}, {}) out of bounds: outside the quintant of the local grid", i, j));
+            let (Some(row), Some(col)) = (row, col) else {
+                return Err(out_of_bounds());
+            };
+            // A row and column fix the triple, whose coordinates sum to 0 or 1
+            let parity = (col - row).rem_euclid(2);
+            let x = (parity - row + col) / 2;
+            let triple = a5::lattice::Triple::new(x, row, x - col);
+            if !a5::lattice::triple_in_bounds(&triple, (1 << hilbert_resolution) - 1) {
+                return Err(out_of_bounds());
+            }
+            let (_, orientation) = segment_to_quintant(o.segment, &get_origins()[o.origin_id as usize]);
+            let s = a5::lattice::triple_to_s(&triple, hilbert_resolution, orientation).ok_or_else(out_of_bounds)?;
+            a5::core::serialization::serialize(&a5::A5Cell { s, ..o }).map_err(Error::from)
+        }).into()
+    })
+}
+
+/// Largest number of points `a5_cells_along_geodesic` samples along a path.
+pub const A5_GEODESIC_SAMPLE_LIMIT: u64 = 10_000_000;
+
+fn cells_along_geodesic(start: geometry::Point, end: geometry::Point, resolution: i32) -> Result<Vec<u64>, Error> {
+    validate_resolution(resolution)?;
+    validate_lonlat(start.0, start.1)?;
+    validate_lonlat(end.0, end.1)?;
+    let (length, point) = geometry::geodesic(start, end).ok_or_else(|| Error::new(A5ErrorCode::InvalidCoordinate, "coordinates are antipodal, so the geodesic between them is not unique"))?;
+    let cell_at = |t: f64| {
+        let (lon, lat) = point(t);
+        a5::lonlat_to_cell(a5::LonLat::new(lon, lat), resolution).map_err(Error::from)
+    };
+
+    // Samples a quarter of the cell scale apart cannot step over a whole
+    // cell; bisection then finds any cell clipped between two samples
+    let scale = a5::cell_area(resolution).sqrt();
+    let steps = (length / (0.25 * scale)).ceil().max(1.0);
+    if steps > A5_GEODESIC_SAMPLE_LIMIT as f64 {
+        return Err(Error::new(A5ErrorCode::OutOfBounds, format!("geodesic out of bounds: needs more than {} samples", A5_GEODESIC_SAMPLE_LIMIT)));
+    }
+    let steps = steps as u64;
+    let tolerance = 1e-6 * scale / length.max(scale);
+
+    fn bisect(cell_at: &dyn Fn(f64) -> Result<u64, Error>, (t0, c0): (f64, u64), (t1, c1): (f64, u64), tolerance: f64, out: &mut Vec<u64>) -> Result<(), Error> {
+        if c0 == c1 || (t1 - t0 < tolerance && edge_neighbors(c0)?.contains(&c1)) || t1 - t0 < 1e-15 {
+            return Ok(());
+        }
+        let mid = (t0 + t1) / 2.0;
+        let c = cell_at(mid)?;
+        bisect(cell_at, (t0, c0), (mid, c), tolerance, out)?;
+        out.push(c);
+        bisect(cell_at, (mid, c), (t1, c1), tolerance, out)
+    }
+
+    let mut path = vec![cell_at(0.0)?];
+    let mut previous = (0.0, path[0]);
+    for i in 1..=steps {
+        let t = i as f64 / steps as f64;
+        let next = (t, cell_at(t)?);
+        bisect(&cell_at, previous, next, tolerance, &mut path)?;
+        path.push(next.1);
+        previous = next;
+    }
+    let mut seen = HashSet::new();
+    path.retain(|&cell| seen.insert(cell));
+    Ok(path)
+}
+
+/// Returns the cells at `resolution` that the great-circle path from
+/// `(lon1, lat1)` to `(lon2, lat2)` passes through, in order from start to end
+/// with each cell listed once, at its first visit.
+///
+/// The path is sampled at a quarter of the cell scale `sqrt(area)` and
+/// bisected between samples in different cells until they are edge neighbors
+/// a millionth of the cell scale apart, so even a cell whose corner the path
+/// barely clips is found. The path is traced on the sphere, so one crossing
+/// the antimeridian or passing near a pole needs no splitting. Antipodal
+/// endpoints give `InvalidCoordinate`, as there is no unique path, and a path
+/// needing more than `A5_GEODESIC_SAMPLE_LIMIT` samples gives `OutOfBounds`.
+#[no_mangle]
+pub extern "C" fn a5_cells_along_geodesic(lon1: f64, lat1: f64, lon2: f64, lat2: f64, resolution: i32) -> CellArray {
+    guard(|| cell_vec_result_to_c(cells_along_geodesic((lon1, lat1), (lon2, lat2), resolution)))
+}
+
+/// Largest grid distance `a5_grid_distance` searches before giving up.
+pub const A5_GRID_DISTANCE_LIMIT: u64 = 2000;
+
+fn grid_distance(a: u64, b: u64) -> Result<u64, Error> {
+    validate_cell(a)?;
+    validate_cell(b)?;
+    let (res_a, res_b) = (a5::get_resolution(a), a5::get_resolution(b));
+    if res_a != res_b {
+        return Err(Error::new(A5ErrorCode::InvalidResolution, format!("resolution mismatch: cells are at resolution {} and {}", res_a, res_b)));
+    }
+    if a == b {
+        return Ok(0);
+    }
+
+    let mut previous = HashSet::new();
+    let mut frontier = HashSet::from([a]);
+    for distance in 1..=A5_GRID_DISTANCE_LIMIT {
+        let next = next_ring(&previous, &frontier)?;
+        if next.contains(&b) {
+            return Ok(distance);
+        }
+        previous = std::mem::replace(&mut frontier, next);
+    }
+    Err(Error::new(A5ErrorCode::OutOfBounds, format!("grid distance out of bounds: exceeds {} steps", A5_GRID_DISTANCE_LIMIT)))
+}
+
+/// One step of a breadth-first search over edge adjacency: the cells one step
+/// beyond `frontier`. Only the ring before it needs excluding, since a ring's
+/// neighbors can only lie in the previous, current or next ring.
+fn next_ring(previous: &HashSet<u64>, frontier: &HashSet<u64>) -> Result<HashSet<u64>, Error> {
+    let mut next = HashSet::new();
+    for &cell in frontier {
+        next.extend(edge_neighbors(cell)?.into_iter().filter(|n| !previous.contains(n) && !frontier.contains(n)));
+    }
+    Ok(next)
+}
+
+/// Returns the cells exactly `k` edge-steps from `origin`, sorted by cell id:
+/// the outermost ring of `a5_grid_disk`, at the origin's resolution and never
+/// compacted. `k = 0` returns the origin alone.
+///
+/// Distance is measured over real edge adjacency, as for `a5_grid_distance`,
+/// so the ring has no gaps: every cell at distance `k` is included, also where
+/// it crosses quintant and face boundaries. Every A5 cell is a pentagon, so
+/// there are no special cells to distort it, but the tiling is not regular:
+/// ring sizes vary with position, and near a dodecahedron vertex the ring need
+/// not form one simple loop. A negative `k`, or one beyond
+/// `A5_GRID_DISTANCE_LIMIT`, is an error.
+#[no_mangle]
+pub extern "C" fn a5_grid_ring(origin: u64, k: i32) -> CellArray {
+    guard(|| {
+        let ring = validate_cell(origin).and_then(|_| validate_k(k)).and_then(|k| {
+            let mut previous = HashSet::new();
+            let mut frontier = HashSet::from([origin]);
+            for _ in 0..k {
+                let next = next_ring(&previous, &frontier)?;
+                previous = std::mem::replace(&mut frontier, next);
+            }
+            let mut ring: Vec<u64> = frontier.into_iter().collect();
+            ring.sort_unstable();
+            Ok(ring)
+        });
+        cell_vec_result_to_c(ring)
+    })
+}
+
+/// Writes every cell within `k` edge-steps of `origin` into `out_cells` and its
+/// distance from the origin into the same slot of `out_distances`, both
+/// caller-owned buffers holding `cap` entries. Cells are at the origin's
+/// resolution and come ring by ring, the origin first at distance 0, then
+/// sorted by id within each ring: together, the rings `a5_grid_ring` returns
+/// for 0 to `k`, so distances agree with `a5_grid_distance`. `k` is limited
+/// as for `a5_grid_ring`.
+///
+/// `out_len` receives the cell count. If `cap` is too small nothing is
+/// written and `BufferTooSmall` is returned with the required count in
+/// `out_len`.
+#[no_mangle]
+pub extern "C" fn a5_grid_disk_distances(origin: u64, k: i32, out_cells: *mut u64, out_distances: *mut i32, cap: usize, out_len: *mut usize) -> A5ErrorCode {
+    guard(|| {
+        let disk = validate_cell(origin).and_then(|_| validate_k(k)).and_then(|k| {
+            let (mut cells, mut distances) = (vec![origin], vec![0]);
+            let mut previous = HashSet::new();
+            let mut frontier = HashSet::from([origin]);
+            for distance in 1..=k as i32 {
+                let next = next_ring(&previous, &frontier)?;
+                let mut ring: Vec<u64> = next.iter().copied().collect();
+                ring.sort_unstable();
+                distances.extend(std::iter::repeat_n(distance, ring.len()));
+                cells.extend(ring);
+                previous = std::mem::replace(&mut frontier, next);
+            }
+            Ok((cells, distances))
+        });
+        match disk {
+            // Either buffer null holds nothing, so neither is written
+            Ok((cells, _)) if out_cells.is_null() || out_distances.is_null() => write_into(&cells, out_cells, 0, out_len),
+            Ok((cells, distances)) => match write_into(&cells, out_cells, cap, out_len) {
+                A5ErrorCode::None => write_into(&distances, out_distances, cap, out_len),
+                code => code,
+            },
+            Err(e) => map_error(e).0,
+        }
+    })
+}
+
+/// Returns the number of edge-steps between two cells of the same resolution.
+///
+/// The distance is exact, including across quintant and face boundaries, as it
+/// is found by a breadth-first search over real cell adjacency. The cost grows
+/// with the square of the distance, and cells more than
+/// `A5_GRID_DISTANCE_LIMIT` steps apart are reported as `OutOfBounds`.
+#[no_mangle]
+pub extern "C" fn a5_grid_distance(a: u64, b: u64) -> ResultU64 {
+    guard(|| grid_distance(a, b).into())
+}
+
+/// Same as `a5_grid_disk`, but a step may also cross a shared vertex. The `a5`
+/// traversal has no vertex neighbors below resolution 2, so resolution 0 and 1
+/// cells give just the origin.
+#[no_mangle]
+pub extern "C" fn a5_grid_disk_vertex(cell_id: u64, k: i32) -> CellArray {
+    guard(|| {
+        let disk = validate_cell(cell_id).and_then(|_| validate_k(k)).and_then(|k| a5::grid_disk_vertex(cell_id, k).map_err(Error::from));
+        cell_vec_result_to_c(disk)
+    })
+}
+
+#[cfg(test)]
+// The tests free results with the by-value functions, which keeps the
+// deprecated shims covered
+#[allow(deprecated)]
+mod tests {
+    use super::*;
+
+    /// Held by the tests that fill or empty the global boundary cache.
+    static BOUNDARY_CACHE: std::sync::Mutex<()> = std::sync::Mutex::new(());
+
+    #[test]
+    fn guard_turns_panic_into_error() {
+        let result: ResultU64 = guard(|| panic!("boom"));
+        assert_eq!(result.code, A5ErrorCode::Internal);
+        let message = unsafe { CStr::from_ptr(result.error) }.to_str().unwrap().to_string();
+        assert_eq!(message, "panic: boom");
+        a5_free_string(result.error);
+    }
+
+    #[test]
+    fn errors_raised_here_keep_their_code() {
+        // The code travels with the error, whatever its message says
+        assert_eq!(map_error(Error::new(A5ErrorCode::OutOfBounds, "invalid cell")).0, A5ErrorCode::OutOfBounds);
+        assert_eq!(validate_k(-1).unwrap_err().code, A5ErrorCode::OutOfBounds);
+        assert_eq!(validate_lonlat(0.0, 91.0).unwrap_err().code, A5ErrorCode::InvalidCoordinate);
+        assert_eq!(input_slice::<u64>(std::ptr::null(), 1).unwrap_err().code, A5ErrorCode::NullPointer);
+        assert_eq!(validate_resolution(31).unwrap_err().code, A5ErrorCode::InvalidResolution);
+
+        // Messages from the `a5` crate fall back to their wording
+        for (message, code) in [("Invalid cell id", A5ErrorCode::InvalidCell), ("Resolution 99 is too large", A5ErrorCode::InvalidResolution), ("something else", A5ErrorCode::Internal)] {
+            assert_eq!(map_error(message.to_string()).0, code, "{message}");
+        }
+        let result: Result<u64, Error> = guard(|| panic!("resolution out of bounds"));
+        assert_eq!(result.unwrap_err().code, A5ErrorCode::Internal);
+    }
+
+    fn cell_array_to_vec(arr: CellArray) -> Vec<u64> {
+        assert!(arr.error.is_null());
+        let cells = if arr.data.is_null() { Vec::new() } else { unsafe { std::slice::from_raw_parts(arr.data, arr.len) }.to_vec() };
+        a5_free_cell_array(arr);
+        cells
+    }
+
+    #[test]
+    fn cell_to_children_forwards_resolution() {
+        let cell = a5::lonlat_to_cell(a5::LonLat::new(-122.4, 37.8), 3).unwrap();
+        assert_eq!(cell_array_to_vec(a5_cell_to_children(cell, 5)), a5::cell_to_children(cell, Some(5)).unwrap());
+        assert_eq!(cell_array_to_vec(a5_cell_to_children(cell, A5_DEFAULT_RESOLUTION)), a5::cell_to_children(cell, None).unwrap());
+
+        let out_of_range = a5_cell_to_children(cell, 31);
+        assert_eq!(out_of_range.code, A5ErrorCode::InvalidResolution);
+        a5_free_cell_array(out_of_range);
+
+        // Counted before anything is allocated: 5 * 4^19 children
+        let face = a5::get_res0_cells().unwrap()[0];
+        let mut too_many = a5_cell_to_children(face, 20);
+        assert_eq!(too_many.code, A5ErrorCode::OutOfBounds);
+        a5_free_cell_array_ptr(&mut too_many);
+        let arena = a5_arena_new();
+        assert_eq!(a5_cell_to_children_arena(arena, face, 20).code, A5ErrorCode::OutOfBounds);
+        a5_arena_free(arena);
+        let mut invalid = a5_cell_to_children(0, 5);
+        assert_eq!(invalid.code, A5ErrorCode::InvalidCell);
+        a5_free_cell_array_ptr(&mut invalid);
+    }
+
+    #[test]
+    fn lon_lat_to_cell_rejects_out_of_range_resolution() {
+        for resolution in [-1, a5_max_resolution() + 1] {
+            let result = a5_lon_lat_to_cell(-122.4, 37.8, resolution);
+            assert_eq!(result.code, A5ErrorCode::InvalidResolution);
+            let message = unsafe { CStr::from_ptr(result.error) }.to_str().unwrap().to_string();
+            assert_eq!(message, format!("resolution {} out of range [0, 30]", resolution));
+            a5_free_string(result.error);
+        }
+        assert_eq!(a5_lon_lat_to_cell(-122.4, 37.8, a5_max_resolution()).code, A5ErrorCode::None);
+    }
+
+    #[test]
+    fn is_valid_cell_accepts_real_cells() {
+        for cell in a5::get_res0_cells().unwrap() {
+            assert!(a5_is_valid_cell(cell));
+        }
+        for resolution in [1, 2, 10, 29, 30] {
+            let cell = a5::lonlat_to_cell(a5::LonLat::new(-122.4, 37.8), resolution).unwrap();
+            assert!(a5_is_valid_cell(cell), "resolution {}", resolution);
+        }
+    }
+
+    #[test]
+    fn is_valid_cell_rejects_crafted_values() {
+        let res1 = a5::cell_to_children(a5::get_res0_cells().unwrap()[3], None).unwrap()[2];
+        // World cell
+        assert!(!a5_is_valid_cell(0));
+        // Origin id beyond the 12 dodecahedron faces
+        assert!(!a5_is_valid_cell((12u64 << 58) | (1 << 57)));
+        // Stray bit between the origin field and the resolution marker
+        assert!(a5_is_valid_cell(res1));
+        assert!(!a5_is_valid_cell(res1 | (1 << 57)));
+        // Resolution marker on a bit the encoding never uses
+        assert!(!a5_is_valid_cell((3u64 << 58) | (1 << 54)));
+    }
+
+    fn take_string(ptr: *mut std::os::raw::c_char) -> String {
+        let s = unsafe { CStr::from_ptr(ptr) }.to_str().unwrap().to_string();
+        a5_free_string(ptr);
+        s
+    }
+
+    #[test]
+    fn hex_string_round_trip() {
+        for resolution in [0, 1, 5, 15, 30] {
+            let cell = a5::lonlat_to_cell(a5::LonLat::new(139.7, 35.7), resolution).unwrap();
+            let hex = take_string(a5_cell_to_hex_string(cell));
+            assert_eq!(hex.len(), 16);
+            assert_eq!(hex, hex.to_lowercase());
+
+            let c_hex = CString::new(hex.clone()).unwrap();
+            let parsed = a5_hex_string_to_cell(c_hex.as_ptr());
+            assert_eq!((parsed.code, parsed.value), (A5ErrorCode::None, cell));
+
+            let c_prefixed = CString::new(format!("0x{}", hex)).unwrap();
+            assert_eq!(a5_hex_string_to_cell(c_prefixed.as_ptr()).value, cell);
+        }
+    }
+
+    #[test]
+    fn hex_string_rejects_malformed_input() {
+        for input in ["", "0x", "12345678901234567", "+1f", "xyz", "0x12 "] {
+            let c_input = CString::new(input).unwrap();
+            let parsed = a5_hex_string_to_cell(c_input.as_ptr());
+            assert_eq!(parsed.code, A5ErrorCode::InvalidCell, "input {:?}", input);
+            a5_free_string(parsed.error);
+        }
+        for parsed in [a5_hex_string_to_cell(std::ptr::null()), a5_decimal_string_to_cell(std::ptr::null()), a5_hex_to_u64(std::ptr::null())] {
+            assert_eq!(parsed.code, A5ErrorCode::NullPointer);
+            a5_free_string(parsed.error);
+        }
+    }
+
+    #[test]
+    fn parent_chain_is_coarsest_first() {
+        let cell = a5::lonlat_to_cell(a5::LonLat::new(2.35, 48.85), 6).unwrap();
+        let chain = cell_array_to_vec(a5_cell_to_parent_chain(cell));
+        assert_eq!(chain.len(), 6);
+        for (resolution, &ancestor) in chain.iter().enumerate() {
+            assert_eq!(ancestor, a5::cell_to_parent(cell, Some(resolution as i32)).unwrap());
+        }
+
+        let res0 = a5_cell_to_parent_chain(chain[0]);
+        assert!(res0.data.is_null() && res0.error.is_null() && res0.len == 0);
+
+        let invalid = a5_cell_to_parent_chain(0);
+        assert_eq!(invalid.code, A5ErrorCode::InvalidCell);
+        a5_free_cell_array(invalid);
+    }
+
+    fn origin_of(cell: u64) -> u8 {
+        a5::core::serialization::deserialize(cell).unwrap().origin_id
+    }
+
+    #[test]
+    fn grid_disk_handles_k_bounds() {
+        let cell = a5::lonlat_to_cell(a5::LonLat::new(-122.4, 37.8), 10).unwrap();
+        assert_eq!(cell_array_to_vec(a5_grid_disk(cell, 0)), vec![cell]);
+
+        let negative = a5_grid_disk(cell, -1);
+        assert_eq!(negative.code, A5ErrorCode::OutOfBounds);
+        a5_free_cell_array(negative);
+
+        // Every walk refuses a k past the limit up front
+        let arena = a5_arena_new();
+        for k in [A5_GRID_DISTANCE_LIMIT as i32 + 1, i32::MAX] {
+            for mut result in [a5_grid_disk(cell, k), a5_grid_disk_vertex(cell, k), a5_grid_ring(cell, k)] {
+                assert_eq!(result.code, A5ErrorCode::OutOfBounds, "{k}");
+                a5_free_cell_array_ptr(&mut result);
+            }
+            assert_eq!(a5_grid_disk_arena(arena, cell, k).code, A5ErrorCode::OutOfBounds);
+        }
+        a5_arena_free(arena);
+    }
+
+    #[test]
+    fn grid_disk_walks_resolution_0_and_1_cells() {
+        for resolution in [0, 1] {
+            let cells = a5::uncompact(&a5::get_res0_cells().unwrap(), resolution).unwrap();
+            for &cell in &cells {
+                let disk = cell_array_to_vec(a5_grid_disk(cell, 1));
+                assert!(disk.windows(2).all(|w| w[0] < w[1]));
+                let mut expected = cell_array_to_vec(a5_grid_ring(cell, 1));
+                expected.push(cell);
+                expected.sort_unstable();
+                let mut uniform = a5::uncompact(&disk, resolution).unwrap();
+                uniform.sort_unstable();
+                assert_eq!(uniform, expected);
+            }
+            // Far enough out, a disk covers the sphere and compacts to its faces
+            assert_eq!(cell_array_to_vec(a5_grid_disk(cells[0], 20)), cell_array_to_vec(a5_get_res0_cells()));
+        }
+    }
+
+    #[test]
+    fn grid_disk_is_uniform_across_face_boundaries() {
+        let mut crossings = 0;
+        for cell in a5::uncompact(&a5::get_res0_cells().unwrap(), 3).unwrap() {
+            let disk = a5::uncompact(&cell_array_to_vec(a5_grid_disk(cell, 1)), 3).unwrap();
+            assert_eq!(disk.len(), 6, "cell {:016x}", cell);
+            assert!(disk.contains(&cell));
+            if disk.iter().any(|&c| origin_of(c) != origin_of(cell)) {
+                crossings += 1;
+            }
+        }
+        assert!(crossings > 0);
+    }
+
+    #[test]
+    fn neighbor_counts_by_resolution() {
+        let res0 = a5::get_res0_cells().unwrap();
+        for &cell in &res0 {
+            assert_eq!(cell_array_to_vec(a5_cell_to_neighbors(cell)).len(), 5);
+        }
+        for cell in a5::uncompact(&res0, 1).unwrap() {
+            assert_eq!(cell_array_to_vec(a5_cell_to_neighbors(cell)).len(), 3);
+        }
+        // Includes the cells at the dodecahedron vertices and face centers
+        for cell in a5::uncompact(&res0, 3).unwrap() {
+            let neighbors = cell_array_to_vec(a5_cell_to_neighbors(cell));
+            assert_eq!(neighbors.len(), 5);
+            assert!(neighbors.windows(2).all(|w| w[0] < w[1]));
+            assert!(!neighbors.contains(&cell));
+        }
+    }
+
+    #[test]
+    fn grid_distance_between_cells() {
+        let cell = a5::lonlat_to_cell(a5::LonLat::new(-122.4, 37.8), 8).unwrap();
+        assert_eq!(a5_grid_distance(cell, cell).value, 0);
+
+        let neighbor = cell_array_to_vec(a5_cell_to_neighbors(cell))[0];
+        let adjacent = a5_grid_distance(cell, neighbor);
+        assert_eq!((adjacent.code, adjacent.value), (A5ErrorCode::None, 1));
+
+        let ring2 = a5::uncompact(&a5::grid_disk(cell, 2).unwrap(), 8).unwrap();
+        let ring1 = a5::uncompact(&a5::grid_disk(cell, 1).unwrap(), 8).unwrap();
+        let far = *ring2.iter().find(|c| !ring1.contains(c)).unwrap();
+        assert_eq!(a5_grid_distance(far, cell).value, 2);
+
+        let mismatched = a5_grid_distance(cell, a5::cell_to_parent(cell, None).unwrap());
+        assert_eq!(mismatched.code, A5ErrorCode::InvalidResolution);
+        a5_free_string(mismatched.error);
+    }
+
+    #[test]
+    fn haversine_distance_matches_known_values() {
+        // A quarter of a meridian
+        let quarter = a5_haversine_distance(0.0, 0.0, 0.0, 90.0);
+        assert!((quarter - std::f64::consts::FRAC_PI_2 * geometry::AUTHALIC_RADIUS_EARTH).abs() < 1e-6);
+        // Paris to London is roughly 344 km
+        let paris_london = a5_haversine_distance(2.3522, 48.8566, -0.1276, 51.5072);
+        assert!((paris_london - 344_000.0).abs() < 2_000.0);
+
+        let cell = a5::lonlat_to_cell(a5::LonLat::new(2.3522, 48.8566), 12).unwrap();
+        assert_eq!(a5_great_circle_distance_cells(cell, cell), 0.0);
+        assert!(a5_great_circle_distance_cells(cell, 0).is_nan());
+    }
+
+    #[test]
+    fn bbox_contains_boundary() {
+        let cell = a5::lonlat_to_cell(a5::LonLat::new(-122.4, 37.8), 9).unwrap();
+        let bbox = a5_cell_to_bbox(cell);
+        assert_eq!(bbox.code, A5ErrorCode::None);
+        assert!(!bbox.wraps);
+        assert!(bbox.min_lon < -122.4 && -122.4 < bbox.max_lon);
+        assert!(bbox.min_lat < 37.8 && 37.8 < bbox.max_lat);
+        for p in a5::cell_to_boundary(cell, None).unwrap() {
+            assert!(bbox.min_lon <= p.longitude() && p.longitude() <= bbox.max_lon);
+            assert!(bbox.min_lat <= p.latitude() && p.latitude() <= bbox.max_lat);
+        }
+    }
+
+    #[test]
+    fn bbox_flags_antimeridian_and_poles() {
+        let cell = a5::lonlat_to_cell(a5::LonLat::new(179.999, -16.0), 6).unwrap();
+        let bbox = a5_cell_to_bbox(cell);
+        assert!(bbox.wraps);
+        assert!(bbox.min_lon > bbox.max_lon);
+        assert!(bbox.min_lon > 170.0 && bbox.max_lon < -170.0);
+
+        let cap = a5_cell_to_bbox(a5::lonlat_to_cell(a5::LonLat::new(0.0, 90.0), 0).unwrap());
+        assert_eq!((cap.min_lon, cap.max_lon, cap.max_lat, cap.wraps), (-180.0, 180.0, 90.0, false));
+
+        // From resolution 1 on the pole is a vertex shared by several cells
+        let polar = a5_cell_to_bbox(a5::lonlat_to_cell(a5::LonLat::new(0.0, -90.0), 4).unwrap());
+        assert_eq!(polar.min_lat, -90.0);
+        let span = if polar.wraps { polar.max_lon + 360.0 - polar.min_lon } else { polar.max_lon - polar.min_lon };
+        assert!(span < 180.0);
+
+        let invalid = a5_cell_to_bbox(0);
+        assert_eq!(invalid.code, A5ErrorCode::InvalidCell);
+        a5_free_string(invalid.error);
+    }
+
+    fn square(min_lon: f64, min_lat: f64, max_lon: f64, max_lat: f64) -> Vec<LonLatDegrees> {
+        [(min_lon, min_lat), (max_lon, min_lat), (max_lon, max_lat), (min_lon, max_lat)].iter().map(|&(lon, lat)| LonLatDegrees { lon, lat }).collect()
+    }
+
+    #[test]
+    fn polygon_to_cells_center_and_intersects() {
+        let ring = square(0.0, 40.0, 10.0, 50.0);
+        let center = cell_array_to_vec(a5_polygon_to_cells(ring.as_ptr(), ring.len(), 6, FillMode::Center));
+        let intersects = cell_array_to_vec(a5_polygon_to_cells(ring.as_ptr(), ring.len(), 6, FillMode::Intersects));
+        assert!(!center.is_empty());
+        assert!(center.windows(2).all(|w| w[0] < w[1]));
+        assert!(center.iter().all(|c| intersects.binary_search(c).is_ok()));
+        assert!(intersects.len() > center.len());
+
+        for cell in &center {
+            let c = a5::cell_to_lonlat(*cell).unwrap();
+            assert!((0.0..=10.0).contains(&c.longitude()) && (40.0..=50.0).contains(&c.latitude()));
+        }
+        for i in 0..=20 {
+            for j in 0..=20 {
+                let (lon, lat) = (i as f64 * 0.5, 40.0 + j as f64 * 0.5);
+                let cell = a5::lonlat_to_cell(a5::LonLat::new(lon, lat), 6).unwrap();
+                assert!(intersects.binary_search(&cell).is_ok());
+                let c = a5::cell_to_lonlat(cell).unwrap();
+                if (0.0..=10.0).contains(&c.longitude()) && (40.0..=50.0).contains(&c.latitude()) {
+                    assert!(center.binary_search(&cell).is_ok());
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn polygon_to_cells_across_antimeridian() {
+        let ring = square(170.0, -10.0, -170.0, 10.0);
+        let cells = cell_array_to_vec(a5_polygon_to_cells(ring.as_ptr(), ring.len(), 5, FillMode::Center));
+        let unwrapped = square(170.0, -10.0, 190.0, 10.0);
+        assert_eq!(cells, cell_array_to_vec(a5_polygon_to_cells(unwrapped.as_ptr(), unwrapped.len(), 5, FillMode::Center)));
+
+        // Centers come back unwrapped, so bring them into [-180, 180] first
+        let lons: Vec<f64> = cells.iter().map(|c| (a5::cell_to_lonlat(*c).unwrap().longitude() + 180.0).rem_euclid(360.0) - 180.0).collect();
+        assert!(lons.iter().any(|&lon| lon > 170.0) && lons.iter().any(|&lon| lon < -170.0));
+        assert!(lons.iter().all(|&lon| lon.abs() >= 170.0));
+    }
+
+    #[test]
+    fn polygon_to_cells_rejects_bad_input() {
+        let line = square(0.0, 0.0, 1.0, 1.0)[..2].to_vec();
+        let result = a5_polygon_to_cells(line.as_ptr(), line.len(), 5, FillMode::Center);
+        assert_eq!(result.code, A5ErrorCode::InvalidCoordinate);
+        a5_free_cell_array(result);
+
+        let ring = square(0.0, 0.0, 1.0, 1.0);
+        let result = a5_polygon_to_cells(ring.as_ptr(), ring.len(), 31, FillMode::Center);
+        assert_eq!(result.code, A5ErrorCode::InvalidResolution);
+        a5_free_cell_array(result);
+    }
+
+    #[test]
+    fn polygon_with_holes_excludes_hole() {
+        let outer = square(0.0, 0.0, 10.0, 10.0);
+        let hole = square(3.0, 3.0, 7.0, 7.0);
+        let hole_lens = [hole.len()];
+        let donut = cell_array_to_vec(a5_polygon_with_holes_to_cells(outer.as_ptr(), outer.len(), hole.as_ptr(), hole_lens.as_ptr(), 1, 6));
+        let full = cell_array_to_vec(a5_polygon_to_cells(outer.as_ptr(), outer.len(), 6, FillMode::Center));
+        let inner = cell_array_to_vec(a5_polygon_to_cells(hole.as_ptr(), hole.len(), 6, FillMode::Center));
+        assert!(!inner.is_empty());
+        assert_eq!(donut.len(), full.len() - inner.len());
+        assert!(donut.iter().all(|c| full.binary_search(c).is_ok() && inner.binary_search(c).is_err()));
+
+        let no_holes = cell_array_to_vec(a5_polygon_with_holes_to_cells(outer.as_ptr(), outer.len(), std::ptr::null(), std::ptr::null(), 0, 6));
+        assert_eq!(no_holes, full);
+    }
+
+    #[test]
+    fn geojson_feature_collection_shape() {
+        let cell = a5::lonlat_to_cell(a5::LonLat::new(-122.4, 37.8), 9).unwrap();
+        let json = take_string(a5_cells_to_geojson([cell].as_ptr(), 1, true));
+        assert!(json.starts_with("{\"type\":\"FeatureCollection\",\"features\":[{\"type\":\"Feature\""));
+        assert!(json.contains(&format!("\"properties\":{{\"a5\":\"{:016x}\"}}", cell)));
+        assert!(json.contains("\"type\":\"Polygon\""));
+
+        let plain = take_string(a5_cells_to_geojson([cell].as_ptr(), 1, false));
+        assert!(plain.contains("\"properties\":{}"));
+        assert_eq!(take_string(a5_cells_to_geojson(std::ptr::null(), 0, true)), "{\"type\":\"FeatureCollection\",\"features\":[]}");
+        assert!(a5_cells_to_geojson([0u64].as_ptr(), 1, true).is_null());
+    }
+
+    #[test]
+    fn cell_parts_are_ccw_and_split_at_antimeridian() {
+        let cell = a5::lonlat_to_cell(a5::LonLat::new(179.999, -16.0), 6).unwrap();
+        let parts = cell_parts(cell).unwrap();
+        assert_eq!(parts.len(), 2);
+        for part in &parts {
+            assert!(geometry::signed_area(part) > 0.0);
+            assert!(part.iter().all(|&(lon, _)| (-180.0..=180.0).contains(&lon)));
+        }
+        let json = take_string(a5_cells_to_geojson([cell].as_ptr(), 1, false));
+        assert!(json.contains("\"type\":\"MultiPolygon\""));
+
+        for resolution in [0, 3] {
+            let polar = a5::lonlat_to_cell(a5::LonLat::new(0.0, 90.0), resolution).unwrap();
+            for part in cell_parts(polar).unwrap() {
+                assert!(geometry::signed_area(&part) > 0.0);
+                assert!(part.iter().any(|&(_, lat)| lat == 90.0));
+            }
+        }
+    }
+
+    #[test]
+    fn wkt_round_trips_boundary() {
+        let cell = a5::lonlat_to_cell(a5::LonLat::new(-122.4, 37.8), 9).unwrap();
+        let wkt = take_string(a5_cell_to_wkt(cell));
+        let body = wkt.strip_prefix("POLYGON((").and_then(|s| s.strip_suffix("))")).unwrap();
+        let coords: Vec<(f64, f64)> = body.split(", ").map(|pair| {
+            let (lon, lat) = pair.split_once(' ').unwrap();
+            (lon.parse().unwrap(), lat.parse().unwrap())
+        }).collect();
+        let boundary: Vec<(f64, f64)> = a5::cell_to_boundary(cell, None).unwrap().iter().map(|p| (p.longitude(), p.latitude())).collect();
+        // Same vertices, though the closed ring may start at another one
+        assert_eq!(coords.len(), boundary.len());
+        assert!(boundary.iter().all(|p| coords.contains(p)));
+        assert_eq!(coords.first(), coords.last());
+
+        assert!(a5_cell_to_wkt(0).is_null());
+    }
+
+    #[test]
+    fn wkb_polygon_layout() {
+        let cell = a5::lonlat_to_cell(a5::LonLat::new(179.999, -16.0), 6).unwrap();
+        let mut len = 0;
+        let ptr = a5_cell_to_wkb(cell, &mut len);
+        let bytes = unsafe { std::slice::from_raw_parts(ptr, len) }.to_vec();
+        a5_free_wkb(ptr, len);
+
+        let u32_at = |i: usize| u32::from_le_bytes(bytes[i..i + 4].try_into().unwrap());
+        let f64_at = |i: usize| f64::from_le_bytes(bytes[i..i + 8].try_into().unwrap());
+        assert_eq!((bytes[0], u32_at(1), u32_at(5)), (1, 3, 1));
+        let points = u32_at(9) as usize;
+        assert_eq!(len, 13 + points * 16);
+        assert_eq!((f64_at(13), f64_at(21)), (f64_at(len - 16), f64_at(len - 8)));
+        // One ring with unwrapped longitudes rather than a jump across the map
+        let lons: Vec<f64> = (0..points).map(|i| f64_at(13 + i * 16)).collect();
+        assert!(lons.windows(2).all(|w| (w[0] - w[1]).abs() < 10.0));
+
+        let mut len = 7;
+        assert!(a5_cell_to_wkb(0, &mut len).is_null());
+        assert_eq!(len, 0);
+    }
+
+    #[test]
+    fn flat_boundary_interleaves_coordinates() {
+        let cell = a5::lonlat_to_cell(a5::LonLat::new(-122.4, 37.8), 9).unwrap();
+        let mut len = 0;
+        let ptr = a5_cell_to_boundary_flat(cell, false, &mut len);
+        let open = unsafe { std::slice::from_raw_parts(ptr, len) }.to_vec();
+        a5_free_f64_array(ptr, len);
+        let expected: Vec<f64> = a5::cell_to_boundary(cell, Some(a5::core::cell::CellToBoundaryOptions { closed_ring: false, segments: None })).unwrap().iter().flat_map(|p| [p.longitude(), p.latitude()]).collect();
+        assert_eq!(open, expected);
+
+        let ptr = a5_cell_to_boundary_flat(cell, true, &mut len);
+        let closed = unsafe { std::slice::from_raw_parts(ptr, len) }.to_vec();
+        a5_free_f64_array(ptr, len);
+        assert_eq!(closed.len(), open.len() + 2);
+        assert_eq!(closed[..2], closed[closed.len() - 2..]);
+
+        assert!(a5_cell_to_boundary_flat(0, true, &mut len).is_null());
+        assert_eq!(len, 0);
+    }
+
+    #[test]
+    fn boundary_options_control_segments_and_closure() {
+        let cell = a5::lonlat_to_cell(a5::LonLat::new(-122.4, 37.8), 9).unwrap();
+        let len = |segments, closed_ring| {
+            let arr = a5_cell_to_boundary_opts(cell, BoundaryOptions { segments, closed_ring });
+            let len = arr.len;
+            a5_free_lonlatdegrees_array(arr);
+            len
+        };
+        assert_eq!(len(0, false), a5::cell_to_boundary(cell, Some(a5::core::cell::CellToBoundaryOptions { closed_ring: false, segments: None })).unwrap().len());
+        assert_eq!(len(1, false), 5);
+        assert_eq!(len(1, true), 6);
+        assert_eq!(len(4, false), 20);
+
+        let invalid = a5_cell_to_boundary_opts(0, BoundaryOptions { segments: 0, closed_ring: true });
+        assert_eq!(invalid.code, A5ErrorCode::InvalidCell);
+        a5_free_lonlatdegrees_array(invalid);
+    }
+
+    #[test]
+    fn cell_area_in_units() {
+        let m2 = a5_cell_area(5);
+        let area = |unit| a5_cell_area_in(5, unit).value;
+        assert_eq!(area(AreaUnit::SquareMeters), m2);
+        assert!((area(AreaUnit::SquareKilometers) * 1.0e6 - m2).abs() / m2 < 1e-12);
+        assert!((area(AreaUnit::Hectares) * 1.0e4 - m2).abs() / m2 < 1e-12);
+        assert!((area(AreaUnit::SquareMiles) * 2.589988110336e6 - m2).abs() / m2 < 1e-12);
+
+        let invalid = a5_cell_area_in(31, AreaUnit::SquareMeters);
+        assert_eq!(invalid.code, A5ErrorCode::InvalidResolution);
+        assert!(invalid.value.is_nan());
+        a5_free_string(invalid.error);
+        assert!(a5_cell_area(-1).is_nan());
+    }
+
+    #[test]
+    fn exact_area_of_res0_cells_covers_sphere() {
+        let total: f64 = a5::get_res0_cells().unwrap().iter().map(|&cell| a5_cell_exact_area(cell).value).sum();
+        let sphere = a5::core::constants::AUTHALIC_AREA_EARTH;
+        assert!((total - sphere).abs() / sphere < 1e-3, "{} vs {}", total, sphere);
+
+        let cell = a5::lonlat_to_cell(a5::LonLat::new(-122.4, 37.8), 9).unwrap();
+        let area = a5_cell_exact_area(cell).value;
+        assert!((area - a5_cell_area(9)).abs() / area < 0.1);
+
+        let invalid = a5_cell_exact_area(0);
+        assert_eq!(invalid.code, A5ErrorCode::InvalidCell);
+        a5_free_string(invalid.error);
+    }
+
+    #[test]
+    fn compact_cells_merges_siblings() {
+        let parent = a5::lonlat_to_cell(a5::LonLat::new(-122.4, 37.8), 6).unwrap();
+        let mut input = a5::cell_to_children(parent, Some(8)).unwrap();
+        assert_eq!(cell_array_to_vec(a5_compact_cells(input.as_ptr(), input.len())), vec![parent]);
+
+        // Duplicates and cells nested in others collapse too
+        let child = input[0];
+        input.push(child);
+        input.extend(a5::cell_to_children(child, None).unwrap());
+        input.reverse();
+        assert_eq!(cell_array_to_vec(a5_compact_cells(input.as_ptr(), input.len())), vec![parent]);
+
+        let children = a5::cell_to_children(parent, Some(8)).unwrap();
+        let partial = &children[1..];
+        let compacted = cell_array_to_vec(a5_compact_cells(partial.as_ptr(), partial.len()));
+        assert!(compacted.len() > 1 && !compacted.contains(&parent));
+        assert!(compacted.windows(2).all(|w| w[0] < w[1]));
+
+        let res0 = a5::get_res0_cells().unwrap();
+        assert_eq!(cell_array_to_vec(a5_compact_cells(res0.as_ptr(), res0.len())), res0);
+
+        let result = a5_compact_cells([parent, 0].as_ptr(), 2);
+        assert_eq!(result.code, A5ErrorCode::InvalidCell);
+        a5_free_cell_array(result);
+    }
+
+    #[test]
+    fn uncompact_cells_inverts_compaction() {
+        let ring = square(0.0, 40.0, 10.0, 50.0);
+        let original = cell_array_to_vec(a5_polygon_to_cells(ring.as_ptr(), ring.len(), 6, FillMode::Center));
+        let compacted = cell_array_to_vec(a5_compact_cells(original.as_ptr(), original.len()));
+        assert!(compacted.len() < original.len());
+        assert_eq!(cell_array_to_vec(a5_uncompact_cells(compacted.as_ptr(), compacted.len(), 6)), original);
+
+        let result = a5_uncompact_cells(original.as_ptr(), original.len(), 5);
+        assert_eq!(result.code, A5ErrorCode::InvalidResolution);
+        assert!(result.data.is_null());
+        a5_free_cell_array(result);
+
+        // Sized before expanding: one face at resolution 20 is 5 * 4^19 cells
+        let face = a5::get_res0_cells().unwrap()[0];
+        for mut result in [a5_uncompact_cells(&face, 1, 20), a5_uncompact(&face, 1, 20), a5_compact_cells_to(&face, 1, 20)] {
+            assert_eq!(result.code, A5ErrorCode::OutOfBounds);
+            assert!(result.data.is_null());
+            a5_free_cell_array_ptr(&mut result);
+        }
+        for (cells, resolution, code) in [(&[0u64][..], 5, A5ErrorCode::InvalidCell), (&compacted[..], 31, A5ErrorCode::InvalidResolution), (&compacted[..], -1, A5ErrorCode::InvalidResolution)] {
+            let mut result = a5_uncompact(cells.as_ptr(), cells.len(), resolution);
+            assert_eq!(result.code, code, "{resolution}");
+            a5_free_cell_array_ptr(&mut result);
+        }
+        let mut expected = cell_array_to_vec(a5_uncompact(compacted.as_ptr(), compacted.len(), 6));
+        expected.sort_unstable();
+        assert_eq!(expected, original);
+    }
+
+    #[test]
+    fn into_variants_report_buffer_too_small() {
+        let cell = a5::lonlat_to_cell(a5::LonLat::new(-122.4, 37.8), 9).unwrap();
+        let mut len = 0;
+        let mut small = [LonLatDegrees { lon: 0.0, lat: 0.0 }; 2];
+        assert_eq!(a5_cell_to_boundary_into(cell, small.as_mut_ptr(), small.len(), &mut len), A5ErrorCode::BufferTooSmall);
+        let boundary = a5::cell_to_boundary(cell, None).unwrap();
+        assert_eq!(len, boundary.len());
+
+        let mut buffer = vec![LonLatDegrees { lon: 0.0, lat: 0.0 }; len];
+        assert_eq!(a5_cell_to_boundary_into(cell, buffer.as_mut_ptr(), buffer.len(), &mut len), A5ErrorCode::None);
+        assert!(buffer.iter().zip(&boundary).all(|(v, p)| v.lon == p.longitude() && v.lat == p.latitude()));
+
+        let mut cells = [0u64; 3];
+        assert_eq!(a5_cell_to_children_into(cell, 10, cells.as_mut_ptr(), cells.len(), &mut len), A5ErrorCode::BufferTooSmall);
+        assert_eq!(len, 4);
+        let mut cells = [0u64; 16];
+        assert_eq!(a5_cell_to_children_into(cell, A5_DEFAULT_RESOLUTION, cells.as_mut_ptr(), cells.len(), &mut len), A5ErrorCode::None);
+        assert_eq!(cells[..len].to_vec(), a5::cell_to_children(cell, None).unwrap());
+
+        assert_eq!(a5_cell_to_children_into(cell, 8, cells.as_mut_ptr(), cells.len(), &mut len), A5ErrorCode::InvalidResolution);
+        assert_eq!(a5_cell_to_boundary_into(0, buffer.as_mut_ptr(), buffer.len(), &mut len), A5ErrorCode::InvalidCell);
+    }
+
+    #[test]
+    fn children_size_matches_children() {
+        let face = a5::get_res0_cells().unwrap()[0];
+        let quintant = a5::cell_to_children(face, None).unwrap()[0];
+        let cell = a5::lonlat_to_cell(a5::LonLat::new(-122.4, 37.8), 5).unwrap();
+        for (cell, child_resolution) in [(face, 1), (face, 3), (quintant, 2), (quintant, 4), (cell, 5), (cell, 8), (cell, A5_DEFAULT_RESOLUTION)] {
+            let size = a5_cell_to_children_size(cell, child_resolution);
+            assert_eq!(size.code, A5ErrorCode::None);
+            let children = a5_cell_to_children(cell, child_resolution);
+            assert_eq!(size.value, children.len as u64);
+            a5_free_cell_array(children);
+        }
+
+        let coarser = a5_cell_to_children_size(cell, 4);
+        assert_eq!(coarser.code, A5ErrorCode::InvalidResolution);
+        a5_free_string(coarser.error);
+    }
+
+    #[test]
+    fn free_cstring_accepts_errors_and_null() {
+        a5_free_cstring(std::ptr::null_mut());
+        let result = a5_lon_lat_to_cell(0.0, 0.0, 99);
+        assert!(!result.error.is_null());
+        a5_free_cstring(result.error);
+        a5_free_cstring(a5_cell_to_hex_string(a5::lonlat_to_cell(a5::LonLat::new(0.0, 0.0), 5).unwrap()));
+    }
+
+    #[test]
+    fn version_and_build_info_are_static() {
+        let version = unsafe { CStr::from_ptr(a5_version()) }.to_str().unwrap();
+        assert_eq!(version, format!("a5_rust {} (a5 {})", env!("CARGO_PKG_VERSION"), A5_CRATE_VERSION));
+        assert_eq!(a5_version(), a5_version());
+        let info = unsafe { CStr::from_ptr(a5_build_info()) }.to_str().unwrap();
+        assert!(info.starts_with(std::env::consts::ARCH));
+    }
+
+    #[cfg(target_pointer_width = "64")]
+    #[test]
+    fn abi_layout_matches_its_version() {
+        use std::mem::size_of;
+        // A failure here means a `#[repr(C)]` layout changed: bump
+        // A5_ABI_VERSION along with these sizes
+        assert_eq!(a5_abi_version(), 2);
+        let sizes = [
+            size_of::<ResultU64>(),
+            size_of::<ResultLonLat>(),
+            size_of::<ResultF64>(),
+            size_of::<ResultSpherical>(),
+            size_of::<LonLatDegrees>(),
+            size_of::<LonLatDegreesArray>(),
+            size_of::<CellArray>(),
+            size_of::<SplitBoundary>(),
+            size_of::<BBox>(),
+            size_of::<CellBoundaryOptions>(),
+            size_of::<BoundaryOptions>(),
+            size_of::<ArrowArray>(),
+            size_of::<ArrowSchema>(),
+            size_of::<Tile>(),
+            size_of::<TileArray>(),
+        ];
+        assert_eq!(sizes, [24, 32, 24, 32, 16, 32, 32, 88, 56, 8, 8, 80, 72, 12, 32]);
+    }
+
+    #[test]
+    fn get_resolution_checked_rejects_invalid_cells() {
+        let face = a5::get_res0_cells().unwrap()[0];
+        let checked = a5_get_resolution_checked(face);
+        assert_eq!((checked.value, checked.code), (0, A5ErrorCode::None));
+        let cell = a5::lonlat_to_cell(a5::LonLat::new(-122.4, 37.8), 12).unwrap();
+        assert_eq!(a5_get_resolution_checked(cell).value, 12);
+
+        let quintant = a5::cell_to_children(a5::get_res0_cells().unwrap()[3], None).unwrap()[2];
+        for crafted in [0, (12u64 << 58) | (1 << 57), quintant | (1 << 57)] {
+            let checked = a5_get_resolution_checked(crafted);
+            assert_eq!(checked.code, A5ErrorCode::InvalidCell);
+            a5_free_cstring(checked.error);
+        }
+    }
+
+    #[test]
+    fn cell_contains_point_assigns_boundary_once() {
+        let cell = a5::lonlat_to_cell(a5::LonLat::new(-122.4, 37.8), 9).unwrap();
+        let center = a5::cell_to_lonlat(cell).unwrap();
+        assert!(a5_cell_contains_point(cell, center.longitude(), center.latitude()));
+        assert!(!a5_cell_contains_point(cell, 10.0, -40.0));
+        assert!(!a5_cell_contains_point(0, center.longitude(), center.latitude()));
+
+        let boundary = a5::cell_to_boundary(cell, Some(a5::core::cell::CellToBoundaryOptions { closed_ring: false, segments: Some(1) })).unwrap();
+        let mut candidates = edge_neighbors(cell).unwrap();
+        candidates.push(cell);
+        let (a, b) = (boundary[0], boundary[1]);
+        let midpoint = ((a.longitude() + b.longitude()) / 2.0, (a.latitude() + b.latitude()) / 2.0);
+        for (lon, lat) in [(a.longitude(), a.latitude()), midpoint] {
+            let owners = candidates.iter().filter(|&&c| a5_cell_contains_point(c, lon, lat)).count();
+            assert_eq!(owners, 1);
+        }
+    }
+
+    #[test]
+    fn batch_conversions_match_serial_path() {
+        let coords: Vec<LonLatDegrees> = (0..5000).map(|i| LonLatDegrees { lon: (i as f64 * 0.731) % 360.0 - 180.0, lat: (i as f64 * 0.377) % 180.0 - 90.0 }).collect();
+        let cells = cell_array_to_vec(a5_lon_lat_to_cell_batch(coords.as_ptr(), coords.len(), 10, std::ptr::null(), std::ptr::null_mut()));
+        let serial: Vec<u64> = coords.iter().map(|c| a5::lonlat_to_cell(a5::LonLat::new(c.lon, c.lat), 10).unwrap_or(A5_INVALID_CELL)).collect();
+        assert_eq!(cells, serial);
+
+        let centers = a5_cell_to_lon_lat_batch(cells.as_ptr(), cells.len(), std::ptr::null(), std::ptr::null_mut());
+        let slice = unsafe { std::slice::from_raw_parts(centers.data, centers.len) };
+        for (center, &cell) in slice.iter().zip(&cells) {
+            let expected = a5::cell_to_lonlat(cell).unwrap();
+            assert_eq!((center.lon, center.lat), (expected.longitude(), expected.latitude()));
+        }
+        a5_free_lonlatdegrees_array(centers);
+    }
+
+    #[test]
+    fn cell_bytes_round_trip_and_sort() {
+        let mut cells: Vec<u64> = [(0.0, 0.0), (-122.4, 37.8), (179.9, -16.0), (12.5, 89.0)].iter().map(|&(lon, lat)| a5::lonlat_to_cell(a5::LonLat::new(lon, lat), 7).unwrap()).collect();
+        cells.extend(a5::get_res0_cells().unwrap());
+        let encoded: Vec<[u8; 8]> = cells.iter().map(|&cell| {
+            let mut bytes = [0u8; 8];
+            a5_cell_to_bytes(cell, bytes.as_mut_ptr());
+            assert_eq!(a5_cell_from_bytes(bytes.as_ptr()), cell);
+            bytes
+        }).collect();
+
+        let mut by_bytes: Vec<usize> = (0..cells.len()).collect();
+        by_bytes.sort_by_key(|&i| encoded[i]);
+        let mut by_value: Vec<usize> = (0..cells.len()).collect();
+        by_value.sort_by_key(|&i| cells[i]);
+        assert_eq!(by_bytes, by_value);
+        assert_eq!(a5_cell_from_bytes(std::ptr::null()), A5_INVALID_CELL);
+    }
+
+    #[test]
+    fn decimal_string_round_trip() {
+        for cell in [u64::MAX, u64::MAX - 1, 1 << 53 | 1, a5::lonlat_to_cell(a5::LonLat::new(-122.4, 37.8), 20).unwrap()] {
+            let text = take_string(a5_cell_to_decimal_string(cell));
+            let c = CString::new(text).unwrap();
+            let parsed = a5_decimal_string_to_cell(c.as_ptr());
+            assert_eq!((parsed.value, parsed.code), (cell, A5ErrorCode::None));
+        }
+        for bad in ["", "18446744073709551616", "99999999999999999999999", "12a", "-1", "+1", " 1"] {
+            let c = CString::new(bad).unwrap();
+            let parsed = a5_decimal_string_to_cell(c.as_ptr());
+            assert_eq!(parsed.code, A5ErrorCode::InvalidCell, "{:?}", bad);
+            a5_free_cstring(parsed.error);
+        }
+    }
+
+    extern "C" fn collect_cell(ctx: *mut std::os::raw::c_void, cell: u64) -> bool {
+        unsafe { &mut *(ctx as *mut Vec<u64>) }.push(cell);
+        true
+    }
+
+    extern "C" fn collect_ten(ctx: *mut std::os::raw::c_void, cell: u64) -> bool {
+        let cells = unsafe { &mut *(ctx as *mut Vec<u64>) };
+        cells.push(cell);
+        cells.len() < 10
+    }
+
+    #[test]
+    fn children_foreach_streams_in_order() {
+        let face = a5::get_res0_cells().unwrap()[4];
+        let cell = a5::lonlat_to_cell(a5::LonLat::new(-122.4, 37.8), 5).unwrap();
+        for (cell, child_resolution) in [(face, 1), (face, 2), (cell, 5), (cell, A5_DEFAULT_RESOLUTION)] {
+            let mut seen: Vec<u64> = Vec::new();
+            let code = a5_cell_to_children_foreach(cell, child_resolution, &mut seen as *mut Vec<u64> as *mut _, collect_cell);
+            assert_eq!(code, A5ErrorCode::None);
+            let children = cell_array_to_vec(a5_cell_to_children(cell, child_resolution));
+            assert_eq!(seen, children);
+        }
+
+        // The callback stops the walk after ten cells
+        let mut seen: Vec<u64> = Vec::new();
+        assert_eq!(a5_cell_to_children_foreach(cell, 9, &mut seen as *mut Vec<u64> as *mut _, collect_ten), A5ErrorCode::None);
+        assert_eq!(seen, a5::cell_to_children(cell, Some(9)).unwrap()[..10].to_vec());
+        assert_eq!(a5_cell_to_children_foreach(cell, 4, &mut seen as *mut Vec<u64> as *mut _, collect_cell), A5ErrorCode::InvalidResolution);
+    }
+
+    #[test]
+    fn polygon_foreach_matches_polygon_to_cells() {
+        let ring = square(0.0, 40.0, 10.0, 50.0);
+        let mut seen: Vec<u64> = Vec::new();
+        let code = a5_polygon_to_cells_foreach(ring.as_ptr(), ring.len(), 5, FillMode::Intersects, &mut seen as *mut Vec<u64> as *mut _, collect_cell);
+        assert_eq!(code, A5ErrorCode::None);
+        seen.sort_unstable();
+        assert_eq!(seen, cell_array_to_vec(a5_polygon_to_cells(ring.as_ptr(), ring.len(), 5, FillMode::Intersects)));
+
+        let mut seen: Vec<u64> = Vec::new();
+        a5_polygon_to_cells_foreach(ring.as_ptr(), ring.len(), 5, FillMode::Center, &mut seen as *mut Vec<u64> as *mut _, collect_ten);
+        assert_eq!(seen.len(), 10);
+        assert_eq!(a5_polygon_to_cells_foreach(ring.as_ptr(), 2, 5, FillMode::Center, &mut seen as *mut Vec<u64> as *mut _, collect_cell), A5ErrorCode::InvalidCoordinate);
+    }
+
+    #[test]
+    fn arrow_export_of_centers() {
+        let cell = a5::lonlat_to_cell(a5::LonLat::new(-122.4, 37.8), 9).unwrap();
+        let cells = [cell, (12 << 58) | (1 << 57), cell];
+        let mut array = std::mem::MaybeUninit::<ArrowArray>::uninit();
+        let mut schema = std::mem::MaybeUninit::<ArrowSchema>::uninit();
+        assert_eq!(a5_cell_to_lon_lat_arrow(cells.as_ptr(), cells.len(), std::ptr::null(), array.as_mut_ptr(), schema.as_mut_ptr()), A5ErrorCode::None);
+        let (mut array, mut schema) = unsafe { (array.assume_init(), schema.assume_init()) };
+
+        let format = |s: &ArrowSchema| unsafe { CStr::from_ptr(s.format) }.to_str().unwrap().to_string();
+        let name = |s: &ArrowSchema| unsafe { CStr::from_ptr(s.name) }.to_str().unwrap().to_string();
+        assert_eq!((format(&schema), schema.n_children), ("+s".to_string(), 2));
+        let fields: Vec<&ArrowSchema> = (0..2).map(|i| unsafe { &**schema.children.add(i) }).collect();
+        assert_eq!(fields.iter().map(|f| (format(f), name(f))).collect::<Vec<_>>(), vec![("g".to_string(), "lon".to_string()), ("g".to_string(), "lat".to_string())]);
+
+        assert_eq!((array.length, array.null_count, array.n_buffers, array.n_children), (3, 1, 1, 2));
+        let validity = unsafe { *(*array.buffers as *const u8) };
+        assert_eq!(validity & 0b111, 0b101);
+        let lon = unsafe { &**array.children };
+        let values = unsafe { std::slice::from_raw_parts(*lon.buffers.add(1) as *const f64, 3) };
+        let center = a5::cell_to_lonlat(cell).unwrap();
+        assert_eq!(values[0], center.longitude());
+        assert!(values[1].is_nan());
+
+        unsafe {
+            (array.release.unwrap())(&mut array);
+            (schema.release.unwrap())(&mut schema);
+        }
+        assert!(array.release.is_none() && schema.release.is_none());
+    }
+
+    #[test]
+    fn batch_validity_masks() {
+        let coords: Vec<LonLatDegrees> = (0..10).map(|i| LonLatDegrees { lon: i as f64 * 10.0, lat: 45.0 }).collect();
+        let convert = |in_validity: &[u8]| {
+            let mut out_validity = [0xffu8; 2];
+            let cells = cell_array_to_vec(a5_lon_lat_to_cell_batch(coords.as_ptr(), coords.len(), 8, if in_validity.is_empty() { std::ptr::null() } else { in_validity.as_ptr() }, out_validity.as_mut_ptr()));
+            (cells, out_validity)
+        };
+
+        let (cells, out) = convert(&[]);
+        assert!(cells.iter().all(|&c| c != A5_INVALID_CELL));
+        assert_eq!(out, [0xff, 0b11]);
+
+        let (cells, out) = convert(&[0, 0]);
+        assert!(cells.iter().all(|&c| c == A5_INVALID_CELL));
+        assert_eq!(out, [0, 0]);
+
+        // Rows 1 and 8 null on input
+        let mut out = [0u8; 2];
+        let mut cells = cell_array_to_vec(a5_lon_lat_to_cell_batch(coords.as_ptr(), coords.len(), 8, [0b1111_1101, 0b10].as_ptr(), out.as_mut_ptr()));
+        assert_eq!(cells.len(), 10);
+        assert_eq!(out, [0b1111_1101, 0b10]);
+        assert!(cells.iter().enumerate().all(|(i, &c)| (c == A5_INVALID_CELL) == [1, 8].contains(&i)));
+
+        // Row 0 null on input, row 3 present but not a valid cell; rows 1 and 8
+        // hold A5_INVALID_CELL, which stays invalid on the way back
+        cells[3] = (12 << 58) | (1 << 57);
+        let in_validity = [0b1111_1110, 0b11];
+        let mut out = [0u8; 2];
+        let mut centers = a5_cell_to_lon_lat_batch(cells.as_ptr(), cells.len(), in_validity.as_ptr(), out.as_mut_ptr());
+        assert_eq!(centers.len, 10);
+        assert_eq!(out, [0b1111_0100, 0b10]);
+        let points = unsafe { std::slice::from_raw_parts(centers.data, centers.len) };
+        assert!([1, 8].iter().all(|&i| points[i].lon.is_nan() && points[i].lat.is_nan()));
+        a5_free_lonlatdegrees_array_ptr(&mut centers);
+
+        let (mut lon, mut lat, mut valid) = ([0.0; 10], [0.0; 10], [true; 10]);
+        assert_eq!(a5_cell_to_lon_lat_batch_soa(cells.as_ptr(), cells.len(), in_validity.as_ptr(), lon.as_mut_ptr(), lat.as_mut_ptr(), valid.as_mut_ptr()), 6);
+        assert!(!valid[0] && lon[0].is_nan() && valid[2] && !valid[3]);
+        assert!(!valid[1] && !valid[8] && lon[1].is_nan() && lat[8].is_nan());
+
+        let (mut array, mut schema) = (std::mem::MaybeUninit::<ArrowArray>::uninit(), std::mem::MaybeUninit::<ArrowSchema>::uninit());
+        assert_eq!(a5_cell_to_lon_lat_arrow(cells.as_ptr(), cells.len(), in_validity.as_ptr(), array.as_mut_ptr(), schema.as_mut_ptr()), A5ErrorCode::None);
+        let (mut array, mut schema) = unsafe { (array.assume_init(), schema.assume_init()) };
+        assert_eq!(array.null_count, 4);
+        assert_eq!(unsafe { std::slice::from_raw_parts(*array.buffers as *const u8, 2) }, [0b1111_0100, 0b10]);
+        unsafe {
+            (array.release.unwrap())(&mut array);
+            (schema.release.unwrap())(&mut schema);
+        }
+    }
+
+    #[test]
+    fn cell_range_covers_exactly_the_descendants() {
+        let face = a5::get_res0_cells().unwrap()[3];
+        let quintant = a5::cell_to_children(face, None).unwrap()[2];
+        let cell = a5::lonlat_to_cell(a5::LonLat::new(-122.4, 37.8), 5).unwrap();
+        for (cell, resolution) in [(face, 0), (face, 1), (face, 3), (quintant, 2), (quintant, 4), (cell, 5), (cell, 9), (cell, 29)] {
+            let (mut min, mut max) = (0, 0);
+            assert_eq!(a5_cell_to_range(cell, resolution, &mut min, &mut max), A5ErrorCode::None);
+            // From resolution 2 on, consecutive ids differ by twice the marker bit
+            if resolution >= 2 {
+                let step = (min & min.wrapping_neg()) << 1;
+                assert_eq!((max - min) / step + 1, a5_cell_to_children_size(cell, resolution).value);
+            }
+            if resolution < 10 {
+                let children = a5::cell_to_children(cell, Some(resolution)).unwrap();
+                assert!(children.iter().all(|&child| (min..=max).contains(&child)));
+            }
+        }
+
+        let (mut min, mut max) = (0, 0);
+        assert_eq!(a5_cell_to_range(cell, 30, &mut min, &mut max), A5ErrorCode::InvalidResolution);
+        assert_eq!(a5_cell_to_range(cell, 4, &mut min, &mut max), A5ErrorCode::InvalidResolution);
+        assert_eq!(a5_cell_to_range(cell, 8, std::ptr::null_mut(), &mut max), A5ErrorCode::NullPointer);
+    }
+
+    #[test]
+    fn multipolygon_dissolves_shared_edges() {
+        let region = square(10.0, 40.0, 12.0, 42.0);
+        let mut cells = cell_array_to_vec(a5_polygon_to_cells(region.as_ptr(), region.len(), 8, FillMode::Center));
+        let outline = cells_outline(&cells).unwrap();
+        assert_eq!(outline.len(), 1);
+        assert_eq!(outline[0].len(), 1);
+        assert!(geometry::signed_area(&outline[0][0]) > 0.0);
+
+        // Compacting changes nothing, since the set is expanded back
+        let compact = compact_cells(&cells).unwrap();
+        assert!(compact.len() < cells.len());
+        assert_eq!(cells_outline(&compact).unwrap(), outline);
+
+        // A cell removed from the middle leaves a clockwise hole
+        let middle = a5::lonlat_to_cell(a5::LonLat::new(11.0, 41.0), 8).unwrap();
+        cells.retain(|&cell| cell != middle);
+        let outline = cells_outline(&cells).unwrap();
+        assert_eq!(outline[0].len(), 2);
+        assert!(geometry::signed_area(&outline[0][1]) < 0.0);
+        assert_eq!(outline[0][1].len(), boundary_points(middle).unwrap().len());
+
+        let crossing = square(178.0, -18.0, 182.0, -14.0);
+        let crossing = cell_array_to_vec(a5_polygon_to_cells(crossing.as_ptr(), crossing.len(), 6, FillMode::Center));
+        assert_eq!(cells_outline(&crossing).unwrap().iter().map(Vec::len).collect::<Vec<_>>(), vec![1]);
+
+        let far = a5::lonlat_to_cell(a5::LonLat::new(-60.0, -20.0), 8).unwrap();
+        let json = take_string(a5_cells_to_multipolygon([middle, far].as_ptr(), 2));
+        assert!(json.starts_with("{\"type\":\"MultiPolygon\",\"coordinates\":[[[["));
+        assert_eq!(cells_outline(&[middle, far]).unwrap().len(), 2);
+
+        // The quintants of a polar face dissolve back into a cap around the pole
+        let face = a5::lonlat_to_cell(a5::LonLat::new(0.0, 90.0), 0).unwrap();
+        let cap = cells_outline(&a5::cell_to_children(face, None).unwrap()).unwrap();
+        assert_eq!((cap.len(), cap[0].len()), (1, 1));
+        assert!(cap[0][0].iter().any(|&(_, lat)| lat == 90.0));
+
+        // Leaving out that face leaves the rest of the sphere with a hole
+        let rest: Vec<u64> = a5::get_res0_cells().unwrap().into_iter().filter(|&cell| cell != face).collect();
+        let world = cells_outline(&rest).unwrap();
+        assert_eq!((world.len(), world[0].len()), (1, 2));
+        assert!(a5_cells_to_multipolygon([0].as_ptr(), 1).is_null());
+
+        // A coarse cell beside a fine one is refused before it is expanded
+        let fine = a5::lonlat_to_cell(a5::LonLat::new(10.0, 45.0), 20).unwrap();
+        let mixed = [rest[0], fine];
+        assert_eq!(cells_outline(&mixed).unwrap_err().code, A5ErrorCode::OutOfBounds);
+        assert!(a5_cells_to_multipolygon(mixed.as_ptr(), mixed.len()).is_null());
+    }
+
+    #[test]
+    fn are_neighbors_requires_a_shared_edge() {
+        let cell = a5::lonlat_to_cell(a5::LonLat::new(-122.4, 37.8), 9).unwrap();
+        let edge = cell_array_to_vec(a5_cell_to_neighbors(cell));
+        let corner: Vec<u64> = a5::grid_disk_vertex(cell, 1).unwrap().into_iter().filter(|c| *c != cell && !edge.contains(c)).collect();
+        assert!(!corner.is_empty());
+        assert!(edge.iter().all(|&n| a5_are_neighbors(cell, n) && a5_are_neighbors(n, cell)));
+        assert!(corner.iter().all(|&n| !a5_are_neighbors(cell, n)));
+
+        let far = a5::lonlat_to_cell(a5::LonLat::new(-122.4, 38.8), 9).unwrap();
+        assert!(!a5_are_neighbors(cell, far));
+        assert!(!a5_are_neighbors(cell, cell));
+        assert!(!a5_are_neighbors(cell, a5::cell_to_parent(edge[0], None).unwrap()));
+        assert!(!a5_are_neighbors(cell, 0));
+
+        let face = a5::get_res0_cells().unwrap()[0];
+        let adjacent = cell_array_to_vec(a5_cell_to_neighbors(face));
+        assert!(a5_are_neighbors(face, adjacent[0]));
+    }
+
+    #[test]
+    fn grid_ring_is_disk_minus_inner_disk() {
+        // Just inside a dodecahedron vertex, where three faces meet
+        let face = a5::get_res0_cells().unwrap()[0];
+        let corner = boundary_points(face).unwrap()[0];
+        let center = a5::cell_to_lonlat(face).unwrap();
+        let near = a5::LonLat::new(corner.0 + (center.longitude() - corner.0) * 0.001, corner.1 + (center.latitude() - corner.1) * 0.001);
+        let origin = a5::lonlat_to_cell(near, 6).unwrap();
+
+        let disk = |k: usize| a5::uncompact(&a5::grid_disk(origin, k).unwrap(), 6).unwrap().into_iter().collect::<HashSet<u64>>();
+        assert_eq!(cell_array_to_vec(a5_grid_ring(origin, 0)), vec![origin]);
+        for k in 1..=3 {
+            let ring = cell_array_to_vec(a5_grid_ring(origin, k));
+            let mut expected: Vec<u64> = disk(k as usize).difference(&disk(k as usize - 1)).copied().collect();
+            expected.sort_unstable();
+            assert_eq!(ring, expected);
+            assert!(ring.iter().all(|&cell| grid_distance(origin, cell).unwrap() == k as u64));
+        }
+        let faces: HashSet<u8> = cell_array_to_vec(a5_grid_ring(origin, 3)).iter().map(|&cell| a5::core::serialization::deserialize(cell).unwrap().origin_id).collect();
+        assert_eq!(faces.len(), 3);
+
+        assert_eq!(cell_array_to_vec(a5_grid_ring(face, 1)), cell_array_to_vec(a5_cell_to_neighbors(face)));
+        let negative = a5_grid_ring(origin, -1);
+        assert_eq!(negative.code, A5ErrorCode::OutOfBounds);
+        a5_free_cell_array(negative);
+    }
+
+    #[test]
+    fn geodesic_cells_are_a_connected_path() {
+        let cells = cell_array_to_vec(a5_cells_along_geodesic(2.35, 48.86, 13.40, 52.52, 7));
+        assert_eq!(cells[0], a5::lonlat_to_cell(a5::LonLat::new(2.35, 48.86), 7).unwrap());
+        assert_eq!(cells[cells.len() - 1], a5::lonlat_to_cell(a5::LonLat::new(13.40, 52.52), 7).unwrap());
+        assert!(cells.windows(2).all(|pair| a5_are_neighbors(pair[0], pair[1])));
+        assert_eq!(cells.iter().collect::<HashSet<_>>().len(), cells.len());
+
+        let crossing = cell_array_to_vec(a5_cells_along_geodesic(179.5, 10.0, -179.5, 10.5, 6));
+        assert!(crossing.windows(2).all(|pair| a5_are_neighbors(pair[0], pair[1])));
+        assert!(crossing.iter().all(|&cell| a5::cell_to_lonlat(cell).unwrap().longitude().rem_euclid(360.0) > 178.0));
+
+        let point = cell_array_to_vec(a5_cells_along_geodesic(10.0, 10.0, 10.0, 10.0, 9));
+        assert_eq!(point.len(), 1);
+        for (result, code) in [(a5_cells_along_geodesic(0.0, 0.0, 180.0, 0.0, 5), A5ErrorCode::InvalidCoordinate), (a5_cells_along_geodesic(0.0, 0.0, 90.0, 0.0, 30), A5ErrorCode::OutOfBounds)] {
+            assert_eq!(result.code, code);
+            a5_free_cell_array(result);
+        }
+    }
+
+    #[test]
+    fn every_cell_but_a_quintant_is_a_pentagon() {
+        for (resolution, count) in [(0, 12), (1, 0), (2, 240), (3, 960)] {
+            let pentagons = cell_array_to_vec(a5_get_pentagon_cells(resolution));
+            assert_eq!(pentagons.len(), count);
+            assert!(pentagons.iter().all(|&cell| a5_is_pentagon(cell) && cell_array_to_vec(a5_cell_to_neighbors(cell)).len() == 5));
+        }
+        let quintant = a5::cell_to_children(a5::get_res0_cells().unwrap()[0], None).unwrap()[0];
+        assert!(!a5_is_pentagon(quintant) && !a5_is_pentagon(0));
+        for resolution in [13, 15, 30] {
+            let mut fine = a5_get_pentagon_cells(resolution);
+            assert_eq!((fine.code, fine.len), (A5ErrorCode::OutOfBounds, 0));
+            a5_free_cell_array_ptr(&mut fine);
+        }
+
+        let faces: Vec<u64> = a5::get_res0_cells().unwrap().iter().map(|&cell| a5_cell_to_face(cell).value).collect();
+        assert_eq!(faces, (0..12).collect::<Vec<_>>());
+        let face = a5::get_res0_cells().unwrap()[7];
+        let fine = a5::cell_to_children(face, Some(6)).unwrap()[100];
+        assert_eq!(a5_cell_to_face(fine).value, 7);
+        let invalid = a5_cell_to_face(0);
+        assert_eq!(invalid.code, A5ErrorCode::InvalidCell);
+        a5_free_string(invalid.error);
+    }
+
+    #[test]
+    fn local_ij_round_trips_within_a_quintant() {
+        let origin = a5::lonlat_to_cell(a5::LonLat::new(-122.4, 37.8), 8).unwrap();
+        let quintant = a5::cell_to_parent(origin, Some(1)).unwrap();
+        let (mut i, mut j) = (7, 7);
+        assert_eq!(a5_cell_to_local_ij(origin, origin, &mut i, &mut j), A5ErrorCode::None);
+        assert_eq!((i, j), (0, 0));
+
+        let disk = a5::uncompact(&a5::grid_disk(origin, 4).unwrap(), 8).unwrap();
+        let mut seen = HashSet::new();
+        for &cell in disk.iter().filter(|&&cell| a5::cell_to_parent(cell, Some(1)).unwrap() == quintant) {
+            assert_eq!(a5_cell_to_local_ij(origin, cell, &mut i, &mut j), A5ErrorCode::None);
+            assert!(seen.insert((i, j)));
+            let back = a5_local_ij_to_cell(origin, i, j);
+            assert_eq!((back.code, back.value), (A5ErrorCode::None, cell));
+        }
+        assert!(seen.len() > 20);
+
+        let distant = a5::lonlat_to_cell(a5::LonLat::new(100.0, -40.0), 8).unwrap();
+        assert_eq!(a5_cell_to_local_ij(origin, distant, &mut i, &mut j), A5ErrorCode::OutOfBounds);
+        let outside = a5_local_ij_to_cell(origin, 0, 1 << 20);
+        assert_eq!(outside.code, A5ErrorCode::OutOfBounds);
+        a5_free_string(outside.error);
+        assert_eq!(a5_cell_to_local_ij(origin, quintant, &mut i, &mut j), A5ErrorCode::InvalidResolution);
+    }
+
+    #[test]
+    fn lon_lat_to_cell_validates_coordinates() {
+        for (lon, lat) in [(0.0, f64::NAN), (f64::INFINITY, 0.0), (0.0, 91.0), (0.0, -90.5)] {
+            let result = a5_lon_lat_to_cell(lon, lat, 5);
+            assert_eq!(result.code, A5ErrorCode::InvalidCoordinate, "({lon}, {lat})");
+            a5_free_string(result.error);
+        }
+        let wrapped = a5_lon_lat_to_cell(540.0, 10.0, 5);
+        assert_eq!(wrapped.value, a5_lon_lat_to_cell(180.0, 10.0, 5).value);
+        assert_eq!(wrapped.value, a5_lon_lat_to_cell(-180.0, 10.0, 5).value);
+        assert_eq!(a5_lon_lat_to_cell(-300.0, 10.0, 5).value, a5_lon_lat_to_cell(60.0, 10.0, 5).value);
+        assert_eq!(a5_lon_lat_to_cell(0.0, 90.0, 5).code, A5ErrorCode::None);
+
+        let coords = [LonLatDegrees { lon: 10.0, lat: 10.0 }, LonLatDegrees { lon: f64::NAN, lat: 10.0 }, LonLatDegrees { lon: 10.0, lat: 95.0 }];
+        let mut out = [0u8];
+        let cells = cell_array_to_vec(a5_lon_lat_to_cell_batch(coords.as_ptr(), coords.len(), 5, std::ptr::null(), out.as_mut_ptr()));
+        assert_eq!((cells[1], cells[2], out[0]), (A5_INVALID_CELL, A5_INVALID_CELL, 0b001));
+        assert!(!a5_cell_contains_point(cells[0], 10.0, f64::NAN));
+    }
+
+    #[test]
+    fn boundary_split_at_antimeridian() {
+        let lonlats = |arr: &LonLatDegreesArray| unsafe { std::slice::from_raw_parts(arr.data, arr.len) }.to_vec();
+        let cell = a5::lonlat_to_cell(a5::LonLat::new(179.999, -16.0), 6).unwrap();
+        let boundary = a5_cell_to_boundary_split(cell);
+        assert_eq!(boundary.code, A5ErrorCode::None);
+        assert!(boundary.split);
+        let (first, second) = (lonlats(&boundary.first), lonlats(&boundary.second));
+        assert!(first.len() >= 3 && second.len() >= 3);
+        assert!(first.iter().chain(&second).all(|p| (-180.0..=180.0).contains(&p.lon)));
+        assert!(first.iter().chain(&second).any(|p| p.lon.abs() == 180.0));
+        a5_free_split_boundary(boundary);
+
+        let inland = a5_cell_to_boundary_split(a5::lonlat_to_cell(a5::LonLat::new(10.0, 45.0), 6).unwrap());
+        assert!(!inland.split && inland.first.len == 5 && inland.second.len == 0);
+        a5_free_split_boundary(inland);
+
+        let mut invalid = a5_cell_to_boundary_split(0);
+        assert_eq!(invalid.code, A5ErrorCode::InvalidCell);
+        assert!(invalid.first.data.is_null());
+        a5_free_split_boundary_ptr(&mut invalid);
+        assert!(invalid.error.is_null());
+
+        // A second free through the pointer is a no-op
+        let mut twice = a5_cell_to_boundary_split(cell);
+        a5_free_split_boundary_ptr(&mut twice);
+        assert!(twice.first.data.is_null() && twice.second.data.is_null() && twice.error.is_null());
+        a5_free_split_boundary_ptr(&mut twice);
+        a5_free_split_boundary_ptr(std::ptr::null_mut());
+    }
+
+    #[test]
+    fn bbox_cells_cover_the_box() {
+        let cells = cell_array_to_vec(a5_bbox_to_cells(10.0, 40.0, 11.0, 41.0, 7));
+        let region = square(10.0, 40.0, 11.0, 41.0);
+        assert_eq!(cells, cell_array_to_vec(a5_polygon_to_cells(region.as_ptr(), region.len(), 7, FillMode::Intersects)));
+        for lon in [10.0, 10.5, 11.0] {
+            for lat in [40.0, 40.5, 41.0] {
+                assert!(cells.contains(&a5::lonlat_to_cell(a5::LonLat::new(lon, lat), 7).unwrap()));
+            }
+        }
+
+        let wrapped = cell_array_to_vec(a5_bbox_to_cells(179.0, -17.0, -179.0, -15.0, 6));
+        for lon in [179.0, 179.9, -179.9, -179.0] {
+            assert!(wrapped.contains(&a5::lonlat_to_cell(a5::LonLat::new(lon, -16.0), 6).unwrap()));
+        }
+        assert!(!wrapped.contains(&a5::lonlat_to_cell(a5::LonLat::new(0.0, -16.0), 6).unwrap()));
+
+        let band = cell_array_to_vec(a5_bbox_to_cells(-180.0, -1.0, 180.0, 1.0, 3));
+        assert!((-180..180).step_by(15).all(|lon| band.contains(&a5::lonlat_to_cell(a5::LonLat::new(lon as f64, 0.0), 3).unwrap())));
+        let inverted = a5_bbox_to_cells(0.0, 10.0, 1.0, 5.0, 5);
+        assert_eq!(inverted.code, A5ErrorCode::InvalidCoordinate);
+        a5_free_cell_array(inverted);
+    }
+
+    #[test]
+    fn perimeters_are_densified_and_even() {
+        let perimeters: Vec<f64> = a5::get_res0_cells().unwrap().iter().map(|&cell| a5_cell_perimeter(cell).value).collect();
+        let (min, max) = perimeters.iter().fold((f64::INFINITY, 0.0f64), |(lo, hi), &p| (lo.min(p), hi.max(p)));
+        // The two polar faces come out 0.4% shorter than the other ten
+        assert!(max / min < 1.005, "{perimeters:?}");
+
+        let cell = a5::lonlat_to_cell(a5::LonLat::new(10.0, 45.0), 4).unwrap();
+        let ring = boundary_points(cell).unwrap();
+        let chords: f64 = (0..ring.len()).map(|i| {
+            let (a, b) = (ring[i], ring[(i + 1) % ring.len()]);
+            geometry::haversine_distance(a.0, a.1, b.0, b.1)
+        }).sum();
+        let perimeter = a5_cell_perimeter(cell);
+        assert_eq!(perimeter.code, A5ErrorCode::None);
+        assert!(perimeter.value >= chords && perimeter.value < chords * 1.001);
+        assert!(a5_cell_perimeter(0).value.is_nan());
+    }
+
+    #[test]
+    fn random_cells_and_points_are_reproducible() {
+        for resolution in [0, 1, 2, 9, 30] {
+            let cells: Vec<u64> = (0..20).map(|seed| a5_random_cell(resolution, seed)).collect();
+            assert!(cells.iter().all(|&cell| a5_is_valid_cell(cell) && a5::get_resolution(cell) == resolution));
+            assert_eq!(cells, (0..20).map(|seed| a5_random_cell(resolution, seed)).collect::<Vec<_>>());
+        }
+        assert!((0..20).map(|seed| a5_random_cell(9, seed)).collect::<HashSet<_>>().len() > 15);
+        assert_eq!(a5_random_cell(31, 0), A5_INVALID_CELL);
+
+        let polar = a5::lonlat_to_cell(a5::LonLat::new(0.0, 90.0), 0).unwrap();
+        let crossing = a5::lonlat_to_cell(a5::LonLat::new(179.999, -16.0), 6).unwrap();
+        for cell in [a5_random_cell(8, 7), polar, crossing] {
+            let (mut lon, mut lat) = (0.0, 0.0);
+            for seed in 0..10 {
+                assert_eq!(a5_sample_point_in_cell(cell, seed, &mut lon, &mut lat), A5ErrorCode::None);
+                assert!(a5_cell_contains_point(cell, lon, lat));
+                let (mut again_lon, mut again_lat) = (0.0, 0.0);
+                a5_sample_point_in_cell(cell, seed, &mut again_lon, &mut again_lat);
+                assert_eq!((lon, lat), (again_lon, again_lat));
+            }
+        }
+        let (mut lon, mut lat) = (0.0, 0.0);
+        assert_eq!(a5_sample_point_in_cell(0, 1, &mut lon, &mut lat), A5ErrorCode::InvalidCell);
+    }
+
+    #[test]
+    fn simple_calls_keep_a_last_error_per_thread() {
+        let message = || unsafe { CStr::from_ptr(a5_last_error_message()) }.to_str().unwrap().to_string();
+        let mut cell = 0;
+        assert_eq!(a5_lon_lat_to_cell_simple(10.0, 20.0, 5, &mut cell), A5ErrorCode::None);
+        assert_eq!(cell, a5_lon_lat_to_cell(10.0, 20.0, 5).value);
+        assert!(a5_last_error_message().is_null());
+        assert_eq!(a5_lon_lat_to_cell_simple(10.0, 20.0, 5, std::ptr::null_mut()), A5ErrorCode::NullPointer);
+        let mut center = LonLatDegrees { lon: 0.0, lat: 0.0 };
+        assert_eq!(a5_cell_to_lon_lat_simple(cell, &mut center), A5ErrorCode::None);
+        let (mut lon, mut lat) = (f64::NAN, f64::NAN);
+        assert_eq!(a5_cell_to_lon_lat_out(cell, &mut lon, &mut lat), A5ErrorCode::None);
+        assert_eq!((lon, lat), (center.lon, center.lat));
+        assert_eq!(a5_cell_to_lon_lat_out(cell, &mut lon, std::ptr::null_mut()), A5ErrorCode::NullPointer);
+        assert_eq!(a5_cell_to_lon_lat_out(u64::MAX - 1, &mut lon, &mut lat), A5ErrorCode::InvalidCell);
+        assert_eq!((lon, lat), (center.lon, center.lat));
+        assert!(!a5_last_error_message().is_null());
+        let mut parent = 0;
+        assert_eq!(a5_cell_to_parent_simple(cell, 2, &mut parent), A5ErrorCode::None);
+        assert_eq!(parent, a5::cell_to_parent(cell, Some(2)).unwrap());
+
+        // Each thread fails with its own resolution and must only ever see
+        // that message, however the threads interleave
+        let barrier = std::sync::Barrier::new(8);
+        std::thread::scope(|scope| {
+            for t in 0..8 {
+                let barrier = &barrier;
+                scope.spawn(move || {
+                    let resolution = 40 + t;
+                    let mut cell = 0;
+                    barrier.wait();
+                    for _ in 0..200 {
+                        assert_eq!(a5_lon_lat_to_cell_simple(1.0, 2.0, resolution, &mut cell), A5ErrorCode::InvalidResolution);
+                        std::thread::yield_now();
+                        assert!(message().starts_with(&format!("resolution {}", resolution)));
+                    }
+                    assert_eq!(a5_lon_lat_to_cell_simple(1.0, 2.0, 3, &mut cell), A5ErrorCode::None);
+                    assert!(a5_last_error_message().is_null());
+                });
+            }
+        });
+        assert_eq!(a5_lon_lat_to_cell_simple(1.0, 2.0, 3, std::ptr::null_mut()), A5ErrorCode::NullPointer);
+        assert_eq!(message(), "null pointer: output");
+    }
+
+    #[test]
+    fn init_and_shutdown_are_idempotent() {
+        let _cache = BOUNDARY_CACHE.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        assert_eq!(a5_init(), A5ErrorCode::None);
+        assert_eq!(a5_init(), A5ErrorCode::None);
+        assert_eq!(a5_lon_lat_to_cell_simple(0.0, 0.0, 99, std::ptr::null_mut()), A5ErrorCode::NullPointer);
+        assert!(!a5_last_error_message().is_null());
+        a5_shutdown();
+        a5_shutdown();
+        assert!(a5_last_error_message().is_null());
+        assert_eq!(a5_lon_lat_to_cell(10.0, 20.0, 5).code, A5ErrorCode::None);
+
+        #[cfg(feature = "boundary-cache")]
+        {
+            assert!(a5_set_boundary_cache_size(2));
+            let mut boundary = a5_cell_to_boundary(a5_lon_lat_to_cell(10.0, 20.0, 5).value, CellBoundaryOptions::new(false, None));
+            a5_free_lonlatdegrees_array_ptr(&mut boundary);
+            assert_eq!(cache::boundaries().len(), 1);
+            a5_shutdown();
+            assert_eq!(cache::boundaries().len(), 0);
+            assert!(a5_set_boundary_cache_size(0));
+        }
+    }
+
+    #[test]
+    fn res0_cells_are_cached() {
+        let read = || {
+            let arr = a5_get_res0_cells();
+            let cells = unsafe { std::slice::from_raw_parts(arr.data, arr.len) }.to_vec();
+            a5_free_cell_array(arr);
+            cells
+        };
+        let first = read();
+        assert_eq!(first, a5::get_res0_cells().unwrap());
+        assert_eq!(read(), first);
+        assert_eq!(a5_get_res0_cells_count(), 12);
+        let at: Vec<u64> = (0..a5_get_res0_cells_count()).map(|i| a5_get_res0_cell_at(i).value).collect();
+        assert_eq!(at, first);
+        let past = a5_get_res0_cell_at(12);
+        assert_eq!(past.code, A5ErrorCode::OutOfBounds);
+        a5_free_cstring(past.error);
+    }
+
+    #[test]
+    fn immediate_parents_climb_one_resolution_at_a_time() {
+        let mut cell = a5::lonlat_to_cell(a5::LonLat::new(-73.98, 40.75), 18).unwrap();
+        for resolution in (0..18).rev() {
+            let parent = a5_cell_to_immediate_parent(cell);
+            assert_eq!(parent.code, A5ErrorCode::None);
+            assert_eq!(a5::get_resolution(parent.value), resolution);
+            assert_eq!(parent.value, a5::cell_to_parent(cell, Some(resolution)).unwrap());
+            cell = parent.value;
+        }
+        let top = a5_cell_to_immediate_parent(cell);
+        assert_eq!(top.code, A5ErrorCode::InvalidResolution);
+        a5_free_cstring(top.error);
+        let invalid = a5_cell_to_immediate_parent(0);
+        assert_eq!(invalid.code, A5ErrorCode::InvalidCell);
+        a5_free_cstring(invalid.error);
+    }
+
+    #[test]
+    fn bearings_follow_the_compass() {
+        let close = |a: f64, b: f64| (a - b).abs() < 1e-9;
+        assert!(close(a5_bearing(0.0, 0.0, 0.0, 10.0), 0.0));
+        assert!(close(a5_bearing(0.0, 0.0, 10.0, 0.0), 90.0));
+        assert!(close(a5_bearing(0.0, 10.0, 0.0, 0.0), 180.0));
+        assert!(close(a5_bearing(0.0, 0.0, -10.0, 0.0), 270.0));
+        assert!(close(a5_bearing(179.0, 0.0, -179.0, 0.0), 90.0));
+        assert!(close(a5_bearing(10.0, 45.0, 10.0, 90.0), 0.0));
+        assert!(a5_bearing(5.0, 5.0, 5.0, 5.0).is_nan());
+        assert!(a5_bearing(0.0, 0.0, 180.0, 0.0).is_nan());
+
+        let here = a5::lonlat_to_cell(a5::LonLat::new(0.0, 0.0), 10).unwrap();
+        let north = a5::lonlat_to_cell(a5::LonLat::new(0.0, 5.0), 10).unwrap();
+        let bearing = a5_cell_bearing(here, north);
+        assert!(!(5.0..355.0).contains(&bearing));
+        assert!((a5_cell_bearing(north, here) - 180.0).abs() < 5.0);
+        assert!(a5_cell_bearing(here, here).is_nan());
+        assert!(a5_cell_bearing(here, 0).is_nan());
+    }
+
+    #[test]
+    fn densified_boundaries_follow_the_edges() {
+        let cell = a5::lonlat_to_cell(a5::LonLat::new(170.0, 60.0), 2).unwrap();
+        let original = boundary_points(cell).unwrap();
+        let read = |arr: LonLatDegreesArray| {
+            assert!(arr.error.is_null());
+            let points: Vec<geometry::Point> = unsafe { std::slice::from_raw_parts(arr.data, arr.len) }.iter().map(|p| (p.lon, p.lat)).collect();
+            a5_free_lonlatdegrees_array(arr);
+            points
+        };
+        assert_eq!(read(a5_cell_to_boundary_densified(cell, 0)), original);
+
+        let densified = read(a5_cell_to_boundary_densified(cell, 7));
+        assert_eq!(densified.len(), original.len() * 8);
+        for (i, edge) in densified.chunks(8).enumerate() {
+            let (start, end) = (original[i], original[(i + 1) % original.len()]);
+            assert_eq!(edge[0], start);
+            let span = geometry::haversine_distance(start.0, start.1, end.0, end.1);
+            for p in &edge[1..] {
+                let via = geometry::haversine_distance(start.0, start.1, p.0, p.1) + geometry::haversine_distance(p.0, p.1, end.0, end.1);
+                assert!((via - span).abs() < 1e-6 * span);
+            }
+        }
+
+        let huge = a5_cell_to_boundary_densified(cell, u32::MAX);
+        assert_eq!(huge.code, A5ErrorCode::OutOfBounds);
+        a5_free_lonlatdegrees_array(huge);
+        let invalid = a5_cell_to_boundary_densified(0, 3);
+        assert_eq!(invalid.code, A5ErrorCode::InvalidCell);
+        a5_free_lonlatdegrees_array(invalid);
+    }
+
+    #[test]
+    fn simplified_rings_stay_closed_and_within_tolerance() {
+        // A square with many near-collinear vertices along its edges
+        let mut ring: Vec<LonLatDegrees> = Vec::new();
+        for (start, end) in [((0.0, 0.0), (1.0, 0.0)), ((1.0, 0.0), (1.0, 1.0)), ((1.0, 1.0), (0.0, 1.0)), ((0.0, 1.0), (0.0, 0.0))] {
+            let (_, along) = geometry::geodesic(start, end).unwrap();
+            for k in 0..25 {
+                let (lon, lat) = along(k as f64 / 25.0);
+                ring.push(LonLatDegrees { lon, lat: lat + if k % 2 == 1 { 1e-6 } else { 0.0 } });
+            }
+        }
+        ring.push(ring[0]);
+
+        let mut len = 0;
+        let ptr = a5_simplify_ring(ring.as_ptr(), ring.len(), 10.0, &mut len);
+        let simplified: Vec<geometry::Point> = unsafe { std::slice::from_raw_parts(ptr, len) }.iter().map(|p| (p.lon, p.lat)).collect();
+        a5_free_lonlatdegrees(ptr, len);
+        assert_eq!(simplified.len(), 5);
+        assert_eq!(simplified.first(), simplified.last());
+        assert_eq!(simplified[0], (ring[0].lon, ring[0].lat));
+
+        // Nothing collapses below a quadrilateral, however coarse the tolerance
+        let ptr = a5_simplify_ring(ring.as_ptr(), ring.len(), 1e9, &mut len);
+        a5_free_lonlatdegrees(ptr, len);
+        assert_eq!(len, 5);
+        // The 0.1 m wobbles survive a finer tolerance
+        let ptr = a5_simplify_ring(ring.as_ptr(), ring.len(), 0.01, &mut len);
+        a5_free_lonlatdegrees(ptr, len);
+        assert!(len >= 4 * 12 + 5);
+
+        assert!(a5_simplify_ring(ring.as_ptr(), ring.len(), -1.0, &mut len).is_null());
+        assert_eq!(len, 0);
+        assert!(a5_simplify_ring(std::ptr::null(), 3, 1.0, &mut len).is_null());
+    }
+
+    #[test]
+    fn descendant_counts_match_repeated_subdivision() {
+        fn count(cell: u64, resolution: i32) -> u64 {
+            if a5::get_resolution(cell) == resolution {
+                return 1;
+            }
+            a5::cell_to_children(cell, None).unwrap().into_iter().map(|child| count(child, resolution)).sum()
+        }
+        let face = a5::get_res0_cells().unwrap()[5];
+        let cell = a5::lonlat_to_cell(a5::LonLat::new(139.7, 35.7), 6).unwrap();
+        for (cell, resolution) in [(face, 1), (face, 3), (a5::cell_to_parent(cell, Some(1)).unwrap(), 4), (cell, 7), (cell, 9)] {
+            let descendants = a5_cell_descendant_count(cell, resolution);
+            assert_eq!(descendants.code, A5ErrorCode::None);
+            assert_eq!(descendants.value, count(cell, resolution));
+        }
+        assert_eq!(a5_cell_descendant_count(face, MAX_RESOLUTION).value, 5 * 4u64.pow(29));
+        for resolution in [6, 5, 31] {
+            let invalid = a5_cell_descendant_count(cell, resolution);
+            assert_eq!(invalid.code, A5ErrorCode::InvalidResolution);
+            a5_free_cstring(invalid.error);
+        }
+    }
+
+    #[test]
+    fn centroids_lie_inside_their_cells() {
+        let (mut lon, mut lat, mut area) = (0.0, 0.0, 0.0);
+        let mut cells = vec![a5::get_res0_cells().unwrap()[2]];
+        cells.extend([(1.0, 2.0, 1), (-60.0, -30.0, 5), (179.9, 10.0, 9), (20.0, 89.0, 12)].map(|(lon, lat, res)| a5::lonlat_to_cell(a5::LonLat::new(lon, lat), res).unwrap()));
+        for cell in cells {
+            assert_eq!(a5_cell_to_center_and_area(cell, &mut lon, &mut lat, &mut area), A5ErrorCode::None);
+            assert!(a5_cell_contains_point(cell, lon, lat));
+            assert_eq!(area, a5_cell_exact_area(cell).value);
+            let generator = a5::cell_to_lonlat(cell).unwrap();
+            assert!(geometry::haversine_distance(lon, lat, generator.longitude(), generator.latitude()) < 0.2 * area.sqrt());
+        }
+        assert_eq!(a5_cell_to_center_and_area(0, &mut lon, &mut lat, &mut area), A5ErrorCode::InvalidCell);
+        assert_eq!(a5_cell_to_center_and_area(a5::get_res0_cells().unwrap()[0], std::ptr::null_mut(), &mut lat, &mut area), A5ErrorCode::NullPointer);
+    }
+
+    #[test]
+    fn cells_export_to_csv() {
+        let cells = [a5::lonlat_to_cell(a5::LonLat::new(-0.1, 51.5), 7).unwrap(), a5::get_res0_cells().unwrap()[1]];
+        let read = |ptr: *mut std::os::raw::c_char| {
+            let text = unsafe { CStr::from_ptr(ptr) }.to_str().unwrap().to_string();
+            a5_free_cstring(ptr);
+            text
+        };
+        assert_eq!(read(a5_cells_to_csv(cells.as_ptr(), 2, false, false)), format!("a5\n{:016x}\n{:016x}\n", cells[0], cells[1]));
+        let full = read(a5_cells_to_csv(cells.as_ptr(), 2, true, true));
+        let lines: Vec<&str> = full.lines().collect();
+        assert_eq!(lines[0], "a5,center_lon,center_lat,resolution");
+        let fields: Vec<&str> = lines[1].split(',').collect();
+        let center = a5::cell_to_lonlat(cells[0]).unwrap();
+        assert_eq!(fields[0], format!("{:016x}", cells[0]));
+        assert_eq!((fields[1].parse::<f64>().unwrap(), fields[2].parse::<f64>().unwrap()), (center.longitude(), center.latitude()));
+        assert_eq!(fields[3], "7");
+        assert!(lines[2].ends_with(",0"));
+        assert_eq!(read(a5_cells_to_csv(std::ptr::null(), 0, false, true)), "a5,resolution\n");
+        assert!(a5_cells_to_csv([cells[0], 0].as_ptr(), 2, false, false).is_null());
+    }
+
+    #[test]
+    fn f32_boundaries_round_the_f64_ones() {
+        let cell = a5::lonlat_to_cell(a5::LonLat::new(179.99, -45.0), 14).unwrap();
+        let (mut len64, mut len32) = (0, 0);
+        let wide = a5_cell_to_boundary_flat(cell, true, &mut len64);
+        let narrow = a5_cell_to_boundary_f32(cell, true, &mut len32);
+        assert_eq!(len32, len64);
+        let (wide_values, narrow_values) = unsafe { (std::slice::from_raw_parts(wide, len64), std::slice::from_raw_parts(narrow, len32)) };
+        for (&w, &n) in wide_values.iter().zip(narrow_values) {
+            assert_eq!(n, w as f32);
+            assert!((n as f64 - w).abs() < 1e-5);
+        }
+        a5_free_f64_array(wide, len64);
+        a5_free_f32_array(narrow, len32);
+        assert!(a5_cell_to_boundary_f32(0, false, &mut len32).is_null());
+        assert_eq!(len32, 0);
+    }
+
+    #[test]
+    fn mercator_projection_matches_epsg_3857() {
+        let close = |a: geometry::Point, b: geometry::Point| (a.0 - b.0).abs() < 1e-6 && (a.1 - b.1).abs() < 1e-6;
+        assert!(close(geometry::web_mercator(0.0, 0.0), (0.0, 0.0)));
+        assert!(close(geometry::web_mercator(180.0, 0.0), (20_037_508.342_789_244, 0.0)));
+        // New York City, at its commonly published 3857 coordinate
+        assert!((geometry::web_mercator(-74.006, 40.7128).0 - -8_238_310.24).abs() < 0.01);
+        assert!((geometry::web_mercator(-74.006, 40.7128).1 - 4_970_071.58).abs() < 0.01);
+        assert!(close(geometry::web_mercator(10.0, 90.0), geometry::web_mercator(10.0, geometry::WEB_MERCATOR_MAX_LATITUDE)));
+        assert!((geometry::web_mercator(0.0, geometry::WEB_MERCATOR_MAX_LATITUDE).1 - 20_037_508.342_789_244).abs() < 1e-6);
+
+        let cell = a5::lonlat_to_cell(a5::LonLat::new(-74.0, 40.7), 9).unwrap();
+        let center = a5::cell_to_lonlat(cell).unwrap();
+        let (mut x, mut y) = (0.0, 0.0);
+        assert_eq!(a5_cell_to_center_mercator(cell, &mut x, &mut y), A5ErrorCode::None);
+        assert_eq!((x, y), geometry::web_mercator(center.longitude(), center.latitude()));
+        assert_eq!(a5_cell_to_center_mercator(0, &mut x, &mut y), A5ErrorCode::InvalidCell);
+
+        let polar = a5::lonlat_to_cell(a5::LonLat::new(0.0, 89.9), 3).unwrap();
+        let boundary = a5_cell_to_boundary_mercator(polar);
+        let points = unsafe { std::slice::from_raw_parts(boundary.data, boundary.len) };
+        assert_eq!(points.len(), boundary_points(polar).unwrap().len());
+        assert!(points.iter().all(|p| p.lat.is_finite() && p.lat <= 20_037_508.35));
+        a5_free_lonlatdegrees_array(boundary);
+    }
+
+    #[cfg(feature = "wasm")]
+    #[test]
+    fn wasm_bindings_use_hex_cells() {
+        let cell = wasm::lon_lat_to_cell_hex(2.35, 48.86, 8).unwrap();
+        assert_eq!(cell, format!("{:016x}", a5::lonlat_to_cell(a5::LonLat::new(2.35, 48.86), 8).unwrap()));
+        let center = a5::cell_to_lonlat(parse_hex_cell(&cell).unwrap()).unwrap();
+        assert_eq!(wasm::cell_to_lon_lat_pair(&cell).unwrap(), vec![center.longitude(), center.latitude()]);
+        assert_eq!(wasm::cell_to_boundary_flat(&cell).unwrap().len(), 2 * boundary_points(parse_hex_cell(&cell).unwrap()).unwrap().len());
+        let parent = wasm::cell_to_parent_hex(&cell, 6).unwrap();
+        assert!(wasm::cell_to_children_hex(&parent, 8).unwrap().contains(&cell));
+        assert!(wasm::lon_lat_to_cell_hex(0.0, 95.0, 8).is_err());
+        assert!(wasm::cell_to_lon_lat_pair("zz").is_err());
+        assert!(wasm::cell_to_children_hex(&parent, 40).is_err());
+        assert!(wasm::cell_to_parent_hex(&cell, -1).is_err());
+        assert!(wasm::cell_to_children_hex(&parent, 20).unwrap_err().contains("exceed the limit"));
+    }
+
+    #[cfg(feature = "python")]
+    #[test]
+    fn python_batches_mark_failed_rows() {
+        let cell = a5::lonlat_to_cell(a5::LonLat::new(12.5, 41.9), 10).unwrap();
+        let center = a5::cell_to_lonlat(cell).unwrap();
+        assert_eq!(python::cell_center(cell).unwrap(), (center.longitude(), center.latitude()));
+        assert!(python::cell_center(0).is_err());
+        let cells = python::cells_of(&[12.5, f64::NAN], &[41.9, 0.0], 10).unwrap();
+        assert_eq!(cells, vec![cell, A5_INVALID_CELL]);
+        assert!(python::cells_of(&[1.0], &[], 10).is_err());
+        assert!(python::cells_of(&[1.0], &[1.0], 31).is_err());
+        let (lons, lats) = python::centers_of(&[cell, 0]);
+        assert_eq!((lons[0], lats[0]), (center.longitude(), center.latitude()));
+        assert!(lons[1].is_nan() && lats[1].is_nan());
+    }
+
+    #[test]
+    fn descendants_come_level_by_level() {
+        let cell = a5::lonlat_to_cell(a5::LonLat::new(-3.7, 40.4), 4).unwrap();
+        let descendants = cell_array_to_vec(a5_cell_to_descendants(cell, 7));
+        assert_eq!(descendants.len() as u64, (5..=7).map(|r| a5_cell_descendant_count(cell, r).value).sum::<u64>());
+        assert!(descendants.windows(2).all(|w| a5::get_resolution(w[0]) <= a5::get_resolution(w[1])));
+        for resolution in 5..=7 {
+            let level: Vec<u64> = descendants.iter().copied().filter(|&c| a5::get_resolution(c) == resolution).collect();
+            assert_eq!(level, a5::cell_to_children(cell, Some(resolution)).unwrap());
+        }
+        let face = a5::get_res0_cells().unwrap()[9];
+        assert_eq!(cell_array_to_vec(a5_cell_to_descendants(face, 2)).len(), 5 + 20);
+
+        for (cell, resolution, code) in [(cell, 4, A5ErrorCode::InvalidResolution), (cell, 30, A5ErrorCode::OutOfBounds), (0, 5, A5ErrorCode::InvalidCell)] {
+            let invalid = a5_cell_to_descendants(cell, resolution);
+            assert_eq!(invalid.code, code);
+            a5_free_cell_array(invalid);
+        }
+    }
+
+    #[test]
+    fn snapping_error_stays_within_the_circumradius() {
+        let (mut cell, mut offset) = (0, 0.0);
+        let mut rng = SplitMix64(11);
+        for resolution in [1, 4, 9, 15] {
+            for _ in 0..40 {
+                let lon = rng.next_f64() * 360.0 - 180.0;
+                let lat = (rng.next_f64() * 2.0 - 1.0).asin().to_degrees();
+                assert_eq!(a5_lon_lat_to_cell_with_error(lon, lat, resolution, &mut cell, &mut offset), A5ErrorCode::None);
+                assert_eq!(cell, a5_lon_lat_to_cell(lon, lat, resolution).value);
+                // Edges bow outwards on the sphere, so measure out to the
+                // densified boundary rather than just the corners
+                let center = a5::cell_to_lonlat(cell).unwrap();
+                let boundary = a5::cell_to_boundary(cell, Some(a5::core::cell::CellToBoundaryOptions { closed_ring: false, segments: Some(16) })).unwrap();
+                let circumradius = boundary.iter().map(|p| geometry::haversine_distance(center.longitude(), center.latitude(), p.longitude(), p.latitude())).fold(0.0, f64::max);
+                assert!(offset <= circumradius * 1.001, "{offset} > {circumradius}");
+            }
+        }
+        let center = a5::cell_to_lonlat(cell).unwrap();
+        a5_lon_lat_to_cell_with_error(center.longitude(), center.latitude(), 15, &mut cell, &mut offset);
+        assert!(offset < 1e-6);
+        assert_eq!(a5_lon_lat_to_cell_with_error(0.0, 91.0, 5, &mut cell, &mut offset), A5ErrorCode::InvalidCoordinate);
+        assert_eq!(a5_lon_lat_to_cell_with_error(0.0, 0.0, 5, std::ptr::null_mut(), &mut offset), A5ErrorCode::NullPointer);
+    }
+
+    #[test]
+    fn parent_batch_rolls_up_per_row() {
+        let cells: Vec<u64> = [(1.0, 1.0, 8), (50.0, -20.0, 5), (-120.0, 60.0, 12), (0.0, 0.0, 3)].iter().map(|&(lon, lat, res)| a5::lonlat_to_cell(a5::LonLat::new(lon, lat), res).unwrap()).collect();
+        let resolutions = [2, 5, 0, 7];
+        let mut valid = [0u8; 1];
+        let parents = cell_array_to_vec(a5_cell_to_parent_batch(cells.as_ptr(), resolutions.as_ptr(), 4, std::ptr::null(), valid.as_mut_ptr()));
+        assert_eq!(parents[..3], [a5::cell_to_parent(cells[0], Some(2)).unwrap(), cells[1], a5::cell_to_parent(cells[2], Some(0)).unwrap()]);
+        assert_eq!(parents[3], A5_INVALID_CELL);
+        assert_eq!(valid[0], 0b0111);
+
+        let immediate = cell_array_to_vec(a5_cell_to_parent_batch(cells.as_ptr(), std::ptr::null(), 4, [0b1110u8].as_ptr(), valid.as_mut_ptr()));
+        assert_eq!(immediate[0], A5_INVALID_CELL);
+        for i in 1..4 {
+            assert_eq!(immediate[i], a5::cell_to_parent(cells[i], None).unwrap());
+        }
+        assert_eq!(valid[0], 0b1110);
+
+        let face = a5::get_res0_cells().unwrap()[0];
+        let rows = [face, 0];
+        let failed = cell_array_to_vec(a5_cell_to_parent_batch(rows.as_ptr(), std::ptr::null(), 2, std::ptr::null(), valid.as_mut_ptr()));
+        assert_eq!(failed, vec![A5_INVALID_CELL; 2]);
+        assert_eq!(valid[0], 0);
+    }
+
+    #[test]
+    fn cells_intersect_boxes_by_their_polygons() {
+        let cell = a5::lonlat_to_cell(a5::LonLat::new(10.0, 45.0), 6).unwrap();
+        let bbox = a5_cell_to_bbox(cell);
+        let (west, east, south, north) = (bbox.min_lon, bbox.max_lon, bbox.min_lat, bbox.max_lat);
+        assert!(a5_cell_intersects_bbox(cell, west - 1.0, south - 1.0, east + 1.0, north + 1.0));
+        assert!(a5_cell_intersects_bbox(cell, 9.99, 44.99, 10.01, 45.01));
+        assert!(!a5_cell_intersects_bbox(cell, east + 0.1, south, east + 1.0, north));
+        // A box straddling the cell's western edge
+        let ring = cell_ring(cell).unwrap();
+        let westmost = ring.iter().copied().min_by(|a, b| a.0.total_cmp(&b.0)).unwrap();
+        assert!(a5_cell_intersects_bbox(cell, westmost.0 - 0.5, westmost.1 - 0.01, westmost.0 + 0.01, westmost.1 + 0.01));
+        // A pentagon leaves at least one corner of its bounding box empty
+        let inset = 1e-3;
+        let corners = [(west + inset, south + inset), (east - inset, south + inset), (east - inset, north - inset), (west + inset, north - inset)];
+        let (x, y) = corners.into_iter().find(|&(x, y)| !geometry::ring_contains(&ring, x, y)).unwrap();
+        assert!(!a5_cell_intersects_bbox(cell, x - 1e-4, y - 1e-4, x + 1e-4, y + 1e-4));
+
+        let crossing = a5::lonlat_to_cell(a5::LonLat::new(179.95, 0.0), 9).unwrap();
+        assert!(a5_cell_intersects_bbox(crossing, 170.0, -5.0, -170.0, 5.0));
+        assert!(!a5_cell_intersects_bbox(crossing, -170.0, -5.0, 170.0, -4.0));
+        assert!(!a5_cell_intersects_bbox(0, -180.0, -90.0, 180.0, 90.0));
+        assert!(!a5_cell_intersects_bbox(cell, 0.0, 50.0, 20.0, 40.0));
+        for cell in polyfill::bbox_to_cells(5.0, 40.0, 12.0, 44.0, 5).unwrap() {
+            assert!(a5_cell_intersects_bbox(cell, 5.0, 40.0, 12.0, 44.0));
+        }
+    }
+
+    #[test]
+    fn oriented_boundaries_wind_as_asked() {
+        let mut cells = vec![a5::get_res0_cells().unwrap()[0], a5::get_res0_cells().unwrap()[11]];
+        cells.extend([(10.0, 45.0, 6), (-100.0, -30.0, 2), (179.9, 0.0, 8), (0.0, -89.9, 3)].map(|(lon, lat, res)| a5::lonlat_to_cell(a5::LonLat::new(lon, lat), res).unwrap()));
+        for cell in cells {
+            let original = boundary_points(cell).unwrap();
+            assert!(geometry::signed_area(&geometry::planar_ring(&original)) > 0.0);
+            for ccw in [true, false] {
+                let boundary = a5_cell_to_boundary_oriented(cell, ccw);
+                let ring: Vec<geometry::Point> = unsafe { std::slice::from_raw_parts(boundary.data, boundary.len) }.iter().map(|p| (p.lon, p.lat)).collect();
+                a5_free_lonlatdegrees_array(boundary);
+                assert_eq!(geometry::signed_area(&geometry::planar_ring(&ring)) > 0.0, ccw);
+                assert_eq!(ring.len(), original.len());
+            }
+        }
+        let invalid = a5_cell_to_boundary_oriented(0, true);
+        assert_eq!(invalid.code, A5ErrorCode::InvalidCell);
+        a5_free_lonlatdegrees_array(invalid);
+    }
+
+    #[test]
+    fn grid_disk_area_sums_the_disk_cells() {
+        let origin = a5::lonlat_to_cell(a5::LonLat::new(30.0, 10.0), 8).unwrap();
+        let (mut area, mut count) = (0.0, 0);
+        assert_eq!(a5_grid_disk_area(origin, 0, &mut area, &mut count), A5ErrorCode::None);
+        assert_eq!((area, count), (a5_cell_exact_area(origin).value, 1));
+
+        assert_eq!(a5_grid_disk_area(origin, 3, &mut area, &mut count), A5ErrorCode::None);
+        let disk = a5::uncompact(&cell_array_to_vec(a5_grid_disk(origin, 3)), 8).unwrap();
+        assert_eq!(count, disk.len() as u64);
+        let summed: f64 = disk.iter().map(|&cell| a5_cell_exact_area(cell).value).sum();
+        assert!((area - summed).abs() < 1e-6 * summed);
+        assert!((area / (count as f64 * a5::cell_area(8)) - 1.0).abs() < 0.05);
+
+        assert_eq!(a5_grid_disk_area(origin, -1, &mut area, &mut count), A5ErrorCode::OutOfBounds);
+        assert_eq!(a5_grid_disk_area(origin, A5_GRID_DISTANCE_LIMIT as i32 + 1, &mut area, &mut count), A5ErrorCode::OutOfBounds);
+        assert_eq!(a5_grid_disk_area(origin, i32::MAX, &mut area, &mut count), A5ErrorCode::OutOfBounds);
+        assert_eq!(a5_grid_disk_area(0, 1, &mut area, &mut count), A5ErrorCode::InvalidCell);
+        assert_eq!(a5_grid_disk_area(origin, 1, std::ptr::null_mut(), &mut count), A5ErrorCode::NullPointer);
+    }
+
+    #[test]
+    fn hex_validation_names_the_failure() {
+        let check = |s: &str| a5_validate_hex_string(CString::new(s).unwrap().as_ptr());
+        let cell = a5::lonlat_to_cell(a5::LonLat::new(5.0, 5.0), 9).unwrap();
+        let hex = format!("{:016x}", cell);
+        assert_eq!(check(&hex), A5ErrorCode::None);
+        assert_eq!(check(&format!("0x{}", hex.to_uppercase())), A5ErrorCode::None);
+        for not_hex in ["63c228000000000g", "63c2 28000000000", "0x-1", "0xx63c2280000000000"] {
+            assert_eq!(check(not_hex), A5ErrorCode::NotHex, "{not_hex}");
+        }
+        for wrong_length in ["", "0x", "63c228", "63c22800000000000", &hex[1..]] {
+            assert_eq!(check(wrong_length), A5ErrorCode::WrongLength, "{wrong_length}");
+        }
+        assert_eq!(check("0000000000000000"), A5ErrorCode::InvalidCell);
+        assert_eq!(check(&format!("{:016x}", (12u64 << 58) | (1 << 57))), A5ErrorCode::InvalidCell);
+        assert_eq!(a5_validate_hex_string(std::ptr::null()), A5ErrorCode::NullPointer);
+    }
+
+    #[test]
+    fn cell_fields_reconstruct_the_id() {
+        let rebuild = |cell: u64| {
+            let resolution = a5_cell_get_resolution_bits(cell) as i32;
+            let base = a5_cell_get_base(cell) as u64;
+            if resolution == 0 {
+                return (base << 58) | (1 << 57);
+            }
+            let top = 5 * base + a5_cell_get_digit(cell, 1) as u64;
+            let digits = (2..=resolution).fold(0u64, |acc, level| (acc << 2) | a5_cell_get_digit(cell, level) as u64);
+            if resolution == 30 {
+                return match top {
+                    0..=31 => (top << 59) | (digits << 1) | 1,
+                    32..=39 => ((top - 32) << 61) | (digits << 3) | 0b100,
+                    _ => ((top - 40) << 63) | (digits << 5) | 0b10000,
+                };
+            }
+            let marker = if resolution == 1 { 56 } else { 59 - 2 * resolution as u32 };
+            (top << 58) | (digits << (58 - 2 * (resolution as u32 - 1))) | (1 << marker)
+        };
+        for (lon, lat) in [(-73.98, 40.75), (151.2, -33.9), (0.0, 0.0), (-179.9, 89.0)] {
+            for resolution in 0..=30 {
+                let cell = a5::lonlat_to_cell(a5::LonLat::new(lon, lat), resolution).unwrap();
+                if a5::get_resolution(cell) != resolution {
+                    continue;
+                }
+                assert_eq!(rebuild(cell), cell, "{cell:016x}");
+                assert!(a5_cell_get_base(cell) < 12);
+                assert_eq!(a5_cell_get_base(cell), a5_cell_get_base(a5::cell_to_parent(cell, Some(0)).unwrap()));
+                if resolution > 0 {
+                    assert!(a5_cell_get_digit(cell, 1) < 5);
+                }
+                assert_eq!(a5_cell_get_digit(cell, resolution + 1), -1);
+                assert_eq!(a5_cell_get_digit(cell, 0), -1);
+            }
+        }
+        // The narrower resolution 30 top fields, for quintants 33 and 41
+        let digits = 0b10_01u64 << 54;
+        for (cell, base, quintant) in [((1u64 << 61) | (digits << 3) | 0b100, 6, 3), ((1 << 63) | (digits << 5) | 0b10000, 8, 1)] {
+            assert!(a5_is_valid_cell(cell), "{cell:016x}");
+            assert_eq!(a5_cell_get_resolution_bits(cell), 30);
+            assert_eq!(a5_cell_get_base(cell), base);
+            assert_eq!((a5_cell_get_digit(cell, 1), a5_cell_get_digit(cell, 2), a5_cell_get_digit(cell, 3)), (quintant, 2, 1));
+        }
+        assert_eq!(a5_cell_get_resolution_bits(0), u32::MAX);
+        assert_eq!(a5_cell_get_base(0), u32::MAX);
+        assert_eq!(a5_cell_get_digit(0, 1), -1);
+    }
+
+    #[test]
+    fn debug_strings_show_the_cell_structure() {
+        let render = |cell: u64| {
+            let ptr = a5_cell_to_debug_string(cell);
+            let text = unsafe { CStr::from_ptr(ptr) }.to_str().unwrap().to_string();
+            a5_free_cstring(ptr);
+            text
+        };
+        let face = (3u64 << 58) | (1 << 57);
+        assert_eq!(render(face), "A5[res=0, face=3, path=]");
+        let cell = (3u64 * 5 + 4) << 58 | 0b10_01 << 54 | 1 << 53;
+        assert_eq!(render(cell), "A5[res=3, face=3, path=4/2/1]");
+        assert_eq!(render(0), "A5[invalid: 0x0000000000000000]");
+        assert_eq!(render(0xfe00000000000000), "A5[invalid: 0xfe00000000000000]");
+    }
+
+    #[test]
+    fn compact_cells_to_stops_at_the_floor() {
+        let ring = square(0.0, 40.0, 10.0, 50.0);
+        let original = cell_array_to_vec(a5_polygon_to_cells(ring.as_ptr(), ring.len(), 6, FillMode::Center));
+        let full = cell_array_to_vec(a5_compact_cells(original.as_ptr(), original.len()));
+        assert!(full.iter().any(|&cell| a5::get_resolution(cell) < 5));
+        assert_eq!(cell_array_to_vec(a5_compact_cells_to(original.as_ptr(), original.len(), 0)), full);
+
+        let floored = cell_array_to_vec(a5_compact_cells_to(original.as_ptr(), original.len(), 5));
+        assert!(floored.iter().all(|&cell| (5..=6).contains(&a5::get_resolution(cell))));
+        assert!(floored.len() > full.len() && floored.len() < original.len());
+        assert!(floored.windows(2).all(|w| w[0] < w[1]));
+        assert_eq!(a5::uncompact(&floored, 6).unwrap(), original);
+
+        // Coarse input is expanded to the floor
+        let parent = a5::cell_to_parent(original[0], Some(3)).unwrap();
+        let expanded = cell_array_to_vec(a5_compact_cells_to(&parent, 1, 4));
+        let mut children = a5::cell_to_children(parent, Some(4)).unwrap();
+        children.sort_unstable();
+        assert_eq!(expanded, children);
+
+        let result = a5_compact_cells_to(original.as_ptr(), original.len(), 31);
+        assert_eq!(result.code, A5ErrorCode::InvalidResolution);
+        a5_free_cell_array(result);
+    }
+
+    #[test]
+    fn msgpack_round_trips_cell_sets() {
+        let encode = |cells: &[u64]| {
+            let mut len = usize::MAX;
+            let ptr = a5_cells_to_msgpack(cells.as_ptr(), cells.len(), &mut len);
+            let bytes = unsafe { std::slice::from_raw_parts(ptr, len) }.to_vec();
+            a5_free_bytes(ptr, len);
+            bytes
+        };
+        let decode = |bytes: &[u8]| cell_array_to_vec(a5_msgpack_to_cells(bytes.as_ptr(), bytes.len()));
+
+        let ring = square(0.0, 40.0, 10.0, 50.0);
+        let cells = cell_array_to_vec(a5_polygon_to_cells(ring.as_ptr(), ring.len(), 9, FillMode::Center));
+        let mut shuffled = cells.clone();
+        shuffled.reverse();
+        shuffled.push(cells[3]);
+        let blob = encode(&shuffled);
+        let mut expected = shuffled.clone();
+        expected.sort_unstable();
+        assert_eq!(decode(&blob), expected);
+        // Clustered cells need well under 8 bytes each
+        assert!(blob.len() < 2 * cells.len());
+
+        let res0 = a5::get_res0_cells().unwrap();
+        let mut spread = res0.clone();
+        spread.push(a5::lonlat_to_cell(a5::LonLat::new(179.9, -89.0), 30).unwrap());
+        spread.sort_unstable();
+        assert_eq!(decode(&encode(&spread)), spread);
+        assert_eq!(encode(&[]), [0x93, 0x00, 0x00, 0xc4, 0x00]);
+        assert!(decode(&encode(&[])).is_empty());
+    }
+
+    #[test]
+    fn msgpack_rejects_malformed_blobs() {
+        let cell = a5::lonlat_to_cell(a5::LonLat::new(5.0, 5.0), 9).unwrap();
+        let mut len = 0;
+        let ptr = a5_cells_to_msgpack(&cell, 1, &mut len);
+        let blob = unsafe { std::slice::from_raw_parts(ptr, len) }.to_vec();
+        a5_free_bytes(ptr, len);
+
+        let mut wrong_count = blob.clone();
+        wrong_count[1] = 2;
+        let mut trailing = blob.clone();
+        trailing.push(0);
+        let not_a_cell = [0x93, 0x01, 0x00, 0xc4, 0x01, 0x00];
+        let overflow = [0x93, 0x01, 0x3f, 0xc4, 0x01, 0x02];
+        for bytes in [&blob[..blob.len() - 1], &wrong_count, &trailing, &not_a_cell, &overflow, &[0x90][..], &[][..]] {
+            let result = a5_msgpack_to_cells(bytes.as_ptr(), bytes.len());
+            assert_eq!(result.code, A5ErrorCode::InvalidCell, "{bytes:?}");
+            a5_free_cell_array(result);
+        }
+
+        let ptr = a5_cells_to_msgpack([cell, 0].as_ptr(), 2, &mut len);
+        assert!(ptr.is_null());
+        assert_eq!(len, 0);
+    }
+
+    #[test]
+    fn compressed_cells_round_trip_at_a_fraction_of_the_size() {
+        let compress = |cells: &[u64]| {
+            let mut len = usize::MAX;
+            let ptr = a5_compress_cells(cells.as_ptr(), cells.len(), &mut len);
+            let bytes = if ptr.is_null() { Vec::new() } else { unsafe { std::slice::from_raw_parts(ptr, len) }.to_vec() };
+            a5_free_bytes(ptr, len);
+            (bytes, len)
+        };
+        let ring = square(-74.3, 40.5, -73.7, 40.9);
+        let cells = cell_array_to_vec(a5_polygon_to_cells(ring.as_ptr(), ring.len(), 14, FillMode::Intersects));
+        let mut input = cells.clone();
+        input.reverse();
+        input.extend_from_slice(&cells[..10]);
+        let (bytes, len) = compress(&input);
+        assert_eq!(len, bytes.len());
+        assert_eq!(cell_array_to_vec(a5_decompress_cells(bytes.as_ptr(), bytes.len())), cells);
+
+        // The ratio on a real polyfill; benches/compress.rs reports it in full
+        let ratio = (cells.len() * 8) as f64 / bytes.len() as f64;
+        assert!(ratio > 7.5, "compression ratio {ratio:.2}");
+
+        let mixed = [a5::get_res0_cells().unwrap()[4], cells[0], a5::lonlat_to_cell(a5::LonLat::new(-73.9, 40.7), 30).unwrap()];
+        let (bytes, _) = compress(&mixed);
+        let mut sorted = mixed.to_vec();
+        sorted.sort_unstable();
+        assert_eq!(cell_array_to_vec(a5_decompress_cells(bytes.as_ptr(), bytes.len())), sorted);
+
+        assert_eq!(compress(&[]), (Vec::new(), 0));
+        assert!(cell_array_to_vec(a5_decompress_cells(std::ptr::null(), 0)).is_empty());
+        for bad in [&[64u8, 1][..], &[0, 0x80][..], &[0, 0][..]] {
+            let result = a5_decompress_cells(bad.as_ptr(), bad.len());
+            assert_eq!(result.code, A5ErrorCode::InvalidCell, "{bad:?}");
+            a5_free_cell_array(result);
+        }
+        assert_eq!(compress(&[cells[0], 0]), (Vec::new(), 0));
+    }
+
+    #[test]
+    fn local_coordinates_round_trip_within_the_cell() {
+        let mut rng = SplitMix64(79);
+        for resolution in [0, 1, 5, 12, 20] {
+            for _ in 0..200 {
+                let (lon, lat) = (rng.next_f64() * 360.0 - 180.0, (rng.next_f64() * 2.0 - 1.0).asin().to_degrees());
+                let (mut cell, mut u, mut v) = (0, f64::NAN, f64::NAN);
+                assert_eq!(a5_lon_lat_to_cell_local(lon, lat, resolution, &mut cell, &mut u, &mut v), A5ErrorCode::None);
+                assert_eq!(cell, a5::lonlat_to_cell(a5::LonLat::new(lon, lat), resolution).unwrap());
+                // The lookup now and then places a point lying just past a cell's edge in it
+                assert!((-0.05..=1.05).contains(&u) && (-0.05..=1.05).contains(&v), "({u}, {v}) in {cell:016x}");
+
+                let (mut back_lon, mut back_lat) = (f64::NAN, f64::NAN);
+                assert_eq!(a5_cell_local_to_lon_lat(cell, u, v, &mut back_lon, &mut back_lat), A5ErrorCode::None);
+                assert!(geometry::haversine_distance(lon, lat, back_lon, back_lat) < 1e-6, "({lon}, {lat}) came back as ({back_lon}, {back_lat})");
+            }
+        }
+
+        // The frame's box touches the boundary on every side, west and south at 0
+        let cell = a5::lonlat_to_cell(a5::LonLat::new(-73.98, 40.75), 10).unwrap();
+        let frame = CellFrame::new(cell).unwrap();
+        let local: Vec<geometry::Point> = CellFrame::traced_boundary(cell).unwrap().iter().map(|&(lon, lat)| frame.to_local(lon, lat).unwrap()).collect();
+        let extreme = |f: fn(&geometry::Point) -> f64| local.iter().map(f).fold((f64::INFINITY, f64::NEG_INFINITY), |(lo, hi), x| (lo.min(x), hi.max(x)));
+        for (lo, hi) in [extreme(|p| p.0), extreme(|p| p.1)] {
+            assert!(lo.abs() < 1e-12 && (hi - 1.0).abs() < 1e-12);
+        }
+        // East is +u and north is +v
+        let (mut east, mut north) = ((0.0, 0.0), (0.0, 0.0));
+        a5_cell_local_to_lon_lat(cell, 0.6, 0.5, &mut east.0, &mut east.1);
+        a5_cell_local_to_lon_lat(cell, 0.5, 0.6, &mut north.0, &mut north.1);
+        let (mut mid_lon, mut mid_lat) = (0.0, 0.0);
+        a5_cell_local_to_lon_lat(cell, 0.5, 0.5, &mut mid_lon, &mut mid_lat);
+        assert!(east.0 > mid_lon && (east.1 - mid_lat).abs() < 1e-3);
+        assert!(north.1 > mid_lat && (north.0 - mid_lon).abs() < 1e-3);
+
+        let (mut out, mut u, mut v) = (0, 0.0, 0.0);
+        assert_eq!(a5_lon_lat_to_cell_local(0.0, 95.0, 5, &mut out, &mut u, &mut v), A5ErrorCode::InvalidCoordinate);
+        assert_eq!(a5_lon_lat_to_cell_local(0.0, 0.0, 31, &mut out, &mut u, &mut v), A5ErrorCode::InvalidResolution);
+        assert_eq!(a5_lon_lat_to_cell_local(0.0, 0.0, 5, std::ptr::null_mut(), &mut u, &mut v), A5ErrorCode::NullPointer);
+        assert_eq!(a5_cell_local_to_lon_lat(0, 0.5, 0.5, &mut u, &mut v), A5ErrorCode::InvalidCell);
+        assert_eq!(a5_cell_local_to_lon_lat(cell, f64::NAN, 0.5, &mut u, &mut v), A5ErrorCode::InvalidCoordinate);
+    }
+
+    #[test]
+    fn resolution_for_area_picks_the_nearest_ratio() {
+        for resolution in 0..=30 {
+            let area = a5::cell_area(resolution);
+            assert_eq!(a5_resolution_for_area(area), resolution);
+            assert_eq!(a5_resolution_for_area(area * 1.9), resolution);
+            assert_eq!(a5_resolution_for_edge_length(a5_cell_edge_length(resolution)), resolution);
+        }
+        // Resolution 12 cells average 2.03 km² and 13 cells 0.51 km², so 1 km² is just nearer 13
+        assert_eq!(a5_resolution_for_area(1e6), 13);
+        assert_eq!(a5_resolution_for_area(1.0), 22);
+        assert_eq!(a5_resolution_for_area(10_000.0), 16);
+        assert_eq!(a5_resolution_for_edge_length(1_000.0), 12);
+        assert_eq!(a5_resolution_for_edge_length(1.0), 22);
+
+        assert_eq!(a5_resolution_for_area(a5::cell_area(0) * 3.0), 0);
+        assert_eq!(a5_resolution_for_area(a5::cell_area(0) * 4.0), -1);
+        assert_eq!(a5_resolution_for_area(a5::cell_area(30) / 1.9), 30);
+        assert_eq!(a5_resolution_for_area(a5::cell_area(30) / 2.1), -1);
+        for bad in [0.0, -1.0, f64::NAN, f64::INFINITY] {
+            assert_eq!(a5_resolution_for_area(bad), -1);
+            assert_eq!(a5_resolution_for_edge_length(bad), -1);
+        }
+    }
+
+    #[test]
+    fn area_table_matches_cell_area() {
+        let mut table = [f64::NAN; 40];
+        assert_eq!(a5_cell_area_table(table.as_mut_ptr(), table.len()), 31);
+        for resolution in [0, 1, 9, 30] {
+            assert_eq!(table[resolution], a5_cell_area(resolution as i32));
+        }
+        assert!(table[31..].iter().all(|v| v.is_nan()));
+        assert!(table[..31].windows(2).all(|w| w[1] < w[0]));
+
+        let mut short = [f64::NAN; 4];
+        assert_eq!(a5_cell_area_table(short.as_mut_ptr(), 3), 3);
+        assert_eq!(short[..3], table[..3]);
+        assert!(short[3].is_nan());
+        assert_eq!(a5_cell_area_table(std::ptr::null_mut(), 10), 0);
+
+        // The typical edge is that of a regular pentagon with the average area
+        let edge = a5_cell_edge_length(12);
+        assert!((edge * edge * PENTAGON_AREA_PER_SQUARE_EDGE - a5_cell_area(12)).abs() < 1e-6);
+        assert!((a5_cell_edge_length(11) / a5_cell_edge_length(12) - 2.0).abs() < 1e-12);
+        assert!(a5_cell_edge_length(31).is_nan() && a5_cell_edge_length(-1).is_nan());
+    }
+
+    #[test]
+    fn cells_at_one_resolution_have_equal_areas() {
+        let mut rng = SplitMix64(81);
+        for resolution in [2, 6, 10, 14] {
+            let average = a5_cell_area(resolution);
+            for _ in 0..50 {
+                let cell = a5_random_cell(resolution, rng.next_u64());
+                let area = a5_cell_exact_area(cell).value;
+                // Exact areas use great-circle edges between the vertices, which
+                // shifts them by up to about 1% from the true equal areas
+                assert!((area / average - 1.0).abs() < 0.015, "{:016x}: {} vs average {}", cell, area, average);
+            }
+        }
+    }
+
+    #[test]
+    fn cell_child_indexes_cell_to_children() {
+        let mut cells = a5::get_res0_cells().unwrap();
+        cells.push(a5::lonlat_to_cell(a5::LonLat::new(-73.98, 40.75), 1).unwrap());
+        cells.push(a5::lonlat_to_cell(a5::LonLat::new(-73.98, 40.75), 7).unwrap());
+        cells.push(a5::lonlat_to_cell(a5::LonLat::new(151.2, -33.9), 29).unwrap());
+        for cell in cells {
+            let children = cell_array_to_vec(a5_cell_to_children(cell, A5_DEFAULT_RESOLUTION));
+            let picked: Vec<u64> = (0..children.len() as u32).map(|i| a5_cell_child(cell, i)).map(|r| {
+                assert_eq!(r.code, A5ErrorCode::None);
+                r.value
+            }).collect();
+            assert_eq!(picked, children);
+            assert_eq!(children.len(), if a5::get_resolution(cell) == 0 { 5 } else { 4 });
+
+            let past = a5_cell_child(cell, children.len() as u32);
+            assert_eq!(past.code, A5ErrorCode::OutOfBounds);
+            a5_free_cstring(past.error);
+        }
+        let finest = a5_cell_child(a5::lonlat_to_cell(a5::LonLat::new(5.0, 5.0), 30).unwrap(), 0);
+        assert_eq!(finest.code, A5ErrorCode::InvalidResolution);
+        a5_free_cstring(finest.error);
+        let invalid = a5_cell_child(0, 0);
+        assert_eq!(invalid.code, A5ErrorCode::InvalidCell);
+        a5_free_cstring(invalid.error);
+    }
+
+    #[test]
+    fn conversions_agree_across_concurrent_threads() {
+        // Run under ThreadSanitizer with
+        // RUSTFLAGS=-Zsanitizer=thread cargo +nightly test -Zbuild-std --target <host>
+        let run = |seed: u64| {
+            let mut rng = SplitMix64(seed);
+            let mut results = Vec::new();
+            for i in 0..150 {
+                let (lon, lat) = (rng.next_f64() * 360.0 - 180.0, rng.next_f64() * 180.0 - 90.0);
+                let resolution = (i % 31) as i32;
+                let cell = a5_lon_lat_to_cell(lon, lat, resolution);
+                let center = a5_cell_to_lon_lat(cell.value);
+                let ring: Vec<(u64, u64)> = boundary_points(cell.value).unwrap().iter().map(|p| (p.0.to_bits(), p.1.to_bits())).collect();
+                let parent = a5_cell_to_parent(cell.value, resolution / 2);
+                let res0 = a5_get_res0_cell_at(i % 12);
+                let mut out = 0;
+                let code = a5_cell_to_parent_simple(cell.value, 31, &mut out);
+                let message = unsafe { CStr::from_ptr(a5_last_error_message()) }.to_owned();
+                results.push((cell.value, center.longitude.to_bits(), center.latitude.to_bits(), ring, parent.value, res0.value, code, message));
+            }
+            results
+        };
+        let expected: Vec<_> = (0..8).map(run).collect();
+        let threads: Vec<_> = (0..32).map(|t| std::thread::spawn(move || (t % 8, run(t % 8)))).collect();
+        for thread in threads {
+            let (seed, results) = thread.join().unwrap();
+            assert!(results == expected[seed as usize], "thread with seed {seed} disagreed");
+        }
+    }
+
+    #[test]
+    fn pointer_frees_make_a_second_free_a_no_op() {
+        let cell = a5::lonlat_to_cell(a5::LonLat::new(-73.98, 40.75), 9).unwrap();
+        let mut children = a5_cell_to_children(cell, A5_DEFAULT_RESOLUTION);
+        assert_eq!(children.len, 4);
+        a5_free_cell_array_ptr(&mut children);
+        assert!(children.data.is_null() && children.error.is_null() && children.len == 0);
+        a5_free_cell_array_ptr(&mut children);
+
+        let mut failed = a5_compact_cells([cell, 0].as_ptr(), 2);
+        assert!(!failed.error.is_null());
+        a5_free_cell_array_ptr(&mut failed);
+        a5_free_cell_array_ptr(&mut failed);
+        assert_eq!(failed.code, A5ErrorCode::InvalidCell);
+
+        let mut boundary = a5_cell_to_boundary(cell, CellBoundaryOptions::new(false, None));
+        assert!(boundary.len > 0);
+        a5_free_lonlatdegrees_array_ptr(&mut boundary);
+        assert!(boundary.data.is_null() && boundary.error.is_null() && boundary.len == 0);
+        a5_free_lonlatdegrees_array_ptr(&mut boundary);
+
+        a5_free_cell_array_ptr(std::ptr::null_mut());
+        a5_free_lonlatdegrees_array_ptr(std::ptr::null_mut());
+    }
+
+    #[test]
+    fn adaptive_fill_is_coarse_inside_and_fine_at_the_edge() {
+        let ring = [(0.0, 40.0), (12.0, 41.0), (9.0, 50.0), (3.0, 47.0)].map(|(lon, lat)| LonLatDegrees { lon, lat });
+        let covering = cell_array_to_vec(a5_polygon_to_cells_adaptive(ring.as_ptr(), ring.len(), 3, 9));
+        let fine = cell_array_to_vec(a5_polygon_to_cells(ring.as_ptr(), ring.len(), 9, FillMode::Intersects));
+        assert!(covering.len() * 5 < fine.len());
+        assert!(covering.windows(2).all(|w| w[0] < w[1]));
+        let mut expanded = a5::uncompact(&covering, 9).unwrap();
+        expanded.sort_unstable();
+        assert_eq!(expanded, fine);
+        assert_eq!(covering, cell_array_to_vec(a5_compact_cells_to(fine.as_ptr(), fine.len(), 3)));
+
+        // Every point inside lies in some cell of the covering
+        let outline: Vec<geometry::Point> = ring.iter().map(|p| (p.lon, p.lat)).collect();
+        let set: HashSet<u64> = covering.iter().copied().collect();
+        let mut rng = SplitMix64(86);
+        let mut inside = 0;
+        while inside < 500 {
+            let (lon, lat) = (rng.next_f64() * 12.0, 40.0 + rng.next_f64() * 10.0);
+            if !geometry::ring_contains(&outline, lon, lat) {
+                continue;
+            }
+            inside += 1;
+            let cell = a5::lonlat_to_cell(a5::LonLat::new(lon, lat), 9).unwrap();
+            assert!((3..=9).any(|r| set.contains(&a5::cell_to_parent(cell, Some(r)).unwrap())), "({lon}, {lat})");
+        }
+
+        // Cells crossing the boundary are finer than those inside it
+        let on_boundary = |cell: u64| {
+            let cell_ring = cell_ring(cell).unwrap();
+            geometry::rings_intersect(&cell_ring, &outline) && !geometry::ring_encloses(&outline, &cell_ring)
+        };
+        let (edge, interior): (Vec<u64>, Vec<u64>) = covering.iter().partition(|&&cell| on_boundary(cell));
+        // Compaction can merge siblings that all touch the polygon into a parent
+        // crossing its edge, so the split shows in the mix rather than per cell
+        let mean = |cells: &[u64]| cells.iter().map(|&cell| a5::get_resolution(cell) as f64).sum::<f64>() / cells.len() as f64;
+        assert!(mean(&edge) > mean(&interior) + 0.5, "{} vs {}", mean(&edge), mean(&interior));
+        assert!(interior.iter().all(|&cell| a5::get_resolution(cell) >= 3));
+        assert!(covering.iter().filter(|&&cell| a5::get_resolution(cell) <= 5).all(|cell| interior.contains(cell)));
+        assert!(covering.iter().any(|&cell| a5::get_resolution(cell) <= 5));
+
+        let result = a5_polygon_to_cells_adaptive(ring.as_ptr(), ring.len(), 9, 3);
+        assert_eq!(result.code, A5ErrorCode::InvalidResolution);
+        a5_free_cell_array(result);
+    }
+
+    #[test]
+    fn cell_polygon_overlap_measures_covered_share() {
+        let cell = a5_lon_lat_to_cell(10.0, 45.0, 8).value;
+        let center = a5::cell_to_lonlat(cell).unwrap();
+        let overlap = |ring: &[LonLatDegrees]| {
+            let mut fraction = f64::NAN;
+            assert_eq!(a5_cell_polygon_overlap(cell, ring.as_ptr(), ring.len(), &mut fraction), A5ErrorCode::None);
+            fraction
+        };
+
+        assert!((overlap(&square(9.0, 44.0, 11.0, 46.0)) - 1.0).abs() < 1e-9);
+        assert_eq!(overlap(&square(20.0, 44.0, 21.0, 46.0)), 0.0);
+
+        // Split at the center's longitude, each side holds about half. Cut
+        // points are interpolated in the plane, off the cell's great-circle
+        // edges, so the halves add up to the whole only closely
+        let west = overlap(&square(9.0, 44.0, center.longitude(), 46.0));
+        let east = overlap(&square(center.longitude(), 44.0, 11.0, 46.0));
+        assert!((west - 0.5).abs() < 0.1 && (east - 0.5).abs() < 0.1, "{} {}", west, east);
+        assert!((west + east - 1.0).abs() < 1e-3, "{} + {}", west, east);
+
+        // A hole takes its share back out
+        let (outer, hole) = (square(9.0, 44.0, 11.0, 46.0), square(9.5, 44.0, center.longitude(), 46.0));
+        let donut = polyfill::Polygon::with_holes(&outer, &[&hole]).unwrap();
+        assert!((polyfill::cell_overlap_fraction(&donut, cell).unwrap() - (1.0 - west)).abs() < 1e-9);
+
+        // A ring crossing the antimeridian covers a cell on the far side
+        let far = a5_lon_lat_to_cell(-179.95, 0.0, 8).value;
+        let ring = square(179.0, -1.0, 181.0, 1.0);
+        let mut fraction = f64::NAN;
+        assert_eq!(a5_cell_polygon_overlap(far, ring.as_ptr(), ring.len(), &mut fraction), A5ErrorCode::None);
+        assert!((fraction - 1.0).abs() < 1e-9, "{}", fraction);
+
+        assert_eq!(a5_cell_polygon_overlap(cell, ring.as_ptr(), ring.len(), std::ptr::null_mut()), A5ErrorCode::NullPointer);
+        assert_eq!(a5_cell_polygon_overlap(cell, std::ptr::null(), 0, &mut fraction), A5ErrorCode::InvalidCoordinate);
+        assert_eq!(a5_cell_polygon_overlap(cell, std::ptr::null(), 4, &mut fraction), A5ErrorCode::NullPointer);
+        assert_eq!(a5_cell_polygon_overlap(0xfe00000000000000, ring.as_ptr(), ring.len(), &mut fraction), A5ErrorCode::InvalidCell);
+    }
+
+    #[test]
+    fn sort_and_dedup_cells_canonicalize_in_place() {
+        let res0 = a5::get_res0_cells().unwrap();
+        let children = cell_array_to_vec(a5_cell_to_children(res0[1], A5_DEFAULT_RESOLUTION));
+        assert!(!children.windows(2).all(|w| w[0] < w[1]), "expected quintant order to differ from id order");
+        let grandchildren = cell_array_to_vec(a5_cell_to_children(children[0], A5_DEFAULT_RESOLUTION));
+        assert!(grandchildren.windows(2).all(|w| w[0] < w[1]));
+
+        let mut sorted = children.clone();
+        assert_eq!(a5_sort_cells(sorted.as_mut_ptr(), sorted.len()), A5ErrorCode::None);
+        let mut expected = children.clone();
+        expected.sort_unstable();
+        assert_eq!(sorted, expected);
+
+        let mut cells: Vec<u64> = children.iter().chain(&children).chain(&children[..2]).copied().rev().collect();
+        let len = a5_dedup_cells(cells.as_mut_ptr(), cells.len());
+        assert_eq!(&cells[..len], &expected[..]);
+
+        assert_eq!(a5_dedup_cells(std::ptr::null_mut(), 4), usize::MAX);
+        assert_eq!(a5_sort_cells(std::ptr::null_mut(), 4), A5ErrorCode::NullPointer);
+        assert_eq!(a5_dedup_cells(std::ptr::null_mut(), 0), 0);
+        assert_eq!(a5_sort_cells(std::ptr::null_mut(), 0), A5ErrorCode::None);
+    }
+
+    #[test]
+    fn covering_tiles_and_tile_cells_agree() {
+        let tiles_of = |cell: u64, zoom: u32| {
+            let mut arr = a5_cell_to_covering_tiles(cell, zoom);
+            assert_eq!(arr.code, A5ErrorCode::None);
+            let tiles = if arr.data.is_null() { Vec::new() } else { unsafe { std::slice::from_raw_parts(arr.data, arr.len) }.to_vec() };
+            a5_free_tile_array_ptr(&mut arr);
+            a5_free_tile_array_ptr(&mut arr);
+            tiles
+        };
+
+        // Zurich at zoom 10 is in tile 536/358
+        let zurich = a5_lon_lat_to_cell(8.54, 47.37, 12).value;
+        assert!(tiles_of(zurich, 10).contains(&Tile { z: 10, x: 536, y: 358 }));
+
+        let across = a5_lon_lat_to_cell(180.0, 10.0, 6).value;
+        let polar = a5_lon_lat_to_cell(0.0, 89.5, 5).value;
+        for (cell, zoom) in [(zurich, 10), (across, 6), (polar, 4)] {
+            let tiles = tiles_of(cell, zoom);
+            assert!(!tiles.is_empty() && tiles.windows(2).all(|w| w[0] < w[1]));
+            for tile in &tiles {
+                let cells = cell_array_to_vec(a5_tile_to_cells(tile.z, tile.x, tile.y, a5::get_resolution(cell)));
+                assert!(cells.contains(&cell), "{:?} misses {:x}", tile, cell);
+            }
+        }
+        let across_tiles = tiles_of(across, 6);
+        assert!(across_tiles.iter().any(|t| t.x == 0) && across_tiles.iter().any(|t| t.x == 63));
+        assert!(tiles_of(polar, 4).iter().all(|t| t.y == 0));
+
+        // Every cell of a tile lists the tile back
+        let tile = Tile { z: 8, x: 133, y: 90 };
+        let cells = cell_array_to_vec(a5_tile_to_cells(tile.z, tile.x, tile.y, 9));
+        assert!(!cells.is_empty());
+        for &cell in &cells {
+            assert!(tiles_of(cell, 8).contains(&tile), "{:x}", cell);
+        }
+
+        assert_eq!(a5_cell_to_covering_tiles(zurich, A5_MAX_TILE_ZOOM + 1).code, A5ErrorCode::OutOfBounds);
+        assert_eq!(a5_cell_to_covering_tiles(a5::get_res0_cells().unwrap()[0], 20).code, A5ErrorCode::OutOfBounds);
+        assert_eq!(a5_cell_to_covering_tiles(0xfe00000000000000, 4).code, A5ErrorCode::InvalidCell);
+        assert_eq!(a5_tile_to_cells(2, 4, 0, 5).code, A5ErrorCode::OutOfBounds);
+        assert_eq!(a5_tile_to_cells(2, 0, 0, 31).code, A5ErrorCode::InvalidResolution);
+    }
+
+    #[test]
+    fn cell_to_lon_lat_all_matches_separate_calls() {
+        let cell = a5_lon_lat_to_cell(-73.98, 40.75, 11).value;
+        let (mut lon, mut lat) = (f64::NAN, f64::NAN);
+        let mut vertices = LonLatDegreesArray::empty();
+        assert_eq!(a5_cell_to_lon_lat_all(cell, &mut lon, &mut lat, &mut vertices), A5ErrorCode::None);
+        let center = a5_cell_to_lon_lat(cell);
+        assert_eq!((lon, lat), (center.longitude, center.latitude));
+        let mut boundary = a5_cell_to_boundary(cell, CellBoundaryOptions::new(false, None));
+        let as_pairs = |arr: &LonLatDegreesArray| unsafe { std::slice::from_raw_parts(arr.data, arr.len) }.iter().map(|p| (p.lon, p.lat)).collect::<Vec<_>>();
+        assert_eq!(as_pairs(&vertices), as_pairs(&boundary));
+        a5_free_lonlatdegrees_array_ptr(&mut vertices);
+        a5_free_lonlatdegrees_array_ptr(&mut boundary);
+
+        assert_eq!(a5_cell_to_lon_lat_all(0xfe00000000000000, &mut lon, &mut lat, &mut vertices), A5ErrorCode::InvalidCell);
+        assert!(vertices.data.is_null() && !vertices.error.is_null());
+        a5_free_lonlatdegrees_array_ptr(&mut vertices);
+        assert_eq!(a5_cell_to_lon_lat_all(cell, &mut lon, std::ptr::null_mut(), &mut vertices), A5ErrorCode::NullPointer);
+    }
+
+    #[test]
+    fn grid_disk_distances_pairs_cells_with_rings() {
+        let origin = a5_lon_lat_to_cell(2.35, 48.85, 9).value;
+        let (mut cells, mut distances, mut len) = (vec![0u64; 200], vec![-1i32; 200], 0usize);
+        assert_eq!(a5_grid_disk_distances(origin, 3, cells.as_mut_ptr(), distances.as_mut_ptr(), cells.len(), &mut len), A5ErrorCode::None);
+        let (cells, distances) = (&cells[..len], &distances[..len]);
+        assert_eq!((cells[0], distances[0]), (origin, 0));
+        assert!(distances.windows(2).all(|w| w[0] <= w[1]));
+        for k in 0..=3 {
+            let ring: Vec<u64> = cells.iter().zip(distances).filter(|&(_, &d)| d == k).map(|(&c, _)| c).collect();
+            assert_eq!(ring, cell_array_to_vec(a5_grid_ring(origin, k)));
+        }
+        for (&cell, &d) in cells.iter().zip(distances).step_by(7) {
+            assert_eq!(a5_grid_distance(origin, cell).value, d as u64);
+        }
+
+        // An undersized buffer reports the count without writing
+        let (mut small, mut small_distances, mut needed) = (vec![0u64; 4], vec![-1i32; 4], 0usize);
+        assert_eq!(a5_grid_disk_distances(origin, 3, small.as_mut_ptr(), small_distances.as_mut_ptr(), small.len(), &mut needed), A5ErrorCode::BufferTooSmall);
+        assert_eq!((needed, small[0], small_distances[0]), (len, 0, -1));
+        assert_eq!(a5_grid_disk_distances(origin, 1, small.as_mut_ptr(), std::ptr::null_mut(), 200, &mut needed), A5ErrorCode::BufferTooSmall);
+        assert_eq!(a5_grid_disk_distances(origin, -1, small.as_mut_ptr(), small_distances.as_mut_ptr(), 4, &mut needed), A5ErrorCode::OutOfBounds);
+    }
+
+    #[test]
+    fn null_array_pointers_are_empty_only_at_length_zero() {
+        let (null_cells, null_bytes) = (std::ptr::null::<u64>(), std::ptr::null::<u8>());
+        let null_coords = std::ptr::null::<LonLatDegrees>();
+        let code = |mut arr: CellArray| {
+            let code = arr.code;
+            assert!(arr.data.is_null());
+            a5_free_cell_array_ptr(&mut arr);
+            code
+        };
+
+        assert_eq!(code(a5_lon_lat_to_cell_batch(null_coords, 0, 5, std::ptr::null(), std::ptr::null_mut())), A5ErrorCode::None);
+        assert_eq!(code(a5_lon_lat_to_cell_batch(null_coords, 3, 5, std::ptr::null(), std::ptr::null_mut())), A5ErrorCode::NullPointer);
+        assert_eq!(code(a5_cell_to_parent_batch(null_cells, std::ptr::null(), 3, std::ptr::null(), std::ptr::null_mut())), A5ErrorCode::NullPointer);
+        for f in [a5_compact, a5_compact_cells] {
+            assert_eq!(code(f(null_cells, 0)), A5ErrorCode::None);
+            assert_eq!(code(f(null_cells, 3)), A5ErrorCode::NullPointer);
+        }
+        for f in [a5_compact_cells_to, a5_uncompact_cells, a5_uncompact] {
+            assert_eq!(code(f(null_cells, 3, 5)), A5ErrorCode::NullPointer);
+        }
+        assert_eq!(code(a5_msgpack_to_cells(null_bytes, 3)), A5ErrorCode::NullPointer);
+        assert_eq!(code(a5_decompress_cells(null_bytes, 3)), A5ErrorCode::NullPointer);
+        assert_eq!(code(a5_decompress_cells(null_bytes, 0)), A5ErrorCode::None);
+
+        let mut centers = a5_cell_to_lon_lat_batch(null_cells, 3, std::ptr::null(), std::ptr::null_mut());
+        assert_eq!(centers.code, A5ErrorCode::NullPointer);
+        a5_free_lonlatdegrees_array_ptr(&mut centers);
+        let (mut array, mut schema) = (std::mem::MaybeUninit::<ArrowArray>::uninit(), std::mem::MaybeUninit::<ArrowSchema>::uninit());
+        assert_eq!(a5_cell_to_lon_lat_arrow(null_cells, 3, std::ptr::null(), array.as_mut_ptr(), schema.as_mut_ptr()), A5ErrorCode::NullPointer);
+        let cells = [a5_lon_lat_to_cell(0.0, 0.0, 5).value];
+        assert_eq!(a5_cell_to_lon_lat_arrow(cells.as_ptr(), 1, std::ptr::null(), std::ptr::null_mut(), schema.as_mut_ptr()), A5ErrorCode::NullPointer);
+        assert_eq!(a5_cell_to_lon_lat_arrow(cells.as_ptr(), 1, std::ptr::null(), array.as_mut_ptr(), std::ptr::null_mut()), A5ErrorCode::NullPointer);
+        assert_eq!(a5_cells_to_boundaries_arrow(cells.as_ptr(), 1, std::ptr::null_mut(), schema.as_mut_ptr()), A5ErrorCode::NullPointer);
+        assert_eq!(a5_cells_to_boundaries_arrow(cells.as_ptr(), 1, array.as_mut_ptr(), std::ptr::null_mut()), A5ErrorCode::NullPointer);
+        let (mut lon, mut lat) = ([0.0; 3], [0.0; 3]);
+        assert_eq!(a5_cell_to_lon_lat_batch_soa(null_cells, 3, std::ptr::null(), lon.as_mut_ptr(), lat.as_mut_ptr(), std::ptr::null_mut()), 0);
+
+        assert!(a5_cells_to_geojson(null_cells, 3, true).is_null());
+        assert!(a5_cells_to_csv(null_cells, 3, true, true).is_null());
+        assert!(a5_cells_to_multipolygon(null_cells, 3).is_null());
+        assert_eq!(take_string(a5_cells_to_csv(null_cells, 0, false, false)), "a5\n");
+        let mut len = 7;
+        assert!(a5_cells_to_msgpack(null_cells, 3, &mut len).is_null() && len == 0);
+        len = 7;
+        assert!(a5_compress_cells(null_cells, 3, &mut len).is_null() && len == 0);
+        len = 7;
+        assert!(a5_simplify_ring(null_coords, 3, 1.0, &mut len).is_null() && len == 0);
+
+        assert_eq!(code(a5_polygon_to_cells(null_coords, 4, 5, FillMode::Center)), A5ErrorCode::NullPointer);
+        assert_eq!(code(a5_polygon_to_cells(null_coords, 0, 5, FillMode::Center)), A5ErrorCode::InvalidCoordinate);
+        assert_eq!(code(a5_polygon_to_cells_adaptive(null_coords, 4, 2, 5)), A5ErrorCode::NullPointer);
+        let arena = a5_arena_new();
+        assert_eq!(a5_polygon_to_cells_arena(arena, null_coords, 4, 5, FillMode::Center).code, A5ErrorCode::NullPointer);
+        a5_arena_free(arena);
+        extern "C" fn keep_going(_: *mut std::os::raw::c_void, _: u64) -> bool {
+            true
+        }
+        assert_eq!(a5_polygon_to_cells_foreach(null_coords, 4, 5, FillMode::Center, std::ptr::null_mut(), keep_going), A5ErrorCode::NullPointer);
+        let mut fraction = 0.0;
+        assert_eq!(a5_cell_polygon_overlap(a5_lon_lat_to_cell(0.0, 0.0, 5).value, null_coords, 4, &mut fraction), A5ErrorCode::NullPointer);
+
+        let outer = square(0.0, 0.0, 2.0, 2.0);
+        let lens = [4usize];
+        assert_eq!(code(a5_polygon_with_holes_to_cells(null_coords, 4, std::ptr::null(), std::ptr::null(), 0, 5)), A5ErrorCode::NullPointer);
+        assert_eq!(code(a5_polygon_with_holes_to_cells(outer.as_ptr(), outer.len(), null_coords, lens.as_ptr(), 1, 5)), A5ErrorCode::NullPointer);
+        assert_eq!(code(a5_polygon_with_holes_to_cells(outer.as_ptr(), outer.len(), outer.as_ptr(), std::ptr::null(), 1, 5)), A5ErrorCode::NullPointer);
+        assert_eq!(a5_validate_hex_string(std::ptr::null()), A5ErrorCode::NullPointer);
+    }
+
+    #[test]
+    fn arrow_export_of_boundaries() {
+        let cell = a5::lonlat_to_cell(a5::LonLat::new(-122.4, 37.8), 9).unwrap();
+        let across = a5_lon_lat_to_cell(180.0, 0.0, 6).value;
+        let cells = [cell, (12 << 58) | (1 << 57), across];
+        let mut array = std::mem::MaybeUninit::<ArrowArray>::uninit();
+        let mut schema = std::mem::MaybeUninit::<ArrowSchema>::uninit();
+        assert_eq!(a5_cells_to_boundaries_arrow(cells.as_ptr(), cells.len(), array.as_mut_ptr(), schema.as_mut_ptr()), A5ErrorCode::None);
+        let (mut array, mut schema) = unsafe { (array.assume_init(), schema.assume_init()) };
+
+        let format = |s: &ArrowSchema| unsafe { CStr::from_ptr(s.format) }.to_str().unwrap().to_string();
+        let item = unsafe { &**schema.children };
+        assert_eq!((format(&schema), schema.n_children, format(item), item.n_children), ("+l".to_string(), 1, "+s".to_string(), 2));
+
+        assert_eq!((array.length, array.null_count, array.n_buffers, array.n_children), (3, 1, 2, 1));
+        assert_eq!(unsafe { *(*array.buffers as *const u8) } & 0b111, 0b101);
+        let offsets = unsafe { std::slice::from_raw_parts(*array.buffers.add(1) as *const i32, 4) };
+        let vertices = unsafe { &**array.children };
+        let column = |i: usize| unsafe { std::slice::from_raw_parts(*(**vertices.children.add(i)).buffers.add(1) as *const f64, vertices.length as usize) };
+        assert_eq!(vertices.length, offsets[3] as i64);
+        assert_eq!(offsets[1], offsets[2]);
+        for (row, c) in [(0, cell), (2, across)] {
+            let mut expected = a5_cell_to_boundary_oriented(c, true);
+            let expected_points: Vec<(f64, f64)> = unsafe { std::slice::from_raw_parts(expected.data, expected.len) }.iter().map(|p| (p.lon, p.lat)).collect();
+            let range = offsets[row] as usize..offsets[row + 1] as usize;
+            let got: Vec<(f64, f64)> = column(0)[range.clone()].iter().copied().zip(column(1)[range].iter().copied()).collect();
+            assert_eq!(got, expected_points);
+            a5_free_lonlatdegrees_array_ptr(&mut expected);
+        }
+
+        unsafe {
+            (array.release.unwrap())(&mut array);
+            (schema.release.unwrap())(&mut schema);
+        }
+        assert!(array.release.is_none() && schema.release.is_none());
+    }
+
+    #[test]
+    fn cell_to_parent_at_coarser_equal_and_finer_resolutions() {
+        let cell = a5_lon_lat_to_cell(139.69, 35.69, 12).value;
+        let coarser = a5_cell_to_parent(cell, 8);
+        assert_eq!(coarser.code, A5ErrorCode::None);
+        assert_eq!((coarser.value, a5::get_resolution(coarser.value)), (a5::cell_to_parent(cell, Some(8)).unwrap(), 8));
+
+        let same = a5_cell_to_parent(cell, 12);
+        assert_eq!((same.code, same.value), (A5ErrorCode::None, cell));
+        let finer = a5_cell_to_parent(cell, 13);
+        assert_eq!(finer.code, A5ErrorCode::InvalidResolution);
+        a5_free_cstring(finer.error);
+
+        let mut out = 0;
+        assert_eq!(a5_cell_to_parent_simple(cell, 12, &mut out), A5ErrorCode::None);
+        assert_eq!(out, cell);
+        assert_eq!(a5_cell_to_parent_simple(cell, 13, &mut out), A5ErrorCode::InvalidResolution);
+
+        // No resolution -1 world cell, and no parents of invalid cells
+        for (cell, resolution, code) in [(cell, -1, A5ErrorCode::InvalidResolution), (0, 5, A5ErrorCode::InvalidCell)] {
+            let result = a5_cell_to_parent(cell, resolution);
+            assert_eq!((result.code, result.value), (code, 0));
+            a5_free_cstring(result.error);
+            out = cell;
+            assert_eq!(a5_cell_to_parent_simple(cell, resolution, &mut out), code);
+            assert_eq!(out, cell);
+        }
+
+        let (cells, resolutions) = ([cell, cell, cell], [8, 12, 13]);
+        let parents = cell_array_to_vec(a5_cell_to_parent_batch(cells.as_ptr(), resolutions.as_ptr(), cells.len(), std::ptr::null(), std::ptr::null_mut()));
+        assert_eq!(parents, [coarser.value, cell, A5_INVALID_CELL]);
+    }
+
+    #[test]
+    fn polygon_fill_matches_brute_force_over_all_cells() {
+        let all = a5::uncompact(a5::get_res0_cells().unwrap().as_slice(), 6).unwrap();
+        let check = |outer: &[LonLatDegrees], holes: &[Vec<LonLatDegrees>]| {
+            let polygon = polyfill::Polygon::with_holes(outer, &holes.iter().map(Vec::as_slice).collect::<Vec<_>>()).unwrap();
+            let points = |ring: &[LonLatDegrees]| ring.iter().map(|p| (p.lon, p.lat)).collect::<Vec<geometry::Point>>();
+            let (outer_ring, hole_rings) = (points(outer), holes.iter().map(|hole| points(hole)).collect::<Vec<_>>());
+            // Rings may run past ±180, so cells are tried a turn to either side
+            let turns = [-360.0, 0.0, 360.0];
+            let mut center = Vec::new();
+            let mut intersects = Vec::new();
+            for &cell in &all {
+                let c = a5::cell_to_lonlat(cell).unwrap();
+                let inside = |lon: f64| geometry::ring_contains(&outer_ring, lon, c.latitude()) && !hole_rings.iter().any(|hole| geometry::ring_contains(hole, lon, c.latitude()));
+                if turns.iter().any(|t| inside(c.longitude() + t)) {
+                    center.push(cell);
+                }
+                let ring = cell_ring(cell).unwrap();
+                let overlaps = |t: f64| {
+                    let shifted: Vec<geometry::Point> = ring.iter().map(|&(lon, lat)| (lon + t, lat)).collect();
+                    geometry::rings_intersect(&outer_ring, &shifted) && !hole_rings.iter().any(|hole| geometry::ring_encloses(hole, &shifted))
+                };
+                if turns.iter().any(|&t| overlaps(t)) {
+                    intersects.push(cell);
+                }
+            }
+            center.sort_unstable();
+            intersects.sort_unstable();
+            assert!(center.len() > 100);
+            assert_eq!(polyfill::polygon_to_cells(&polygon, 6, FillMode::Center).unwrap(), center);
+            assert_eq!(polyfill::polygon_to_cells(&polygon, 6, FillMode::Intersects).unwrap(), intersects);
+        };
+
+        // A concave outline about 40 degrees across, with a hole
+        let outer = [(-20.0, 30.0), (20.0, 30.0), (20.0, 40.0), (-5.0, 42.0), (20.0, 55.0), (20.0, 65.0), (-20.0, 65.0)].map(|(lon, lat)| LonLatDegrees { lon, lat });
+        check(&outer, &[square(-15.0, 50.0, -5.0, 58.0)]);
+        // Across the antimeridian, with a hole on each side of it
+        check(&square(165.0, -25.0, 195.0, 5.0), &[square(170.0, -15.0, 176.0, -5.0), square(182.0, -10.0, 188.0, 0.0)]);
+        // A plain box shaped only by its holes: one sharing a corner with the
+        // outer ring, one well inside and one smaller than most cells
+        check(&square(60.0, -10.0, 90.0, 20.0), &[square(60.0, -10.0, 70.0, 0.0), square(75.0, 5.0, 85.0, 15.0), square(86.0, -8.0, 88.0, -6.0)]);
+    }
+
+    #[test]
+    fn ordered_neighbors_run_clockwise_edge_by_edge() {
+        let bearing = |from: (f64, f64), to: (f64, f64)| geometry::initial_bearing(from.0, from.1, to.0, to.1);
+        let near = |a: &(f64, f64), b: &(f64, f64)| ((a.0 - b.0 + 180.0).rem_euclid(360.0) - 180.0).abs() < 1e-9 && (a.1 - b.1).abs() < 1e-9;
+        // Resolutions where boundaries are plain vertices, so shared edges match
+        let origins = [(-3.7, 40.4, 10), (179.9, -12.0, 8), (20.0, 88.0, 7), (-120.0, -75.0, 6)];
+        for cell in origins.iter().flat_map(|&(lon, lat, resolution)| cell_array_to_vec(a5_grid_disk(a5_lon_lat_to_cell(lon, lat, resolution).value, 2))) {
+            let ordered = cell_array_to_vec(a5_cell_to_neighbors_ordered(cell));
+            assert_eq!(ordered, cell_array_to_vec(a5_cell_to_neighbors_ordered(cell)));
+            let mut sorted = ordered.clone();
+            sorted.sort_unstable();
+            assert_eq!(sorted, cell_array_to_vec(a5_cell_to_neighbors(cell)));
+            assert_eq!(ordered.len(), 5);
+            for &neighbor in &ordered {
+                assert!(cell_array_to_vec(a5_cell_to_neighbors_ordered(neighbor)).contains(&cell));
+            }
+
+            // The edge shared with each neighbor comes in the same clockwise order
+            let c = a5::cell_to_lonlat(cell).unwrap();
+            let center = (c.longitude(), c.latitude());
+            let ring = boundary_points(cell).unwrap();
+            let edge_bearings: Vec<f64> = ordered
+                .iter()
+                .map(|&neighbor| {
+                    let other = boundary_points(neighbor).unwrap();
+                    let shared: Vec<&(f64, f64)> = ring.iter().filter(|p| other.iter().any(|q| near(p, q))).collect();
+                    assert!(shared.len() >= 2, "{:x} and {:x} share no edge", cell, neighbor);
+                    let unwrap = |lon: f64| center.0 + (lon - center.0 + 180.0).rem_euclid(360.0) - 180.0;
+                    let n = shared.len() as f64;
+                    bearing(center, (shared.iter().map(|p| unwrap(p.0)).sum::<f64>() / n, shared.iter().map(|p| p.1).sum::<f64>() / n))
+                })
+                .collect();
+            let start = (0..5).min_by(|&a, &b| edge_bearings[a].total_cmp(&edge_bearings[b])).unwrap();
+            let rotated: Vec<f64> = (0..5).map(|i| edge_bearings[(start + i) % 5]).collect();
+            assert!(rotated.windows(2).all(|w| w[0] < w[1]), "{:x}: {:?}", cell, edge_bearings);
+        }
+
+        let invalid = a5_cell_to_neighbors_ordered(0xfe00000000000000);
+        assert_eq!(invalid.code, A5ErrorCode::InvalidCell);
+        a5_free_cell_array(invalid);
+    }
+
+    #[test]
+    fn ancestors_match_parent_chains() {
+        let points = [(-3.7, 40.4), (179.9, -12.0), (20.0, 88.0), (-120.0, -75.0), (100.0, 5.0), (-60.0, -30.0), (150.0, 60.0)];
+        for &(lon, lat) in &points {
+            for resolution in [1, 2, 9, 29, 30] {
+                let descendant = a5_lon_lat_to_cell(lon, lat, resolution).value;
+                // Resolution 30 only encodes some quintants, see `a5_cell_get_digit`
+                let descendant_resolution = a5::get_resolution(descendant);
+                for resolution in 0..=descendant_resolution {
+                    let ancestor = a5::cell_to_parent(descendant, Some(resolution)).unwrap();
+                    assert_eq!(a5_is_ancestor_of(ancestor, descendant), resolution < descendant_resolution);
+                    assert!(!a5_is_ancestor_of(descendant, ancestor));
+                }
+            }
+        }
+
+        // Siblings and cells of other faces are outside the subtree
+        let parent = a5_lon_lat_to_cell(-3.7, 40.4, 8).value;
+        let children = cell_array_to_vec(a5_cell_to_children(parent, 9));
+        let deep = a5_lon_lat_to_cell(-3.7, 40.4, 30).value;
+        let child = a5::cell_to_parent(deep, Some(9)).unwrap();
+        for &sibling in children.iter().filter(|&&c| c != child) {
+            assert!(!a5_is_ancestor_of(sibling, deep));
+        }
+        let other_face = a5_lon_lat_to_cell(100.0, 5.0, 0).value;
+        assert_ne!(a5_cell_get_base(other_face), a5_cell_get_base(deep));
+        assert!(!a5_is_ancestor_of(other_face, deep));
+        assert!(a5_is_ancestor_of(a5::cell_to_parent(deep, Some(0)).unwrap(), deep));
+        assert!(!a5_is_ancestor_of(0xfe00000000000000, deep));
+        assert!(!a5_is_ancestor_of(parent, 0xfe00000000000000));
+    }
+
+    #[test]
+    fn arena_arrays_outlive_frees_until_the_arena_goes() {
+        let arena = a5_arena_new();
+        let parent = a5_lon_lat_to_cell(-3.7, 40.4, 8).value;
+        let mut children = a5_cell_to_children_arena(arena, parent, 10);
+        let mut boundary = a5_cell_to_boundary_arena(arena, parent, CellBoundaryOptions::new(false, None));
+        let mut invalid = a5_grid_disk_arena(arena, 0xfe00000000000000, 1);
+        assert!(children.arena && boundary.arena && invalid.arena);
+        assert_eq!(invalid.code, A5ErrorCode::InvalidCell);
+        assert!(!invalid.error.is_null());
+
+        // Regular frees leave arena arrays as they are
+        let (data, len) = (children.data, children.len);
+        a5_free_cell_array_ptr(&mut children);
+        a5_free_cell_array_ptr(&mut invalid);
+        a5_free_lonlatdegrees_array_ptr(&mut boundary);
+        assert_eq!((children.data, children.len), (data, len));
+        assert!(!invalid.error.is_null());
+        let mut expected = cell_array_to_vec(a5_cell_to_children(parent, 10));
+        expected.sort_unstable();
+        let mut held = unsafe { std::slice::from_raw_parts(children.data, children.len) }.to_vec();
+        held.sort_unstable();
+        assert_eq!(held, expected);
+        assert_eq!(boundary.len, boundary_points(parent).unwrap().len());
+
+        let cells = [parent];
+        let compacted = a5_compact_arena(arena, cells.as_ptr(), 1);
+        assert_eq!(unsafe { std::slice::from_raw_parts(compacted.data, compacted.len) }, &cells);
+        assert_eq!(a5_uncompact_arena(arena, cells.as_ptr(), 1, 9).len, 4);
+        assert_eq!(a5_cell_to_neighbors_arena(arena, parent).len, 5);
+        a5_arena_free(arena);
+        a5_arena_free(std::ptr::null_mut());
+
+        let orphan = a5_cell_to_children_arena(std::ptr::null_mut(), parent, 10);
+        assert_eq!(orphan.code, A5ErrorCode::NullPointer);
+        assert!(!orphan.arena && orphan.data.is_null());
+        a5_free_cell_array(orphan);
+    }
+
+    #[test]
+    fn boundary_cache_copies_the_uncached_rings() {
+        let _cache = BOUNDARY_CACHE.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        let cells: Vec<u64> = [(-3.7, 40.4, 1), (179.9, -12.0, 3), (20.0, 88.0, 9), (-120.0, -75.0, 15)].iter().map(|&(lon, lat, resolution)| a5_lon_lat_to_cell(lon, lat, resolution).value).collect();
+        let ring = |cell, closed, segments| {
+            let mut arr = a5_cell_to_boundary(cell, CellBoundaryOptions::new(closed, segments));
+            assert!(arr.error.is_null());
+            let points: Vec<(f64, f64)> = unsafe { std::slice::from_raw_parts(arr.data, arr.len) }.iter().map(|p| (p.lon, p.lat)).collect();
+            a5_free_lonlatdegrees_array_ptr(&mut arr);
+            points
+        };
+        let uncached: Vec<_> = cells.iter().map(|&cell| (ring(cell, false, None), ring(cell, true, None), ring(cell, true, Some(2)))).collect();
+
+        #[cfg(feature = "boundary-cache")]
+        {
+            assert!(a5_set_boundary_cache_size(4));
+            for pass in 0..2 {
+                let hits = cache::boundaries().hits();
+                for (&cell, expected) in cells.iter().zip(&uncached) {
+                    assert_eq!(&(ring(cell, false, None), ring(cell, true, None), ring(cell, true, Some(2))), expected);
+                }
+                // Every default ring after the first pass is served without geometry
+                let after = cache::boundaries().hits();
+                if pass == 1 {
+                    assert!(after >= hits + 2 * cells.len() as u64, "{} hits after {}", after, hits);
+                }
+            }
+            assert!(a5_set_boundary_cache_size(3));
+            assert!(cache::boundaries().len() <= 3);
+            a5_clear_boundary_cache();
+            let mut invalid = a5_cell_to_boundary(0xfe00000000000000, CellBoundaryOptions::new(false, None));
+            assert_eq!(invalid.code, A5ErrorCode::InvalidCell);
+            a5_free_lonlatdegrees_array_ptr(&mut invalid);
+            assert!(a5_set_boundary_cache_size(0));
+            assert_eq!(cache::boundaries().len(), 0);
+
+            // The least recently used entry goes first
+            let mut lru = cache::Lru::new(2);
+            lru.insert(1, 'a');
+            lru.insert(2, 'b');
+            assert_eq!(lru.get(1), Some('a'));
+            lru.insert(3, 'c');
+            assert_eq!((lru.get(1), lru.get(2), lru.get(3)), (Some('a'), None, Some('c')));
+            lru.resize(1);
+            assert_eq!((lru.len(), lru.get(3)), (1, Some('c')));
+        }
+        #[cfg(not(feature = "boundary-cache"))]
+        {
+            assert!(!a5_set_boundary_cache_size(3));
+            a5_clear_boundary_cache();
+        }
+        assert_eq!(ring(cells[0], false, None), uncached[0].0);
+    }
+
+    #[test]
+    fn indexer_matches_stateless_conversion() {
+        let coords: Vec<LonLatDegrees> = (0..500).map(|i| LonLatDegrees { lon: (i as f64 * 37.1) % 360.0 - 180.0, lat: (i as f64 * 13.7) % 180.0 - 90.0 }).chain([LonLatDegrees { lon: 0.0, lat: f64::NAN }]).collect();
+        for resolution in [0, 1, 5, 12, 30] {
+            let indexer = a5_indexer_new(resolution);
+            assert!(!indexer.is_null());
+            let index = |result: ResultU64| {
+                let error = (!result.error.is_null()).then(|| unsafe { CStr::from_ptr(result.error) }.to_string_lossy().into_owned());
+                a5_free_string(result.error);
+                (result.value, result.code, error)
+            };
+            let expected: Vec<_> = coords.iter().map(|c| index(a5_lon_lat_to_cell(c.lon, c.lat, resolution))).collect();
+            assert_eq!(expected.last().map(|e| e.1), Some(A5ErrorCode::InvalidCoordinate));
+            assert_eq!(coords.iter().map(|c| index(a5_indexer_index(indexer, c.lon, c.lat))).collect::<Vec<_>>(), expected);
+            let cells: Vec<u64> = expected.iter().map(|e| if e.2.is_some() { A5_INVALID_CELL } else { e.0 }).collect();
+            assert_eq!(cell_array_to_vec(a5_indexer_index_batch(indexer, coords.as_ptr(), coords.len())), cells);
+            a5_indexer_free(indexer);
+        }
+
+        assert!(a5_indexer_new(31).is_null());
+        let orphan = a5_indexer_index(std::ptr::null(), 0.0, 0.0);
+        assert_eq!(orphan.code, A5ErrorCode::NullPointer);
+        a5_free_string(orphan.error);
+        let mut orphan = a5_indexer_index_batch(std::ptr::null(), coords.as_ptr(), coords.len());
+        assert_eq!(orphan.code, A5ErrorCode::NullPointer);
+        a5_free_cell_array_ptr(&mut orphan);
+        a5_indexer_free(std::ptr::null_mut());
+    }
+
+    #[test]
+    fn batches_match_single_calls_bit_for_bit() {
+        // Enough rows for the `parallel` feature to split them across threads
+        let mut state = 42u64;
+        let mut next = || {
+            state = state.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+            (state >> 11) as f64 / (1u64 << 53) as f64
+        };
+        let coords: Vec<LonLatDegrees> = (0..20 * 1024).map(|_| LonLatDegrees { lon: next() * 360.0 - 180.0, lat: next() * 180.0 - 90.0 }).collect();
+        let cells = cell_array_to_vec(a5_lon_lat_to_cell_batch(coords.as_ptr(), coords.len(), 11, std::ptr::null(), std::ptr::null_mut()));
+        assert_eq!(cells, coords.iter().map(|c| a5_lon_lat_to_cell(c.lon, c.lat, 11).value).collect::<Vec<_>>());
+
+        let mut centers = a5_cell_to_lon_lat_batch(cells.as_ptr(), cells.len(), std::ptr::null(), std::ptr::null_mut());
+        let batched: Vec<(u64, u64)> = unsafe { std::slice::from_raw_parts(centers.data, centers.len) }.iter().map(|p| (p.lon.to_bits(), p.lat.to_bits())).collect();
+        a5_free_lonlatdegrees_array_ptr(&mut centers);
+        assert_eq!(batched, cells.iter().map(|&cell| a5_cell_to_lon_lat(cell)).map(|c| (c.longitude.to_bits(), c.latitude.to_bits())).collect::<Vec<_>>());
+    }
+}
@@ -0,0 +1,296 @@
+//! Polygon filling: finds the cells at a resolution that cover a polygon.
+//!
+//! Rings are interpreted in the plane of longitude/latitude degrees, so ring
+//! edges are straight lines in that plane rather than great-circle arcs. Rings
+//! may be open or closed and wound either way. Longitudes are unwrapped along
+//! the ring, so a ring crossing the antimeridian is handled as long as no edge
+//! spans more than 180 degrees; rings encircling a pole are not supported.
+
+use crate::geometry::{clip_to_convex, counter_clockwise, ring_contains, ring_encloses, rings_intersect, spherical_polygon_area, unwrap_ring, Point};
+use crate::{cell_bbox, cell_ring, exact_area, for_each_child, res0_cells, A5ErrorCode, BBox, Error, LonLatDegrees};
+
+/// Which cells `a5_polygon_to_cells` returns.
+#[repr(C)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum FillMode {
+    /// Cells whose center lies inside the polygon.
+    Center = 0,
+    /// Cells whose boundary overlaps the polygon at all.
+    Intersects = 1,
+}
+
+/// A ring with its longitudes unwrapped into one contiguous range.
+struct Ring {
+    points: Vec<Point>,
+    min_lon: f64,
+    max_lon: f64,
+    min_lat: f64,
+    max_lat: f64,
+}
+
+impl Ring {
+    fn new(vertices: &[LonLatDegrees]) -> Result<Self, Error> {
+        if let Some(p) = vertices.iter().find(|p| !p.lon.is_finite() || !p.lat.is_finite() || p.lat.abs() > 90.0) {
+            return Err(Error::new(A5ErrorCode::InvalidCoordinate, format!("invalid coordinate ({}, {}) in polygon ring", p.lon, p.lat)));
+        }
+        let points = unwrap_ring(vertices.iter().map(|p| (p.lon, p.lat)));
+        if points.len() < 3 {
+            return Err(Error::new(A5ErrorCode::InvalidCoordinate, format!("polygon ring needs at least 3 distinct vertices, got {}", points.len())));
+        }
+        // After unwrapping, a ring around a pole does not return to its start
+        let (first, last) = (points[0].0, points[points.len() - 1].0);
+        if (first - last).abs() > 180.0 {
+            return Err(Error::new(A5ErrorCode::InvalidCoordinate, "polygon ring encircles a pole, which is not supported"));
+        }
+
+        let mut ring = Self { points, min_lon: f64::INFINITY, max_lon: f64::NEG_INFINITY, min_lat: f64::INFINITY, max_lat: f64::NEG_INFINITY };
+        for &(lon, lat) in &ring.points {
+            ring.min_lon = ring.min_lon.min(lon);
+            ring.max_lon = ring.max_lon.max(lon);
+            ring.min_lat = ring.min_lat.min(lat);
+            ring.max_lat = ring.max_lat.max(lat);
+        }
+        Ok(ring)
+    }
+
+    /// Multiples of 360 degrees to add to the longitude range `[min, max]` so
+    /// that it lines up with the ring's unwrapped longitudes.
+    fn offsets(&self, min: f64, max: f64) -> impl Iterator<Item = f64> + '_ {
+        [-360.0, 0.0, 360.0].into_iter().filter(move |o| min + o <= self.max_lon && max + o >= self.min_lon)
+    }
+
+    fn contains(&self, lon: f64, lat: f64) -> bool {
+        self.offsets(lon, lon).any(|o| ring_contains(&self.points, lon + o, lat))
+    }
+
+    /// Area in square meters of the part of this ring inside the convex,
+    /// counter-clockwise `ring`, clipped at each offset where they line up
+    /// and measured with great-circle edges about `apex`.
+    fn area_within(&self, ring: &[Point], apex: Point) -> f64 {
+        let (min, max) = ring.iter().fold((f64::INFINITY, f64::NEG_INFINITY), |(lo, hi), p| (lo.min(p.0), hi.max(p.0)));
+        self.offsets(min, max)
+            .map(|o| {
+                let shifted: Vec<Point> = ring.iter().map(|&(lon, lat)| (lon + o, lat)).collect();
+                let clipped = clip_to_convex(&self.points, &shifted);
+                if clipped.len() < 3 { 0.0 } else { spherical_polygon_area((apex.0 + o, apex.1), &clipped) }
+            })
+            .sum()
+    }
+
+    /// Tests `other` against this ring with `test`, at each offset where their
+    /// longitude ranges line up.
+    fn any_aligned(&self, other: &[Point], test: impl Fn(&[Point], &[Point]) -> bool) -> bool {
+        let (min, max) = other.iter().fold((f64::INFINITY, f64::NEG_INFINITY), |(lo, hi), p| (lo.min(p.0), hi.max(p.0)));
+        self.offsets(min, max).any(|o| {
+            let shifted: Vec<Point> = other.iter().map(|&(lon, lat)| (lon + o, lat)).collect();
+            test(&self.points, &shifted)
+        })
+    }
+}
+
+/// An outer ring with optional holes.
+pub struct Polygon {
+    outer: Ring,
+    holes: Vec<Ring>,
+}
+
+impl Polygon {
+    pub fn new(outer: &[LonLatDegrees]) -> Result<Self, Error> {
+        Self::with_holes(outer, &[])
+    }
+
+    pub fn with_holes(outer: &[LonLatDegrees], holes: &[&[LonLatDegrees]]) -> Result<Self, Error> {
+        Ok(Self { outer: Ring::new(outer)?, holes: holes.iter().map(|hole| Ring::new(hole)).collect::<Result<_, _>>()? })
+    }
+
+    fn contains(&self, lon: f64, lat: f64) -> bool {
+        self.outer.contains(lon, lat) && !self.holes.iter().any(|hole| hole.contains(lon, lat))
+    }
+
+    /// Whether `ring` overlaps the outer ring without lying wholly inside a hole.
+    fn intersects_ring(&self, ring: &[Point]) -> bool {
+        self.outer.any_aligned(ring, rings_intersect) && !self.holes.iter().any(|hole| hole.any_aligned(ring, ring_encloses))
+    }
+
+    /// Whether any descendant of the cell with bounding box `bbox` may touch
+    /// the polygon: its box padded by `reach` overlaps the outer ring and does
+    /// not lie wholly inside a hole. The polygon's own bounding box is checked
+    /// first, since only boxes within it need the ring tested.
+    fn may_overlap(&self, bbox: &BBox) -> bool {
+        let (west, south, east, north) = reach(bbox);
+        let outer = &self.outer;
+        if south > outer.max_lat || north < outer.min_lat || outer.offsets(west, east).next().is_none() {
+            return false;
+        }
+        if east - west >= 180.0 {
+            return true;
+        }
+        let area = [(west, south), (east, south), (east, north), (west, north)];
+        outer.any_aligned(&area, rings_intersect) && !self.holes.iter().any(|hole| hole.any_aligned(&area, ring_encloses))
+    }
+
+    /// Whether every descendant of the cell with bounding box `bbox` lies
+    /// inside the polygon: its box padded by `reach` is within the outer ring
+    /// and clear of every hole.
+    fn surrounds(&self, bbox: &BBox) -> bool {
+        let (west, south, east, north) = reach(bbox);
+        if south < -90.0 || north > 90.0 || east - west >= 180.0 {
+            return false;
+        }
+        let area = [(west, south), (east, south), (east, north), (west, north)];
+        self.outer.any_aligned(&area, ring_encloses) && !self.holes.iter().any(|hole| hole.any_aligned(&area, rings_intersect))
+    }
+}
+
+/// Fraction of the area of `cell` inside the polygon, in [0, 1]. The polygon
+/// is clipped to the cell's planar ring, which A5 cells keep convex away from
+/// the poles, and the pieces are measured like `exact_area` measures the cell.
+pub fn cell_overlap_fraction(polygon: &Polygon, cell: u64) -> Result<f64, Error> {
+    let area = exact_area(cell)?;
+    if !polygon.may_overlap(&cell_bbox(cell)?) {
+        return Ok(0.0);
+    }
+    let ring = counter_clockwise(cell_ring(cell)?);
+    let center = a5::cell_to_lonlat(cell)?;
+    let apex = (center.longitude(), center.latitude());
+    let inside = polygon.outer.area_within(&ring, apex) - polygon.holes.iter().map(|hole| hole.area_within(&ring, apex)).sum::<f64>();
+    Ok((inside / area).clamp(0.0, 1.0))
+}
+
+/// The box that a cell's descendants at any resolution stay within, as
+/// `(west, south, east, north)` with `east` unwrapped past `west`. A5 cells do
+/// not nest exactly and descendants can reach most of a cell's size past its
+/// boundary, so the cell's box is padded by its larger side in every direction.
+fn reach(bbox: &BBox) -> (f64, f64, f64, f64) {
+    let max_lon = if bbox.wraps { bbox.max_lon + 360.0 } else { bbox.max_lon };
+    let pad = (max_lon - bbox.min_lon).max(bbox.max_lat - bbox.min_lat);
+    (bbox.min_lon - pad, bbox.min_lat - pad, max_lon + pad, bbox.max_lat + pad)
+}
+
+/// Calls `f` with each cell at `resolution` selected by `mode`, found by
+/// descending from the resolution 0 cells and skipping any cell whose bounding
+/// box misses the polygon. A coarser cell that the polygon `surrounds` has
+/// every descendant selected in either mode, so its descendants at
+/// `resolution` are listed without testing any of them; for a large polygon
+/// that is most of the output. Cells arrive in depth-first discovery order,
+/// each exactly once. Returns false if `f` stopped the walk early.
+pub fn for_each_cell(polygon: &Polygon, resolution: i32, mode: FillMode, f: &mut dyn FnMut(u64) -> bool) -> Result<bool, Error> {
+    let mut stack = res0_cells()?.to_vec();
+    while let Some(cell) = stack.pop() {
+        let bbox = cell_bbox(cell)?;
+        if !polygon.may_overlap(&bbox) {
+            continue;
+        }
+        if a5::get_resolution(cell) < resolution {
+            if polygon.surrounds(&bbox) {
+                let mut more = true;
+                for_each_child(cell, resolution, &mut |child| {
+                    more = f(child);
+                    more
+                })?;
+                if !more {
+                    return Ok(false);
+                }
+            } else {
+                stack.extend(a5::cell_to_children(cell, None)?);
+            }
+            continue;
+        }
+        let covered = match mode {
+            FillMode::Center => {
+                let center = a5::cell_to_lonlat(cell)?;
+                polygon.contains(center.longitude(), center.latitude())
+            }
+            FillMode::Intersects => polygon.intersects_ring(&cell_ring(cell)?),
+        };
+        if covered && !f(cell) {
+            return Ok(false);
+        }
+    }
+    Ok(true)
+}
+
+/// Returns the sorted cells at `resolution` selected by `mode`.
+pub fn polygon_to_cells(polygon: &Polygon, resolution: i32, mode: FillMode) -> Result<Vec<u64>, Error> {
+    let mut cells = Vec::new();
+    for_each_cell(polygon, resolution, mode, &mut |cell| {
+        cells.push(cell);
+        true
+    })?;
+    cells.sort_unstable();
+    Ok(cells)
+}
+
+/// Covers the polygon with cells between `min_resolution` and
+/// `max_resolution`: a cell at `min_resolution` or finer whose descendants all
+/// lie inside the polygon is kept whole, and the rest are refined down to
+/// `max_resolution`, where those intersecting the polygon are kept. Expanding
+/// the result to `max_resolution` gives exactly the `Intersects` fill there,
+/// though complete sibling groups near the boundary are left for the caller
+/// to compact. The interior is never expanded.
+pub fn adaptive_cells(polygon: &Polygon, min_resolution: i32, max_resolution: i32) -> Result<Vec<u64>, Error> {
+    let mut cells = Vec::new();
+    let mut stack = res0_cells()?.to_vec();
+    while let Some(cell) = stack.pop() {
+        let bbox = cell_bbox(cell)?;
+        if !polygon.may_overlap(&bbox) {
+            continue;
+        }
+        let resolution = a5::get_resolution(cell);
+        if resolution < max_resolution {
+            if resolution >= min_resolution && polygon.surrounds(&bbox) {
+                cells.push(cell);
+            } else {
+                stack.extend(a5::cell_to_children(cell, None)?);
+            }
+        } else if polygon.intersects_ring(&cell_ring(cell)?) {
+            cells.push(cell);
+        }
+    }
+    Ok(cells)
+}
+
+/// Splits a longitude/latitude box into polygons at most 90 degrees wide,
+/// since a ring edge must span less than 180. A box with `min_lon > max_lon`
+/// wraps across the antimeridian.
+fn bbox_slices(min_lon: f64, min_lat: f64, mut max_lon: f64, max_lat: f64) -> Result<Vec<Polygon>, Error> {
+    if ![min_lon, min_lat, max_lon, max_lat].iter().all(|v| v.is_finite()) || min_lat > max_lat || min_lat < -90.0 || max_lat > 90.0 {
+        return Err(Error::new(A5ErrorCode::InvalidCoordinate, format!("invalid coordinate in box ({}, {}, {}, {})", min_lon, min_lat, max_lon, max_lat)));
+    }
+    if min_lon > max_lon {
+        max_lon += 360.0;
+    }
+    let width = (max_lon - min_lon).min(360.0);
+    let slices = (width / 90.0).ceil().max(1.0) as usize;
+    (0..slices)
+        .map(|i| {
+            let west = min_lon + width * i as f64 / slices as f64;
+            let east = min_lon + width * (i + 1) as f64 / slices as f64;
+            Polygon::new(&[(west, min_lat), (east, min_lat), (east, max_lat), (west, max_lat)].map(|(lon, lat)| LonLatDegrees { lon, lat }))
+        })
+        .collect()
+}
+
+/// Returns the sorted cells at `resolution` intersecting the box, filled one
+/// slice from `bbox_slices` at a time.
+pub fn bbox_to_cells(min_lon: f64, min_lat: f64, max_lon: f64, max_lat: f64, resolution: i32) -> Result<Vec<u64>, Error> {
+    let mut cells = Vec::new();
+    for slice in bbox_slices(min_lon, min_lat, max_lon, max_lat)? {
+        cells.extend(polygon_to_cells(&slice, resolution, FillMode::Intersects)?);
+    }
+    cells.sort_unstable();
+    cells.dedup();
+    Ok(cells)
+}
+
+/// Whether the boundary of `cell` overlaps the box, by the same test as an
+/// `Intersects` fill of it.
+pub fn cell_intersects_bbox(cell: u64, min_lon: f64, min_lat: f64, max_lon: f64, max_lat: f64) -> Result<bool, Error> {
+    let slices = bbox_slices(min_lon, min_lat, max_lon, max_lat)?;
+    let bbox = cell_bbox(cell)?;
+    if bbox.max_lat < min_lat || bbox.min_lat > max_lat {
+        return Ok(false);
+    }
+    let ring = cell_ring(cell)?;
+    Ok(slices.iter().any(|slice| slice.intersects_ring(&ring)))
+}
@@ -0,0 +1,89 @@
+//! Native Python module through PyO3, enabled by the `python` feature.
+//!
+//! Cells are Python ints, coordinates floats, and failures raise `ValueError`
+//! with the message the C API returns. The batch functions take and return
+//! numpy arrays and, like their C counterparts, mark failed rows instead of
+//! raising: `A5_INVALID_CELL` or NaN. They release the GIL while converting,
+//! so other Python threads keep running. Build the module with maturin, or
+//! `cargo rustc --lib --crate-type cdylib --features python` and rename the
+//! library to `a5_rust` plus the platform's extension suffix.
+
+use crate::{batch_map, boundary_points, lonlat_to_cell, validate_cell, validate_resolution, A5_INVALID_CELL};
+use numpy::{IntoPyArray, PyArray1, PyReadonlyArray1};
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+
+/// A float64 numpy array handed back to Python.
+type Column<'py> = Bound<'py, PyArray1<f64>>;
+
+fn value_error(message: impl Into<String>) -> PyErr {
+    PyValueError::new_err(message.into())
+}
+
+pub(crate) fn cell_center(cell: u64) -> Result<(f64, f64), String> {
+    validate_cell(cell)?;
+    let center = a5::cell_to_lonlat(cell)?;
+    Ok((center.longitude(), center.latitude()))
+}
+
+/// Cells of the points at `resolution`, with `A5_INVALID_CELL` for rows that
+/// fail to convert.
+pub(crate) fn cells_of(lons: &[f64], lats: &[f64], resolution: i32) -> Result<Vec<u64>, String> {
+    validate_resolution(resolution)?;
+    if lons.len() != lats.len() {
+        return Err(format!("coordinate arrays differ in length: {} longitudes, {} latitudes", lons.len(), lats.len()));
+    }
+    let points: Vec<(f64, f64)> = lons.iter().copied().zip(lats.iter().copied()).collect();
+    Ok(batch_map(&points, |&(lon, lat)| lonlat_to_cell(lon, lat, resolution).unwrap_or(A5_INVALID_CELL)))
+}
+
+/// Centers of `cells` as separate longitude and latitude columns, NaN for
+/// invalid cells.
+pub(crate) fn centers_of(cells: &[u64]) -> (Vec<f64>, Vec<f64>) {
+    batch_map(cells, |&cell| cell_center(cell).unwrap_or((f64::NAN, f64::NAN))).into_iter().unzip()
+}
+
+/// Cell containing the point at `resolution`.
+#[pyfunction(name = "lonlat_to_cell")]
+fn lonlat_to_cell_py(lon: f64, lat: f64, resolution: i32) -> PyResult<u64> {
+    validate_resolution(resolution).and_then(|_| lonlat_to_cell(lon, lat, resolution)).map_err(value_error)
+}
+
+/// Center of the cell as `(lon, lat)`.
+#[pyfunction]
+fn cell_to_lonlat(cell: u64) -> PyResult<(f64, f64)> {
+    cell_center(cell).map_err(value_error)
+}
+
+/// Open boundary of the cell as a list of `(lon, lat)` tuples.
+#[pyfunction]
+fn cell_to_boundary(cell: u64) -> PyResult<Vec<(f64, f64)>> {
+    boundary_points(cell).map_err(value_error)
+}
+
+/// Cells of the points given by two float64 arrays, as a uint64 array.
+#[pyfunction]
+fn lonlat_to_cell_batch<'py>(py: Python<'py>, lons: PyReadonlyArray1<'py, f64>, lats: PyReadonlyArray1<'py, f64>, resolution: i32) -> PyResult<Bound<'py, PyArray1<u64>>> {
+    let (lons, lats) = (lons.as_slice()?, lats.as_slice()?);
+    let cells = py.allow_threads(|| cells_of(lons, lats, resolution)).map_err(value_error)?;
+    Ok(cells.into_pyarray(py))
+}
+
+/// Centers of a uint64 array of cells, as a pair of float64 arrays.
+#[pyfunction]
+fn cell_to_lonlat_batch<'py>(py: Python<'py>, cells: PyReadonlyArray1<'py, u64>) -> PyResult<(Column<'py>, Column<'py>)> {
+    let cells = cells.as_slice()?;
+    let (lons, lats) = py.allow_threads(|| centers_of(cells));
+    Ok((lons.into_pyarray(py), lats.into_pyarray(py)))
+}
+
+#[pymodule]
+fn a5_rust(module: &Bound<'_, PyModule>) -> PyResult<()> {
+    module.add("A5_INVALID_CELL", A5_INVALID_CELL)?;
+    module.add_function(wrap_pyfunction!(lonlat_to_cell_py, module)?)?;
+    module.add_function(wrap_pyfunction!(cell_to_lonlat, module)?)?;
+    module.add_function(wrap_pyfunction!(cell_to_boundary, module)?)?;
+    module.add_function(wrap_pyfunction!(lonlat_to_cell_batch, module)?)?;
+    module.add_function(wrap_pyfunction!(cell_to_lonlat_batch, module)?)?;
+    Ok(())
+}
@@ -0,0 +1,101 @@
+//! Web map tiles in the XYZ scheme of slippy maps.
+//!
+//! At zoom `z` the Web Mercator square is split into `2^z` by `2^z` tiles,
+//! with `x` counting east from the antimeridian and `y` counting south from
+//! the top edge at `WEB_MERCATOR_MAX_LATITUDE`. TMS numbers rows from the
+//! bottom instead, as `2^z - 1 - y`. Tile edges are meridians and parallels, so
+//! each tile is a longitude/latitude box. Web Mercator clamps latitudes beyond
+//! its limit to the edge of the map, so the top and bottom rows are taken to
+//! reach the poles, and cells there belong to them.
+
+use crate::geometry::WEB_MERCATOR_MAX_LATITUDE;
+use crate::{cell_bbox, polyfill, A5ErrorCode, Error, A5_MAX_TILE_ZOOM};
+
+/// Most tiles a single cell may be tested against, so a coarse cell at a deep
+/// zoom fails instead of enumerating millions of tiles.
+const TILE_LIMIT: u64 = 1 << 20;
+
+/// An XYZ tile.
+#[repr(C)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Tile {
+    pub z: u32,
+    pub x: u32,
+    pub y: u32,
+}
+
+fn validate_zoom(zoom: u32) -> Result<u64, Error> {
+    if zoom > A5_MAX_TILE_ZOOM {
+        return Err(Error::new(A5ErrorCode::OutOfBounds, format!("tile zoom {} out of bounds: must be at most {}", zoom, A5_MAX_TILE_ZOOM)));
+    }
+    Ok(1 << zoom)
+}
+
+/// Column of longitude `lon` at a zoom with `n` tiles a side, unwrapped: not
+/// reduced into `[0, n)`.
+fn column(lon: f64, n: u64) -> i64 {
+    ((lon + 180.0) / 360.0 * n as f64).floor() as i64
+}
+
+/// Row of latitude `lat` at a zoom with `n` tiles a side, clamped to the map.
+fn row(lat: f64, n: u64) -> u64 {
+    let phi = lat.clamp(-WEB_MERCATOR_MAX_LATITUDE, WEB_MERCATOR_MAX_LATITUDE).to_radians();
+    let y = (1.0 - (std::f64::consts::FRAC_PI_4 + phi / 2.0).tan().ln() / std::f64::consts::PI) / 2.0 * n as f64;
+    (y.floor().max(0.0) as u64).min(n - 1)
+}
+
+/// Latitude of the top edge of row `y` at a zoom with `n` tiles a side.
+fn row_top(y: u64, n: u64) -> f64 {
+    (std::f64::consts::PI * (1.0 - 2.0 * y as f64 / n as f64)).sinh().atan().to_degrees()
+}
+
+/// Bounds of the tile as `(west, south, east, north)`, with the edge rows
+/// reaching the poles.
+pub fn tile_bounds(tile: Tile) -> Result<(f64, f64, f64, f64), Error> {
+    let n = validate_zoom(tile.z)?;
+    let (x, y) = (u64::from(tile.x), u64::from(tile.y));
+    if x >= n || y >= n {
+        return Err(Error::new(A5ErrorCode::OutOfBounds, format!("tile {}/{}/{} out of bounds: zoom {} has {} tiles a side", tile.z, tile.x, tile.y, tile.z, n)));
+    }
+    let north = if y == 0 { 90.0 } else { row_top(y, n) };
+    let south = if y == n - 1 { -90.0 } else { row_top(y + 1, n) };
+    let west = x as f64 / n as f64 * 360.0 - 180.0;
+    let east = (x + 1) as f64 / n as f64 * 360.0 - 180.0;
+    Ok((west, south, east, north))
+}
+
+/// Returns the sorted tiles at `zoom` the boundary of `cell` overlaps, by the
+/// test of `polyfill::cell_intersects_bbox`, so that `cell` is among the
+/// `tile_to_cells` of each at its resolution.
+pub fn cell_to_tiles(cell: u64, zoom: u32) -> Result<Vec<Tile>, Error> {
+    let n = validate_zoom(zoom)?;
+    let bbox = cell_bbox(cell)?;
+    let max_lon = if bbox.wraps { bbox.max_lon + 360.0 } else { bbox.max_lon };
+    let (first, last) = (column(bbox.min_lon, n), column(max_lon, n));
+    let columns = ((last - first + 1) as u64).min(n);
+    let (top, bottom) = (row(bbox.max_lat, n), row(bbox.min_lat, n));
+    let candidates = columns * (bottom - top + 1);
+    if candidates > TILE_LIMIT {
+        return Err(Error::new(A5ErrorCode::OutOfBounds, format!("tiles out of bounds: {} candidate tiles exceed the limit of {}", candidates, TILE_LIMIT)));
+    }
+
+    let mut tiles = Vec::new();
+    for x in (0..columns).map(|i| (first + i as i64).rem_euclid(n as i64) as u32) {
+        for y in top as u32..=bottom as u32 {
+            let tile = Tile { z: zoom, x, y };
+            let (west, south, east, north) = tile_bounds(tile)?;
+            if polyfill::cell_intersects_bbox(cell, west, south, east, north)? {
+                tiles.push(tile);
+            }
+        }
+    }
+    tiles.sort_unstable();
+    Ok(tiles)
+}
+
+/// Returns the sorted cells at `resolution` overlapping the tile, as an
+/// `Intersects` fill of its bounds.
+pub fn tile_to_cells(tile: Tile, resolution: i32) -> Result<Vec<u64>, Error> {
+    let (west, south, east, north) = tile_bounds(tile)?;
+    polyfill::bbox_to_cells(west, south, east, north, resolution)
+}
@@ -0,0 +1,77 @@
+//! JavaScript bindings through `wasm-bindgen`, enabled by the `wasm` feature.
+//!
+//! Cells cross the boundary as 16 digit hex strings, since a JS number holds
+//! integers exactly only up to 2^53, and coordinates as `Float64Array`s.
+//! Failures throw a JS `Error` carrying the same message the C API returns.
+//! Build with `cargo rustc --lib --crate-type cdylib --features wasm --target
+//! wasm32-unknown-unknown` and run `wasm-bindgen` over the result.
+
+use crate::{boundary_points, lonlat_to_cell, parse_hex_cell, validate_cell, validate_resolution};
+use wasm_bindgen::prelude::*;
+
+fn hex(cell: u64) -> String {
+    format!("{:016x}", cell)
+}
+
+fn cell(hex: &str) -> Result<u64, String> {
+    let cell = parse_hex_cell(hex)?;
+    validate_cell(cell)?;
+    Ok(cell)
+}
+
+pub(crate) fn lon_lat_to_cell_hex(lon: f64, lat: f64, resolution: i32) -> Result<String, String> {
+    validate_resolution(resolution)?;
+    lonlat_to_cell(lon, lat, resolution).map(hex).map_err(String::from)
+}
+
+pub(crate) fn cell_to_lon_lat_pair(cell_hex: &str) -> Result<Vec<f64>, String> {
+    let center = a5::cell_to_lonlat(cell(cell_hex)?)?;
+    Ok(vec![center.longitude(), center.latitude()])
+}
+
+pub(crate) fn cell_to_boundary_flat(cell_hex: &str) -> Result<Vec<f64>, String> {
+    Ok(boundary_points(cell(cell_hex)?)?.into_iter().flat_map(|(lon, lat)| [lon, lat]).collect())
+}
+
+pub(crate) fn cell_to_parent_hex(cell_hex: &str, resolution: i32) -> Result<String, String> {
+    Ok(hex(crate::cell_to_parent(parse_hex_cell(cell_hex)?, resolution)?))
+}
+
+pub(crate) fn cell_to_children_hex(cell_hex: &str, resolution: i32) -> Result<Vec<String>, String> {
+    validate_resolution(resolution)?;
+    Ok(crate::cell_to_children(parse_hex_cell(cell_hex)?, resolution)?.into_iter().map(hex).collect())
+}
+
+fn throw(message: String) -> JsError {
+    JsError::new(&message)
+}
+
+/// Cell containing the point at `resolution`, as a hex string.
+#[wasm_bindgen(js_name = lonLatToCell)]
+pub fn lon_lat_to_cell(lon: f64, lat: f64, resolution: i32) -> Result<String, JsError> {
+    lon_lat_to_cell_hex(lon, lat, resolution).map_err(throw)
+}
+
+/// Center of the cell as `[lon, lat]`.
+#[wasm_bindgen(js_name = cellToLonLat)]
+pub fn cell_to_lon_lat(cell: &str) -> Result<Vec<f64>, JsError> {
+    cell_to_lon_lat_pair(cell).map_err(throw)
+}
+
+/// Open boundary of the cell as interleaved `[lon0, lat0, lon1, lat1, ...]`.
+#[wasm_bindgen(js_name = cellToBoundary)]
+pub fn cell_to_boundary(cell: &str) -> Result<Vec<f64>, JsError> {
+    cell_to_boundary_flat(cell).map_err(throw)
+}
+
+/// Ancestor of the cell at `resolution`, as a hex string.
+#[wasm_bindgen(js_name = cellToParent)]
+pub fn cell_to_parent(cell: &str, resolution: i32) -> Result<String, JsError> {
+    cell_to_parent_hex(cell, resolution).map_err(throw)
+}
+
+/// Descendants of the cell at `resolution`, as an array of hex strings.
+#[wasm_bindgen(js_name = cellToChildren)]
+pub fn cell_to_children(cell: &str, resolution: i32) -> Result<Vec<String>, JsError> {
+    cell_to_children_hex(cell, resolution).map_err(throw)
+}
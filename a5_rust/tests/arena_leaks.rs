@@ -0,0 +1,56 @@
+//! Checks that freeing an arena returns every byte its arrays took. This has
+//! its own test binary because it swaps in a counting global allocator.
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicIsize, Ordering};
+
+use a5_rust::*;
+
+struct Counting;
+
+static LIVE: AtomicIsize = AtomicIsize::new(0);
+
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        LIVE.fetch_add(layout.size() as isize, Ordering::SeqCst);
+        unsafe { System.alloc(layout) }
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        LIVE.fetch_sub(layout.size() as isize, Ordering::SeqCst);
+        unsafe { System.dealloc(ptr, layout) }
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: Counting = Counting;
+
+/// Fills an arena with results and failures from every `_arena` function and
+/// frees it, twice over so lazily built tables are in place before counting.
+fn fill_and_free() {
+    let cell = a5_lon_lat_to_cell(-3.7, 40.4, 6).value;
+    let ring = [LonLatDegrees { lon: -4.0, lat: 40.0 }, LonLatDegrees { lon: -3.0, lat: 40.0 }, LonLatDegrees { lon: -3.5, lat: 41.0 }];
+    let arena = a5_arena_new();
+    for _ in 0..10 {
+        let children = a5_cell_to_children_arena(arena, cell, 8);
+        assert!(children.error.is_null());
+        a5_compact_arena(arena, children.data, children.len);
+        a5_uncompact_arena(arena, &cell, 1, 7);
+        a5_grid_disk_arena(arena, cell, 2);
+        a5_cell_to_neighbors_arena(arena, cell);
+        a5_polygon_to_cells_arena(arena, ring.as_ptr(), ring.len(), 8, FillMode::Intersects);
+        a5_cell_to_boundary_arena(arena, cell, CellBoundaryOptions::new(true, None));
+        // Failures carry an error string, which the arena owns too
+        assert!(!a5_cell_to_children_arena(arena, cell, 99).error.is_null());
+        assert!(!a5_cell_to_boundary_arena(arena, 0xfe00000000000000, CellBoundaryOptions::new(false, None)).error.is_null());
+    }
+    a5_arena_free(arena);
+}
+
+#[test]
+fn freeing_an_arena_releases_all_its_arrays() {
+    fill_and_free();
+    let before = LIVE.load(Ordering::SeqCst);
+    fill_and_free();
+    assert_eq!(LIVE.load(Ordering::SeqCst), before);
+}
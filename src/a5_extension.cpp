@@ -10,7 +10,7 @@
 namespace duckdb {
 
 #define MAX_RESOLUTION       30
-#define A5_EXTENSION_VERSION "2026031101"
+#define A5_EXTENSION_VERSION "2026101402"
 
 // Helper function to validate resolution and throw with a clear error message
 inline void ValidateResolution(int32_t resolution, const char *function_name) {
@@ -23,7 +23,7 @@ inline void ValidateResolution(int32_t resolution, const char *function_name) {
 inline void ThrowRustError(char *error_ptr, const char *function_name) {
 	if (error_ptr != nullptr) {
 		string error_msg = string(function_name) + ": " + string(error_ptr);
-		free(error_ptr);
+		a5_free_cstring(error_ptr);
 		throw InvalidInputException(error_msg);
 	}
 }
@@ -32,7 +32,7 @@ inline void ThrowRustError(char *error_ptr, const char *function_name) {
 inline void ThrowCellArrayError(CellArray &arr, const char *function_name) {
 	if (arr.error) {
 		string error_msg = string(function_name) + ": " + string(arr.error);
-		a5_free_cell_array(arr);
+		a5_free_cell_array_ptr(&arr);
 		throw InvalidInputException(error_msg);
 	}
 }
@@ -41,7 +41,7 @@ inline void ThrowCellArrayError(CellArray &arr, const char *function_name) {
 inline void ThrowLonLatArrayError(LonLatDegreesArray &arr, const char *function_name) {
 	if (arr.error) {
 		string error_msg = string(function_name) + ": " + string(arr.error);
-		a5_free_lonlatdegrees_array(arr);
+		a5_free_lonlatdegrees_array_ptr(&arr);
 		throw InvalidInputException(error_msg);
 	}
 }
@@ -145,7 +145,7 @@ inline void A5CellToChildrenFun(DataChunk &args, ExpressionState &state, Vector
 			    ThrowCellArrayError(child_result, "a5_cell_to_children");
 
 			    if (child_result.len == 0) {
-				    a5_free_cell_array(child_result);
+				    a5_free_cell_array_ptr(&child_result);
 				    return list_entry_t {0, 0};
 			    }
 			    for (size_t i = 0; i < child_result.len; i++) {
@@ -153,7 +153,7 @@ inline void A5CellToChildrenFun(DataChunk &args, ExpressionState &state, Vector
 			    }
 			    list_entry_t out {offset, child_result.len};
 			    offset += child_result.len;
-			    a5_free_cell_array(child_result);
+			    a5_free_cell_array_ptr(&child_result);
 			    return out;
 		    });
 	} else if (args.ColumnCount() == 1) {
@@ -164,7 +164,7 @@ inline void A5CellToChildrenFun(DataChunk &args, ExpressionState &state, Vector
 			ThrowCellArrayError(child_result, "a5_cell_to_children");
 
 			if (child_result.len == 0) {
-				a5_free_cell_array(child_result);
+				a5_free_cell_array_ptr(&child_result);
 				return list_entry_t {0, 0};
 			}
 			for (size_t i = 0; i < child_result.len; i++) {
@@ -172,7 +172,7 @@ inline void A5CellToChildrenFun(DataChunk &args, ExpressionState &state, Vector
 			}
 			list_entry_t out {offset, child_result.len};
 			offset += child_result.len;
-			a5_free_cell_array(child_result);
+			a5_free_cell_array_ptr(&child_result);
 			return out;
 		});
 	} else {
@@ -199,7 +199,7 @@ inline void A5CellToBoundaryFun(DataChunk &args, ExpressionState &state, Vector
 		auto boundary_result = a5_cell_to_boundary(cell_id, options);
 		ThrowLonLatArrayError(boundary_result, "a5_cell_to_boundary");
 		if (boundary_result.len == 0) {
-			a5_free_lonlatdegrees_array(boundary_result);
+			a5_free_lonlatdegrees_array_ptr(&boundary_result);
 			return {0, 0};
 		}
 
@@ -211,7 +211,7 @@ inline void A5CellToBoundaryFun(DataChunk &args, ExpressionState &state, Vector
 
 		list_entry_t out {offset, boundary_result.len};
 		offset += boundary_result.len;
-		a5_free_lonlatdegrees_array(boundary_result);
+		a5_free_lonlatdegrees_array_ptr(&boundary_result);
 		return out;
 	};
 
@@ -245,7 +245,7 @@ inline void A5GetRes0CellsFun(DataChunk &args, ExpressionState &state, Vector &r
 	for (size_t i = 0; i < cells.len; i++) {
 		cell_vec.emplace_back(Value::UBIGINT(cells.data[i]));
 	}
-	a5_free_cell_array(cells);
+	a5_free_cell_array_ptr(&cells);
 	Value val = Value::LIST(LogicalType::UBIGINT, cell_vec);
 
 	D_ASSERT(args.ColumnCount() == 0);
@@ -269,7 +269,7 @@ inline void A5CompactFun(DataChunk &args, ExpressionState &state, Vector &result
 		    auto compact_result = a5_compact(cell_list_data + cell_list_entry.offset, cell_list_entry.length);
 		    ThrowCellArrayError(compact_result, "a5_compact");
 		    if (compact_result.len == 0) {
-			    a5_free_cell_array(compact_result);
+			    a5_free_cell_array_ptr(&compact_result);
 			    return list_entry_t {0, 0};
 		    }
 		    ListVector::Reserve(result, result_size + compact_result.len);
@@ -280,7 +280,7 @@ inline void A5CompactFun(DataChunk &args, ExpressionState &state, Vector &result
 
 		    list_entry_t out {offset, compact_result.len};
 		    offset += compact_result.len;
-		    a5_free_cell_array(compact_result);
+		    a5_free_cell_array_ptr(&compact_result);
 		    return out;
 	    });
 }
@@ -306,7 +306,7 @@ inline void A5UncompactFun(DataChunk &args, ExpressionState &state, Vector &resu
 		        a5_uncompact(cell_list_data + cell_list_entry.offset, cell_list_entry.length, target_resolution);
 		    ThrowCellArrayError(compact_result, "a5_uncompact");
 		    if (compact_result.len == 0) {
-			    a5_free_cell_array(compact_result);
+			    a5_free_cell_array_ptr(&compact_result);
 			    return list_entry_t {0, 0};
 		    }
 		    ListVector::Reserve(result, result_size + compact_result.len);
@@ -317,7 +317,7 @@ inline void A5UncompactFun(DataChunk &args, ExpressionState &state, Vector &resu
 
 		    list_entry_t out {offset, compact_result.len};
 		    offset += compact_result.len;
-		    a5_free_cell_array(compact_result);
+		    a5_free_cell_array_ptr(&compact_result);
 		    return out;
 	    });
 }
@@ -409,7 +409,7 @@ inline void A5SphericalCapFun(DataChunk &args, ExpressionState &state, Vector &r
 		    ThrowCellArrayError(cap_result, "a5_spherical_cap");
 
 		    if (cap_result.len == 0) {
-			    a5_free_cell_array(cap_result);
+			    a5_free_cell_array_ptr(&cap_result);
 			    return list_entry_t {0, 0};
 		    }
 		    for (size_t i = 0; i < cap_result.len; i++) {
@@ -417,7 +417,7 @@ inline void A5SphericalCapFun(DataChunk &args, ExpressionState &state, Vector &r
 		    }
 		    list_entry_t out {offset, cap_result.len};
 		    offset += cap_result.len;
-		    a5_free_cell_array(cap_result);
+		    a5_free_cell_array_ptr(&cap_result);
 		    return out;
 	    });
 }
@@ -434,11 +434,11 @@ inline void A5GridDiskFun(DataChunk &args, ExpressionState &state, Vector &resul
 		    if (k < 0) {
 			    throw InvalidInputException("a5_grid_disk: k must be >= 0");
 		    }
-		    auto disk_result = a5_grid_disk(cell_id, static_cast<uintptr_t>(k));
+		    auto disk_result = a5_grid_disk(cell_id, k);
 		    ThrowCellArrayError(disk_result, "a5_grid_disk");
 
 		    if (disk_result.len == 0) {
-			    a5_free_cell_array(disk_result);
+			    a5_free_cell_array_ptr(&disk_result);
 			    return list_entry_t {0, 0};
 		    }
 		    for (size_t i = 0; i < disk_result.len; i++) {
@@ -446,7 +446,7 @@ inline void A5GridDiskFun(DataChunk &args, ExpressionState &state, Vector &resul
 		    }
 		    list_entry_t out {offset, disk_result.len};
 		    offset += disk_result.len;
-		    a5_free_cell_array(disk_result);
+		    a5_free_cell_array_ptr(&disk_result);
 		    return out;
 	    });
 }
@@ -463,11 +463,11 @@ inline void A5GridDiskVertexFun(DataChunk &args, ExpressionState &state, Vector
 		    if (k < 0) {
 			    throw InvalidInputException("a5_grid_disk_vertex: k must be >= 0");
 		    }
-		    auto disk_result = a5_grid_disk_vertex(cell_id, static_cast<uintptr_t>(k));
+		    auto disk_result = a5_grid_disk_vertex(cell_id, k);
 		    ThrowCellArrayError(disk_result, "a5_grid_disk_vertex");
 
 		    if (disk_result.len == 0) {
-			    a5_free_cell_array(disk_result);
+			    a5_free_cell_array_ptr(&disk_result);
 			    return list_entry_t {0, 0};
 		    }
 		    for (size_t i = 0; i < disk_result.len; i++) {
@@ -475,7 +475,7 @@ inline void A5GridDiskVertexFun(DataChunk &args, ExpressionState &state, Vector
 		    }
 		    list_entry_t out {offset, disk_result.len};
 		    offset += disk_result.len;
-		    a5_free_cell_array(disk_result);
+		    a5_free_cell_array_ptr(&disk_result);
 		    return out;
 	    });
 }
@@ -7,16 +7,166 @@
 #include <cstdlib>
 #include <ostream>
 #include <new>
+#ifndef ARROW_C_DATA_INTERFACE
+#define ARROW_C_DATA_INTERFACE
+
+#define ARROW_FLAG_DICTIONARY_ORDERED 1
+#define ARROW_FLAG_NULLABLE 2
+#define ARROW_FLAG_MAP_KEYS_SORTED 4
+
+struct ArrowSchema {
+  const char* format;
+  const char* name;
+  const char* metadata;
+  int64_t flags;
+  int64_t n_children;
+  struct ArrowSchema** children;
+  struct ArrowSchema* dictionary;
+  void (*release)(struct ArrowSchema*);
+  void* private_data;
+};
+
+struct ArrowArray {
+  int64_t length;
+  int64_t null_count;
+  int64_t offset;
+  int64_t n_buffers;
+  int64_t n_children;
+  const void** buffers;
+  struct ArrowArray** children;
+  struct ArrowArray* dictionary;
+  void (*release)(struct ArrowArray*);
+  void* private_data;
+};
+
+#endif  // ARROW_C_DATA_INTERFACE
+
+// Declarations below name the structs without the tag, as C++ does
+typedef struct ArrowSchema ArrowSchema;
+typedef struct ArrowArray ArrowArray;
+
+// Marks the functions Rust deprecates, so C and C++ callers get a warning too
+#ifndef A5_DEPRECATED
+#if defined(__cplusplus) && __cplusplus >= 201402L
+#define A5_DEPRECATED(note) [[deprecated(note)]]
+#elif defined(__GNUC__) || defined(__clang__)
+#define A5_DEPRECATED(note) __attribute__((deprecated(note)))
+#elif defined(_MSC_VER)
+#define A5_DEPRECATED(note) __declspec(deprecated(note))
+#else
+#define A5_DEPRECATED(note)
+#endif
+#endif
+
+
+/// Version of this library's C ABI, the layout of its `#[repr(C)]` types and
+/// the signatures of its exported functions. It is bumped on any change that
+/// would make code compiled against an older header misbehave: a struct
+/// field added, removed, reordered or retyped, an enum value renumbered, or an
+/// exported function changing or losing parameters. Adding new functions,
+/// types or trailing enum values does not bump it.
+///
+/// Version 2 added the `arena` flag to `CellArray` and `LonLatDegreesArray`.
+constexpr static const uint32_t A5_ABI_VERSION = 2;
+
+/// Sentinel resolution meaning "use the default" where a function accepts one.
+constexpr static const int32_t A5_DEFAULT_RESOLUTION = -1;
+
+/// Cell ID written into batch outputs for rows that could not be converted.
+/// This is the world cell, which is never produced for a real resolution.
+constexpr static const uint64_t A5_INVALID_CELL = 0;
+
+/// Largest number of vertices `a5_cell_to_boundary_densified` returns.
+constexpr static const uint64_t A5_DENSIFY_POINT_LIMIT = 10000000;
+
+/// Largest number of cells `a5_cell_to_descendants` returns.
+constexpr static const uint64_t A5_DESCENDANT_LIMIT = 100000000;
+
+/// Deepest web map zoom accepted by `a5_cell_to_covering_tiles` and
+/// `a5_tile_to_cells`; tile coordinates then still fit in 32 bits.
+constexpr static const uint32_t A5_MAX_TILE_ZOOM = 30;
+
+/// Largest number of points `a5_cells_along_geodesic` samples along a path.
+constexpr static const uint64_t A5_GEODESIC_SAMPLE_LIMIT = 10000000;
+
+/// Largest grid distance `a5_grid_distance` searches before giving up.
+constexpr static const uint64_t A5_GRID_DISTANCE_LIMIT = 2000;
+
+/// Radius in meters of the WGS84 sphere Web Mercator projects from.
+constexpr static const double WEB_MERCATOR_RADIUS = 6378137.0;
+
+/// Latitude in degrees at which Web Mercator's square world ends.
+constexpr static const double WEB_MERCATOR_MAX_LATITUDE = 85.05112877980659;
+
+/// Stable error category carried by every result struct, so callers can branch
+/// on the kind of failure without parsing the error string.
+enum class A5ErrorCode {
+  None = 0,
+  InvalidResolution,
+  InvalidCell,
+  InvalidCoordinate,
+  OutOfBounds,
+  Internal,
+  /// A caller-allocated buffer was too small; the required length was written out.
+  BufferTooSmall,
+  /// A cell id string held something other than hex digits.
+  NotHex,
+  /// A cell id string had the wrong number of hex digits.
+  WrongLength,
+  /// An input array pointer was null although its length was not 0, or a
+  /// required output pointer was null; nothing was written.
+  NullPointer,
+};
+
+/// Unit of an area returned by `a5_cell_area_in`.
+enum class AreaUnit {
+  SquareMeters = 0,
+  SquareKilometers,
+  Hectares,
+  SquareMiles,
+};
+
+/// Which cells `a5_polygon_to_cells` returns.
+enum class FillMode {
+  /// Cells whose center lies inside the polygon.
+  Center = 0,
+  /// Cells whose boundary overlaps the polygon at all.
+  Intersects = 1,
+};
+
+/// Owner of the arrays returned by the `_arena` functions, so that a batch of
+/// results is released by a single `a5_arena_free` rather than one free per
+/// array. Arena arrays have `arena` set, and the `a5_free_*_ptr` functions
+/// leave them untouched, so code written for heap arrays may still call them.
+/// Create one with `a5_arena_new`.
+///
+/// This is shared ownership rather than arena allocation: each result is
+/// still its own heap allocation, made exactly as for the plain function, and
+/// the arena only records it. It saves the caller the per-array frees, not the
+/// allocator calls.
+struct A5Arena;
+
+/// A handle for indexing a stream of points at one resolution, created by
+/// `a5_indexer_new` and freed by `a5_indexer_free`. The resolution is
+/// validated once at creation rather than on every point. The `a5` crate
+/// already keeps its projection tables per thread and derives its
+/// per-resolution scale with a single multiplication, so there is no further
+/// setup to hoist: results match `a5_lon_lat_to_cell` exactly, at the same
+/// cost per point. An indexer is read-only and may be shared between threads.
+struct A5Indexer;
 
 struct ResultU64 {
   uint64_t value;
   char *error;
+  A5ErrorCode code;
 };
 
-struct ResultLonLat {
-  double longitude;
-  double latitude;
+struct CellArray {
+  uint64_t *data;
+  uintptr_t len;
   char *error;
+  A5ErrorCode code;
+  bool arena;
 };
 
 struct LonLatDegrees {
@@ -24,16 +174,39 @@ struct LonLatDegrees {
   double lat;
 };
 
+struct ResultF64 {
+  double value;
+  char *error;
+  A5ErrorCode code;
+};
+
 struct LonLatDegreesArray {
   LonLatDegrees *data;
   uintptr_t len;
   char *error;
+  A5ErrorCode code;
+  bool arena;
 };
 
-struct CellArray {
-  uint64_t *data;
+struct ResultLonLat {
+  double longitude;
+  double latitude;
+  char *error;
+  A5ErrorCode code;
+};
+
+/// An XYZ tile.
+struct Tile {
+  uint32_t z;
+  uint32_t x;
+  uint32_t y;
+};
+
+struct TileArray {
+  Tile *data;
   uintptr_t len;
   char *error;
+  A5ErrorCode code;
 };
 
 struct CellBoundaryOptions {
@@ -42,44 +215,1026 @@ struct CellBoundaryOptions {
   int32_t segments;
 };
 
+/// Boundary of a cell split at the antimeridian, from
+/// `a5_cell_to_boundary_split`. Free it with `a5_free_split_boundary_ptr`.
+struct SplitBoundary {
+  /// The whole boundary, or when `split` is set its part on one side of the
+  /// antimeridian
+  LonLatDegreesArray first;
+  /// The part on the other side, empty unless `split` is set
+  LonLatDegreesArray second;
+  bool split;
+  char *error;
+  A5ErrorCode code;
+};
+
+/// Options for `a5_cell_to_boundary_opts`.
+struct BoundaryOptions {
+  /// Number of segments to split each edge into; 0 uses the default for the cell's resolution
+  uint32_t segments;
+  bool closed_ring;
+};
+
+/// Callback invoked once per cell by the `_foreach` functions with the caller's
+/// `ctx`; return false to stop early. It must not unwind: a panic or C++
+/// exception escaping it is undefined behavior.
+using A5CellCallback = bool(*)(void *ctx, uint64_t cell);
+
+/// Axis-aligned bounding box of a cell, in degrees.
+///
+/// When `wraps` is true the box crosses the antimeridian and `min_lon` is
+/// greater than `max_lon`: it covers `[min_lon, 180]` and `[-180, max_lon]`.
+struct BBox {
+  double min_lon;
+  double min_lat;
+  double max_lon;
+  double max_lat;
+  bool wraps;
+  char *error;
+  A5ErrorCode code;
+};
+
 struct ResultSpherical {
   double theta;
   double phi;
   char *error;
+  A5ErrorCode code;
 };
 
 extern "C" {
 
+/// Returns a static string such as `a5_rust 0.1.0 (a5 0.7.1)` naming this
+/// library's version and the `a5` version beneath it. The string is owned by
+/// the library and must not be freed.
+const char *a5_version();
+
+/// Returns a static string describing the loaded binary's target and build
+/// profile, such as `x86_64-linux release`, to tell which library was loaded.
+/// The string is owned by the library and must not be freed.
+const char *a5_build_info();
+
+/// Returns the `A5_ABI_VERSION` the library was built with. A binding should
+/// compare it with the `A5_ABI_VERSION` in the header it was compiled
+/// against when loading the library, and refuse to run on a mismatch rather
+/// than misread the library's structs.
+uint32_t a5_abi_version();
+
+/// Returns the finest resolution supported, so callers can validate up front.
+int32_t a5_max_resolution();
+
+/// Returns the cell containing the point at `resolution`.
+///
+/// Any finite longitude is accepted and wrapped into [-180, 180), so 540 is
+/// the same as 180. A non-finite value or a latitude outside [-90, 90] gives
+/// `InvalidCoordinate` instead of being passed on to the grid.
 ResultU64 a5_lon_lat_to_cell(double longitude, double latitude, int32_t resolution);
 
+/// `a5_lon_lat_to_cell` that also reports how far the point lies from the
+/// center of its cell: writes the cell to `out_cell` and the great-circle
+/// distance in meters from the point to the cell's center, as from
+/// `a5_cell_to_lon_lat`, to `out_offset_m`: the error made by replacing the
+/// point with its cell. Returns `NullPointer` if either out
+/// pointer is null.
+A5ErrorCode a5_lon_lat_to_cell_with_error(double longitude,
+                                          double latitude,
+                                          int32_t resolution,
+                                          uint64_t *out_cell,
+                                          double *out_offset_m);
+
+/// Sets the number of threads used by the batch functions when the library is
+/// built with the `parallel` feature. Must be called before the first batch
+/// call; afterwards, or without the feature, it returns false and changes
+/// nothing. Without a call, rayon reads `RAYON_NUM_THREADS` and otherwise uses
+/// one thread per core.
+bool a5_set_thread_pool_size(uintptr_t n);
+
+/// Sets the number of cell boundaries `a5_cell_to_boundary` keeps when the
+/// library is built with the `boundary-cache` feature, evicting the least
+/// recently used beyond it. The cache starts at 0, disabled, and setting 0
+/// again empties it and restores the uncached path. Without the feature it
+/// returns false and changes nothing.
+bool a5_set_boundary_cache_size(uintptr_t n);
+
+/// Empties the boundary cache, keeping its size; see
+/// `a5_set_boundary_cache_size`. Without the `boundary-cache` feature it does
+/// nothing.
+void a5_clear_boundary_cache();
+
+/// Sets up the library's lazily initialized global state up front: the `a5`
+/// crate's lookup tables, the version strings and, with the `parallel`
+/// feature, rayon's global thread pool, so that the first real call does not
+/// pay for them. Call `a5_set_thread_pool_size` before this if at all.
+///
+/// Calling it is optional, as every function initializes what it needs on
+/// first use. It is idempotent and takes no locks of its own, so it is safe
+/// from a library constructor or `DllMain` wrapper. Returns `Internal` only
+/// if setting up fails.
+A5ErrorCode a5_init();
+
+/// Frees the caches the library builds up, including the calling thread's
+/// last error and the boundary cache's entries, though not its size, so that
+/// leak checkers such as valgrind see a clean exit. It is
+/// idempotent, and the library stays usable afterwards, rebuilding caches on
+/// demand. Rayon's global pool, the resolution 0 cells and the `a5` crate's
+/// fixed tables live until the process exits, which leak checkers report as
+/// still reachable rather than lost.
+void a5_shutdown();
+
+/// Converts `len` coordinates to cells at `resolution` in a single call.
+///
+/// A row that fails to convert does not abort the batch: its slot is set to
+/// `A5_INVALID_CELL` and conversion continues; coordinates are validated as
+/// for `a5_lon_lat_to_cell`. The returned array always has `len` entries and
+/// must be freed with `a5_free_cell_array_ptr`.
+///
+/// `in_validity` and `out_validity` are optional Arrow-style bitmaps of `len`
+/// bits (bit `i % 8` of byte `i / 8`, set for valid rows). Rows that
+/// `in_validity` marks null are skipped and come out as `A5_INVALID_CELL`;
+/// `out_validity` is cleared for those and for rows that failed to convert.
+CellArray a5_lon_lat_to_cell_batch(const LonLatDegrees *coords,
+                                   uintptr_t len,
+                                   int32_t resolution,
+                                   const uint8_t *in_validity,
+                                   uint8_t *out_validity);
+
+/// Returns an indexer for `resolution`, or null for an invalid resolution.
+A5Indexer *a5_indexer_new(int32_t resolution);
+
+/// Frees an indexer. A null `indexer` is ignored.
+void a5_indexer_free(A5Indexer *indexer);
+
+/// The cell containing the point at the indexer's resolution, with the same
+/// result and errors as `a5_lon_lat_to_cell`. A null `indexer` gives
+/// `NullPointer`.
+ResultU64 a5_indexer_index(const A5Indexer *indexer, double longitude, double latitude);
+
+/// `a5_lon_lat_to_cell_batch` at the indexer's resolution, without validity
+/// bitmaps: rows that fail come out as `A5_INVALID_CELL`. A null `indexer`
+/// gives `NullPointer`.
+CellArray a5_indexer_index_batch(const A5Indexer *indexer,
+                                 const LonLatDegrees *coords,
+                                 uintptr_t len);
+
+/// Returns the ancestor of `index` at `parent_resolution`:
+///
+/// - a coarser resolution gives the ancestor there;
+/// - the cell's own resolution gives the cell itself, so a target computed
+///   as "at most this coarse" needs no special case;
+/// - a finer resolution fails with `InvalidResolution`.
+///
+/// A resolution outside [0, 30] is `InvalidResolution` and an invalid cell
+/// `InvalidCell`, rather than the `a5` crate's world cell.
 ResultU64 a5_cell_to_parent(uint64_t index, int32_t parent_resolution);
 
+/// Returns the parent of `cell` one resolution up. A resolution 0 cell has no
+/// parent and gives an `InvalidResolution` error rather than the world cell.
+ResultU64 a5_cell_to_immediate_parent(uint64_t cell);
+
+/// Rolls each of `len` cells up to its own target resolution in one call:
+/// row `i` becomes the ancestor of `cells[i]` at `resolutions[i]`, as from
+/// `a5_cell_to_parent`, or with a null `resolutions` its immediate parent, as
+/// from `a5_cell_to_immediate_parent`.
+///
+/// A row that fails, such as an invalid cell or a target finer than the
+/// cell, does not abort the batch: its slot is set to `A5_INVALID_CELL`. The
+/// optional `in_validity` and `out_validity` bitmaps work as for
+/// `a5_lon_lat_to_cell_batch`. The returned array always has `len` entries
+/// and must be freed with `a5_free_cell_array_ptr`.
+CellArray a5_cell_to_parent_batch(const uint64_t *cells,
+                                  const int32_t *resolutions,
+                                  uintptr_t len,
+                                  const uint8_t *in_validity,
+                                  uint8_t *out_validity);
+
+/// Returns every ancestor of `cell`, from resolution 0 up to but excluding the
+/// cell's own resolution. A resolution 0 cell yields an empty array.
+CellArray a5_cell_to_parent_chain(uint64_t cell);
+
+/// Average area in square meters of a cell at `resolution`, or NaN if the
+/// resolution is out of range.
 double a5_cell_area(int32_t resolution);
 
+/// Area in square meters of this particular cell, on the authalic sphere.
+///
+/// Unlike `a5_cell_area`, which is the average for a resolution, this measures
+/// the cell's own boundary: the spherical polygon through its boundary vertices,
+/// split into triangles about the cell center, each measured by its spherical
+/// excess (see `geometry::spherical_polygon_area`).
+ResultF64 a5_cell_exact_area(uint64_t cell);
+
+/// Writes the area-weighted centroid of `cell` to `out_lon` and `out_lat` and
+/// its area in square meters to `out_area_m2`, from one boundary computation.
+///
+/// The area is `a5_cell_exact_area`. The centroid is the center of mass of
+/// the same spherical polygon, which differs slightly from the point
+/// `a5_cell_to_lon_lat` returns: that is the cell's generator point, from
+/// which A5 projects the cell, and does not in general sit at its centroid.
+/// Returns `NullPointer` if any out pointer is null.
+A5ErrorCode a5_cell_to_center_and_area(uint64_t cell,
+                                       double *out_lon,
+                                       double *out_lat,
+                                       double *out_area_m2);
+
+/// Writes the center of `cell`, as from `a5_cell_to_lon_lat`, projected to
+/// Web Mercator (EPSG:3857) meters to `out_x` and `out_y`. Latitudes beyond
+/// about ±85.05°, where the projection ends, are clamped to that limit.
+/// Returns `NullPointer` if either out pointer is null.
+A5ErrorCode a5_cell_to_center_mercator(uint64_t cell, double *out_x, double *out_y);
+
+/// Finds the cell containing the point, as `a5_lon_lat_to_cell`, and writes
+/// it to `out_cell` with the point's local coordinates in it to `out_u` and
+/// `out_v`.
+///
+/// Local coordinates come from the gnomonic projection onto the plane that
+/// touches the sphere at the cell's center, as from `a5_cell_to_lon_lat`,
+/// with x pointing east and y north; at a pole x points toward longitude 90
+/// and y toward longitude 180 in the north or 0 in the south. `u` and `v` are
+/// x and y rescaled so the bounding box of the cell's boundary, traced with
+/// 32 segments per edge to follow its curve, spans 0 to 1: `u = 0` is its
+/// west side, `u = 1` its east side, `v = 0` its south side and `v = 1` its
+/// north side. The pentagon only fills part of that box, so points in the
+/// cell lie in `[0, 1]` but not every such `(u, v)` is in the cell. The
+/// lookup occasionally assigns a point lying just past an edge to the cell,
+/// which can put `u` or `v` a few percent outside `[0, 1]`.
+/// `a5_cell_local_to_lon_lat` is the inverse.
+///
+/// Returns `NullPointer` if any out pointer is null.
+A5ErrorCode a5_lon_lat_to_cell_local(double longitude,
+                                     double latitude,
+                                     int32_t resolution,
+                                     uint64_t *out_cell,
+                                     double *out_u,
+                                     double *out_v);
+
+/// Writes the point at local coordinates `(u, v)` in `cell`, as defined by
+/// `a5_lon_lat_to_cell_local`, to `out_lon` and `out_lat`. Values outside
+/// `[0, 1]` extend the frame past the cell's bounding box. Longitudes are in
+/// [-180, 180]. Returns `NullPointer` if either out pointer is null.
+A5ErrorCode a5_cell_local_to_lon_lat(uint64_t cell,
+                                     double u,
+                                     double v,
+                                     double *out_lon,
+                                     double *out_lat);
+
+/// Returns the open boundary of `cell`, as from `a5_cell_to_boundary` with
+/// default options, projected to Web Mercator (EPSG:3857) meters: each
+/// entry's `lon` holds x and `lat` holds y. Vertices beyond about ±85.05°
+/// are clamped to that latitude, so a polar cell comes out cut off flat at
+/// the top or bottom of the map. Free with `a5_free_lonlatdegrees_array_ptr`.
+LonLatDegreesArray a5_cell_to_boundary_mercator(uint64_t cell);
+
+/// Perimeter in meters of `cell`, on the authalic sphere.
+///
+/// A5 edges are straight on the dodecahedron face, not great circles, so they
+/// bow slightly on the sphere. Each edge is densified to 8 times the segment
+/// count `a5_cell_to_boundary` uses by default before the great-circle lengths
+/// of the pieces are summed, so the result does not underestimate the edges
+/// with chords and densifying further changes it negligibly.
+ResultF64 a5_cell_perimeter(uint64_t cell);
+
+/// Average area of a cell at `resolution`, expressed in `unit`.
+ResultF64 a5_cell_area_in(int32_t resolution, AreaUnit unit);
+
+/// Writes the average cell area in square meters, as from `a5_cell_area`,
+/// for resolutions 0 upward into `out`, index `i` holding resolution `i`,
+/// stopping at `max_len` entries or at `a5_max_resolution`. Returns the number
+/// written: 31 for a large enough buffer, 0 if `out` is null.
+uintptr_t a5_cell_area_table(double *out, uintptr_t max_len);
+
+/// Typical edge length in meters of a cell at `resolution`: the edge of a
+/// regular pentagon with the average area from `a5_cell_area`. Real cells
+/// are irregular, so their edges vary around it. NaN if the resolution is out
+/// of range.
+double a5_cell_edge_length(int32_t resolution);
+
+/// The resolution whose average cell area, as from `a5_cell_area`, is
+/// closest to `target_area_m2` by ratio, so 2 km² and 8 km² are equally far
+/// from 4 km². Returns -1 for a target that is not positive and finite, or
+/// lies beyond the resolutions: nearer by ratio to the whole sphere than to a
+/// resolution 0 cell, or to a quarter of a resolution 30 cell than to a whole
+/// one.
+int32_t a5_resolution_for_area(double target_area_m2);
+
+/// The resolution whose typical edge length, as from `a5_cell_edge_length`,
+/// is closest to `target_edge_m` by ratio. Returns -1 in the same cases as
+/// `a5_resolution_for_area`.
+int32_t a5_resolution_for_edge_length(double target_edge_m);
+
 ResultLonLat a5_cell_to_lon_lat(uint64_t cell);
 
+/// Converts `len` cells to their center coordinates in a single call.
+///
+/// Invalid cells, including `A5_INVALID_CELL` from a failed row of
+/// `a5_lon_lat_to_cell_batch`, do not abort the batch: their slot is set to
+/// NaN for both `lon` and `lat`. The returned array always has `len` entries and must be
+/// freed with `a5_free_lonlatdegrees_array_ptr`.
+///
+/// `in_validity` and `out_validity` are optional validity bitmaps, as for
+/// `a5_lon_lat_to_cell_batch`; null input rows come out as NaN.
+LonLatDegreesArray a5_cell_to_lon_lat_batch(const uint64_t *cells,
+                                            uintptr_t len,
+                                            const uint8_t *in_validity,
+                                            uint8_t *out_validity);
+
+/// Exports the centers of `len` cells through the Arrow C Data Interface as a
+/// nullable Struct array `{lon: float64, lat: float64}`, with invalid cells
+/// marked null in the struct's validity bitmap. Rows that the optional
+/// `in_validity` bitmap marks null are skipped and come out null as well.
+///
+/// `out_array` and `out_schema` must point to uninitialized structs, which the
+/// consumer then owns: each must be freed by calling its own `release`
+/// callback. Returns `NullPointer` without touching them if either pointer
+/// is null.
+A5ErrorCode a5_cell_to_lon_lat_arrow(const uint64_t *cells,
+                                     uintptr_t len,
+                                     const uint8_t *in_validity,
+                                     ArrowArray *out_array,
+                                     ArrowSchema *out_schema);
+
+/// Exports the boundaries of `len` cells through the Arrow C Data Interface as
+/// one nullable `List<Struct<lon: float64, lat: float64>>` array: row `i` is
+/// the open vertex ring of `cells[i]`, as from `a5_cell_to_boundary_oriented`
+/// wound counter-clockwise, and invalid cells are null rows. `out_array` and
+/// `out_schema` work as for `a5_cell_to_lon_lat_arrow`. A batch with more
+/// vertices than 32-bit list offsets can address gives `OutOfBounds`.
+A5ErrorCode a5_cells_to_boundaries_arrow(const uint64_t *cells,
+                                         uintptr_t len,
+                                         ArrowArray *out_array,
+                                         ArrowSchema *out_schema);
+
+/// Structure-of-arrays variant of `a5_cell_to_lon_lat_batch` that writes into
+/// caller-allocated buffers, so nothing needs to be freed afterwards.
+///
+/// `out_lon` and `out_lat` must each hold `len` values. `out_valid` is optional;
+/// when non-null it must hold `len` flags and is set to false for invalid cells.
+/// Rows that the optional `in_validity` bitmap marks null are skipped and
+/// treated as invalid. Invalid rows are written as NaN. Returns the number of
+/// rows that converted.
+uintptr_t a5_cell_to_lon_lat_batch_soa(const uint64_t *cells,
+                                       uintptr_t len,
+                                       const uint8_t *in_validity,
+                                       double *out_lon,
+                                       double *out_lat,
+                                       bool *out_valid);
+
 uint64_t a5_get_num_cells(int32_t resolution);
 
+/// Returns true if `cell` is a canonically encoded A5 cell.
+///
+/// The value must decode to a valid origin and resolution, and re-encoding the
+/// decoded cell must reproduce it exactly, which rejects stray bits outside the
+/// fields used by its resolution. The world cell (0) is not a valid cell.
+bool a5_is_valid_cell(uint64_t cell);
+
 int32_t a5_get_resolution(uint64_t index);
 
+/// The resolution of `cell` as encoded by the position of its marker bit, see
+/// `a5_cell_get_digit`. Returns `u32::MAX` for an invalid cell.
+uint32_t a5_cell_get_resolution_bits(uint64_t cell);
+
+/// The face (0 to 11) of `cell`, the resolution 0 cell it lies in. Returns
+/// `u32::MAX` for an invalid cell.
+uint32_t a5_cell_get_base(uint64_t cell);
+
+/// The digit choosing the subdivision of `cell`'s ancestor at resolution
+/// `level - 1` that leads to `cell`, for `level` from 1 to the cell's
+/// resolution: at level 1 the quintant (0 to 4) of the face, and from level 2
+/// the Hilbert digit (0 to 3) at that resolution. Digits are read from the id
+/// bits, so they come in the id's own order: the quintant is numbered from the
+/// face's first quintant, and Hilbert digits follow the curve rather than
+/// fixed positions. Together with `a5_cell_get_base` and the resolution they
+/// determine the cell; the layout is described below.
+///
+/// A cell at resolution `r` is, from the most significant bit, a 6-bit top
+/// field, then the Hilbert digits for levels 2 to `r` at 2 bits each, then a
+/// single 1 bit marking the resolution, then zeros. The top field is the face
+/// at resolution 0 and `5 * face + quintant` above it. The marker is bit 57 at
+/// resolution 0, bit 56 at resolution 1 and bit `59 - 2 * r` above that, so
+/// resolution 1 leaves bit 57 clear. Resolution 30 has no
+/// room for the full marker, so its top field shrinks: a marker of `...1`
+/// follows a 5-bit field holding 0 to 31, `...100` a 3-bit field holding the
+/// value minus 32, and `...10000` a 1-bit field holding the value minus 40.
+///
+/// Returns -1 for an invalid cell or a level outside 1 to its resolution.
+int32_t a5_cell_get_digit(uint64_t cell, int32_t level);
+
+/// Returns true if `descendant` lies strictly within `ancestor`'s subtree, so
+/// that taking parents of `descendant` reaches `ancestor`. The test compares
+/// the face, quintant and Hilbert digits of `ancestor`'s levels read from the
+/// ids, see `a5_cell_get_digit`, without enumerating children. A cell is not
+/// its own ancestor, so equal cells give false, as do a `descendant` at the
+/// same or a coarser resolution than `ancestor` and invalid cells.
+bool a5_is_ancestor_of(uint64_t ancestor, uint64_t descendant);
+
+/// Whether the point lies in `cell`. Membership is decided by the same lookup
+/// as `a5_lon_lat_to_cell`, so a point on a shared edge or vertex belongs to
+/// exactly one cell. Returns false for an invalid cell or coordinate.
+bool a5_cell_contains_point(uint64_t cell, double longitude, double latitude);
+
+/// Returns a random valid cell at `resolution`, the same one for the same
+/// `seed`. Every cell is equally likely, and as A5 cells have equal area this
+/// is also uniform over the sphere. Returns `A5_INVALID_CELL` for an invalid
+/// resolution.
+///
+/// At resolution 30 the A5 encoding has no room for cells in the last twenty
+/// quintants, so only cells from the first forty are returned there.
+uint64_t a5_random_cell(int32_t resolution, uint64_t seed);
+
+/// Writes a random point inside `cell` to `out_lon` and `out_lat`, the same
+/// one for the same `seed`; it always satisfies `a5_cell_contains_point`.
+///
+/// Points are drawn uniformly by area over the cell's bounding box and
+/// rejected until one lands in the cell, so the accepted point is uniform
+/// over the cell too. A cell fills well over a third of its box, so a few
+/// draws usually suffice. Returns `NullPointer` if either out pointer is
+/// null.
+A5ErrorCode a5_sample_point_in_cell(uint64_t cell, uint64_t seed, double *out_lon, double *out_lat);
+
+/// Returns the resolution of `index`, or an `InvalidCell` error if it is not a
+/// valid cell, so that resolution 0 can be told apart from bad input.
+ResultU64 a5_get_resolution_checked(uint64_t index);
+
+/// Returns the error message of the last failed `_simple` call on the calling
+/// thread, or null if that thread's last `_simple` call succeeded or it has
+/// made none. The string is owned by the library and stays valid until the
+/// thread's next `_simple` call; do not free it.
+const char *a5_last_error_message();
+
+/// `a5_lon_lat_to_cell` writing the cell to `out`; see `a5_last_error_message`.
+A5ErrorCode a5_lon_lat_to_cell_simple(double longitude,
+                                      double latitude,
+                                      int32_t resolution,
+                                      uint64_t *out);
+
+/// `a5_cell_to_lon_lat` writing the center to `out`; see `a5_last_error_message`.
+A5ErrorCode a5_cell_to_lon_lat_simple(uint64_t cell, LonLatDegrees *out);
+
+/// `a5_cell_to_lon_lat_simple` with the center split into `out_lon` and
+/// `out_lat`, for callers that cannot pass a struct; see
+/// `a5_last_error_message`.
+A5ErrorCode a5_cell_to_lon_lat_out(uint64_t cell, double *out_lon, double *out_lat);
+
+/// `a5_cell_to_parent` writing the parent to `out`; see `a5_last_error_message`.
+A5ErrorCode a5_cell_to_parent_simple(uint64_t cell, int32_t parent_resolution, uint64_t *out);
+
+/// Frees a `LonLatDegreesArray` passed by value. Prefer
+/// `a5_free_lonlatdegrees_array_ptr`: a copy freed here cannot be marked as
+/// freed, so freeing it twice is undefined behavior.
+A5_DEPRECATED("use a5_free_lonlatdegrees_array_ptr, which makes a second free a no-op")
 void a5_free_lonlatdegrees_array(LonLatDegreesArray arr);
 
+/// Frees the array `arr` points to, then sets its `data` and `error` to null
+/// and `len` to 0, so that freeing the same array again is a harmless no-op.
+/// A null `arr` is ignored, as is an array owned by an `A5Arena`, which
+/// `a5_arena_free` releases.
+void a5_free_lonlatdegrees_array_ptr(LonLatDegreesArray *arr);
+
+/// Frees a `CellArray` passed by value. Prefer `a5_free_cell_array_ptr`: a
+/// copy freed here cannot be marked as freed, so freeing it twice is undefined
+/// behavior.
+A5_DEPRECATED("use a5_free_cell_array_ptr, which makes a second free a no-op")
 void a5_free_cell_array(CellArray arr);
 
+/// Frees the array `arr` points to, then sets its `data` and `error` to null
+/// and `len` to 0, so that freeing the same array again is a harmless no-op.
+/// A null `arr` is ignored, as is an array owned by an `A5Arena`, which
+/// `a5_arena_free` releases.
+void a5_free_cell_array_ptr(CellArray *arr);
+
+/// Frees the array `arr` points to, as `a5_free_cell_array_ptr` does for
+/// cells. A null `arr` is ignored.
+void a5_free_tile_array_ptr(TileArray *arr);
+
+/// Returns a new, empty arena, to be released with `a5_arena_free`.
+A5Arena *a5_arena_new();
+
+/// Frees `arena` along with every array it holds, error strings included.
+/// Arrays returned into it must not be used afterwards. A null `arena` is
+/// ignored.
+void a5_arena_free(A5Arena *arena);
+
+/// `a5_cell_to_children` with the result held by `arena`.
+CellArray a5_cell_to_children_arena(A5Arena *arena, uint64_t index, int32_t child_resolution);
+
+/// `a5_grid_disk` with the result held by `arena`.
+CellArray a5_grid_disk_arena(A5Arena *arena, uint64_t cell_id, int32_t k);
+
+/// `a5_cell_to_neighbors` with the result held by `arena`.
+CellArray a5_cell_to_neighbors_arena(A5Arena *arena, uint64_t cell);
+
+/// `a5_compact` with the result held by `arena`.
+CellArray a5_compact_arena(A5Arena *arena, const uint64_t *cells, uintptr_t len);
+
+/// `a5_uncompact` with the result held by `arena`.
+CellArray a5_uncompact_arena(A5Arena *arena,
+                             const uint64_t *cells,
+                             uintptr_t len,
+                             int32_t target_resolution);
+
+/// `a5_polygon_to_cells` with the result held by `arena`.
+CellArray a5_polygon_to_cells_arena(A5Arena *arena,
+                                    const LonLatDegrees *ring,
+                                    uintptr_t ring_len,
+                                    int32_t resolution,
+                                    FillMode mode);
+
+/// `a5_cell_to_boundary` with the result held by `arena`.
+LonLatDegreesArray a5_cell_to_boundary_arena(A5Arena *arena,
+                                             uint64_t cell_id,
+                                             CellBoundaryOptions options);
+
+/// Returns the boundary of `cell_id`. Vertices run counter-clockwise seen
+/// from above, the orientation GeoJSON asks of exterior rings; longitudes
+/// are not wrapped, so a cell crossing the antimeridian can run past ±180.
+/// See `a5_cell_to_boundary_oriented` to pick the winding.
+///
+/// With the `boundary-cache` feature and a cache size set, see
+/// `a5_set_boundary_cache_size`, boundaries at the default segment count are
+/// copied out of the cache, which holds exactly what the uncached path
+/// returns; an explicit segment count always recomputes.
 LonLatDegreesArray a5_cell_to_boundary(uint64_t cell_id, CellBoundaryOptions options);
 
+/// Returns the open boundary of `cell`, as from `a5_cell_to_boundary` with
+/// default options, wound counter-clockwise when `ccw` is set, for GeoJSON
+/// exterior rings, and clockwise otherwise, as some renderers expect. The
+/// winding is measured on the ring with the antimeridian and poles unwrapped,
+/// so it holds for every cell, and the ring is reversed if it is wrong.
+LonLatDegreesArray a5_cell_to_boundary_oriented(uint64_t cell, bool ccw);
+
+/// Writes the center of `cell`, as from `a5_cell_to_lon_lat`, to `out_lon` and
+/// `out_lat`, and its open boundary, as from `a5_cell_to_boundary` with default
+/// options, to `out_vertices`, so a join needs one call per cell. On failure
+/// the centers are left untouched and `out_vertices` gets an empty array
+/// carrying the error; free it with `a5_free_lonlatdegrees_array_ptr` either
+/// way. Returns `NullPointer`, writing nothing, if any out pointer is null.
+A5ErrorCode a5_cell_to_lon_lat_all(uint64_t cell,
+                                   double *out_lon,
+                                   double *out_lat,
+                                   LonLatDegreesArray *out_vertices);
+
+/// Returns the boundary of `cell` as one ring, or as two when it crosses the
+/// antimeridian, each cut at ±180 with every longitude in [-180, 180], so it
+/// renders without stretching across the map. `split` says which happened.
+///
+/// Rings are open and counter-clockwise, with a pole vertex expanded to points
+/// along the pole's latitude, exactly as the polygons of
+/// `a5_cells_to_geojson`.
+SplitBoundary a5_cell_to_boundary_split(uint64_t cell);
+
+/// Frees a `SplitBoundary` passed by value. Prefer
+/// `a5_free_split_boundary_ptr`: a copy freed here cannot be marked as freed,
+/// so freeing it twice is undefined behavior.
+A5_DEPRECATED("use a5_free_split_boundary_ptr, which makes a second free a no-op")
+void a5_free_split_boundary(SplitBoundary boundary);
+
+/// Frees both rings and the error of the boundary `boundary` points to, then
+/// nulls them, so that freeing the same boundary again is a harmless no-op.
+/// A null `boundary` is ignored.
+void a5_free_split_boundary_ptr(SplitBoundary *boundary);
+
+/// Encodes `cells` as a GeoJSON FeatureCollection with one Feature per cell.
+///
+/// Exterior rings are counter-clockwise per the right-hand rule, and a cell
+/// crossing the antimeridian becomes a MultiPolygon split at ±180. When
+/// `include_id` is set each Feature carries the cell hex id in
+/// `properties.a5`. Returns null if any cell is invalid; otherwise free the
+/// string with `a5_free_cstring`.
+char *a5_cells_to_geojson(const uint64_t *cells, uintptr_t len, bool include_id);
+
+/// Encodes `cells` as CSV: a header row, then one row per cell with its hex
+/// id in column `a5`, followed by `center_lon,center_lat` when
+/// `include_center` is set and `resolution` when `include_resolution` is set.
+/// Centers are as from `a5_cell_to_lon_lat`, written at full precision; rows
+/// end in `\n`. Returns null if any cell is invalid; otherwise free the string
+/// with `a5_free_cstring`.
+char *a5_cells_to_csv(const uint64_t *cells,
+                      uintptr_t len,
+                      bool include_center,
+                      bool include_resolution);
+
+/// Encodes `cells` as a compact MessagePack blob for caching, writing its size
+/// in bytes to `out_len`. The ids are sorted, then stored as varint gaps
+/// between neighbours, so clustered sets such as polyfill output take one or
+/// two bytes per cell rather than 8; duplicates are kept. The blob is a 3 item
+/// array: the cell count, a shift `s`, then a `bin` of unsigned LEB128 gaps
+/// between the ids shifted right by `s`, the first measured from 0. Returns
+/// null and writes 0 if any cell is invalid.
+/// Decode with `a5_msgpack_to_cells` and free with `a5_free_bytes`.
+uint8_t *a5_cells_to_msgpack(const uint64_t *cells, uintptr_t len, uintptr_t *out_len);
+
+/// Decodes a blob from `a5_cells_to_msgpack` back into its sorted cells.
+/// Malformed input or a decoded id that is not a valid cell fails with
+/// `InvalidCell`.
+CellArray a5_msgpack_to_cells(const uint8_t *bytes, uintptr_t len);
+
+/// Compresses `cells` into a bare byte stream for column storage, writing its
+/// size in bytes to `out_len`. The ids are sorted and deduplicated, then
+/// stored as varint gaps like `a5_cells_to_msgpack` but without any framing:
+/// one byte holding the shift, then the gaps. An empty set compresses to an
+/// empty stream, returned as null with `out_len` 0. Returns null and writes 0
+/// if any cell is invalid. Restore with `a5_decompress_cells` and free with
+/// `a5_free_bytes`.
+uint8_t *a5_compress_cells(const uint64_t *cells, uintptr_t len, uintptr_t *out_len);
+
+/// Restores the sorted cells from an `a5_compress_cells` stream. A malformed
+/// stream or a decoded id that is not a valid cell fails with `InvalidCell`.
+CellArray a5_decompress_cells(const uint8_t *bytes, uintptr_t len);
+
+/// Frees a byte buffer returned by this library, such as from
+/// `a5_cells_to_msgpack`; `len` must be the length it reported.
+void a5_free_bytes(uint8_t *ptr, uintptr_t len);
+
+/// Dissolves `cells` into the outline of their union, returned as a GeoJSON
+/// MultiPolygon with one polygon per connected region and a hole for every
+/// gap inside one. Shared edges are merged, so only the silhouette remains.
+///
+/// Cells may mix resolutions, e.g. after `a5_compact_cells`; they are first
+/// expanded to the finest resolution present, since A5 children do not nest
+/// exactly inside their parents and coarse edges would not meet fine ones.
+/// An expansion past `A5_DESCENDANT_LIMIT` cells is refused. Exterior rings
+/// are counter-clockwise and holes clockwise, per the right-hand rule. Unlike
+/// `a5_cells_to_geojson`, rings are not split at the antimeridian: their
+/// longitudes run on contiguously past ±180. Gaps in a set that reaches
+/// around the sphere become holes in a polygon covering the whole
+/// [-180, 180] x [-90, 90] plane.
+///
+/// Returns null if any cell is invalid or the expansion is too large;
+/// otherwise free the string with `a5_free_cstring`.
+char *a5_cells_to_multipolygon(const uint64_t *cells, uintptr_t len);
+
+/// Returns the cell boundary with the edge segment count and ring closure
+/// taken from `options`.
+LonLatDegreesArray a5_cell_to_boundary_opts(uint64_t cell, BoundaryOptions options);
+
+/// Returns the open boundary of `cell`, as from `a5_cell_to_boundary` with
+/// default options, with `points_per_edge` vertices inserted evenly along the
+/// great circle between each pair of consecutive vertices, including the
+/// closing pair. Zero returns the boundary unchanged. A request for more than
+/// `A5_DENSIFY_POINT_LIMIT` vertices gives `OutOfBounds`.
+LonLatDegreesArray a5_cell_to_boundary_densified(uint64_t cell, uint32_t points_per_edge);
+
+/// Writes the closed cell boundary into the caller-owned buffer `out`, which
+/// holds `cap` vertices, so no allocation has to be freed afterwards.
+///
+/// `out_len` receives the vertex count. If `cap` is too small nothing is
+/// written to `out` and `BufferTooSmall` is returned with the required count
+/// in `out_len`.
+A5ErrorCode a5_cell_to_boundary_into(uint64_t cell,
+                                     LonLatDegrees *out,
+                                     uintptr_t cap,
+                                     uintptr_t *out_len);
+
+/// Returns how many children `cell` has at `child_resolution` without
+/// computing them, e.g. to size the buffer for `a5_cell_to_children_into`.
+///
+/// A5 subdivides each resolution 0 face into 5 quintants and every finer cell
+/// into 4, whatever its shape, so the count depends only on the two resolutions.
+ResultU64 a5_cell_to_children_size(uint64_t cell, int32_t child_resolution);
+
+/// Returns how many descendants `cell` has at `resolution`, which must be
+/// finer than the cell's own; otherwise the error is `InvalidResolution`.
+///
+/// Every A5 cell is a pentagon and each subdivides the same way, 5 quintants
+/// per face and 4 children per cell below that, so no cell's subtree needs
+/// special counting and this is `a5_cell_to_children_size` restricted to
+/// strictly finer resolutions.
+ResultU64 a5_cell_descendant_count(uint64_t cell, int32_t resolution);
+
+/// Returns every descendant of `cell` from the next resolution down to
+/// `max_resolution`, which must be finer than the cell's own. Levels come
+/// coarse to fine, each complete before the next, as for building a tile
+/// pyramid; within a level, cells are in the order of `a5_cell_to_children`.
+/// More than `A5_DESCENDANT_LIMIT` cells give `OutOfBounds`.
+CellArray a5_cell_to_descendants(uint64_t cell, int32_t max_resolution);
+
+/// Returns the immediate child of `cell` at `child_index`, in the order
+/// `a5_cell_to_children` lists them, without building the list. A resolution
+/// 0 cell has 5 children, one per quintant, and every finer cell 4, so valid
+/// indices are `0..a5_cell_to_children_size(cell, A5_DEFAULT_RESOLUTION)`;
+/// a larger index fails with `OutOfBounds`. A resolution 30 cell has no
+/// children and fails with `InvalidResolution`.
+ResultU64 a5_cell_child(uint64_t cell, uint32_t child_index);
+
+/// Writes the smallest and largest id of the descendants of `cell` at
+/// `resolution` to `out_min` and `out_max`, so that a column of cells at that
+/// resolution can be filtered with `cell BETWEEN out_min AND out_max`: the ids
+/// at `resolution` in that range are exactly the descendants. Ids of cells at
+/// other resolutions can also fall in the range, so the column must hold a
+/// single resolution.
+///
+/// At resolution 30 the id layout interleaves quintants, so no such range
+/// exists and the call fails with `InvalidResolution`. Returns
+/// `NullPointer` if either out pointer is null.
+A5ErrorCode a5_cell_to_range(uint64_t cell,
+                             int32_t resolution,
+                             uint64_t *out_min,
+                             uint64_t *out_max);
+
+/// Streams the children of `cell` at `child_resolution` through `cb`, in the
+/// same order as `a5_cell_to_children`, without ever holding the full set.
+/// Pass `A5_DEFAULT_RESOLUTION` for the immediate children. Iteration stops
+/// early, still returning `None`, once `cb` returns false.
+A5ErrorCode a5_cell_to_children_foreach(uint64_t cell,
+                                        int32_t child_resolution,
+                                        void *ctx,
+                                        A5CellCallback cb);
+
+/// Writes the children of `cell` at `child_resolution` into the caller-owned
+/// buffer `out`, which holds `cap` cells. Pass `A5_DEFAULT_RESOLUTION` for the
+/// immediate children.
+///
+/// The child count is known before any child is computed, so an undersized
+/// buffer is reported as `BufferTooSmall`, with the required count in `out_len`,
+/// without doing the work.
+A5ErrorCode a5_cell_to_children_into(uint64_t cell,
+                                     int32_t child_resolution,
+                                     uint64_t *out,
+                                     uintptr_t cap,
+                                     uintptr_t *out_len);
+
+/// Returns the cell boundary as a WKT `POLYGON((lon lat, ...))` with the ring
+/// closed. Coordinates are written at full precision so they parse back
+/// exactly. Returns null for an invalid cell; otherwise free the string with
+/// `a5_free_cstring`.
+char *a5_cell_to_wkt(uint64_t cell);
+
+/// Returns the cell boundary as a little-endian OGC WKB Polygon with one
+/// closed ring, writing its size in bytes to `out_len`.
+///
+/// This is plain WKB without an SRID; coordinates are WGS84 longitude and
+/// latitude. Longitudes are unwrapped around the cell center, so a cell
+/// crossing the antimeridian stays a single valid ring with values past ±180.
+/// Returns null and writes 0 for an invalid cell. Free with `a5_free_wkb`.
+uint8_t *a5_cell_to_wkb(uint64_t cell, uintptr_t *out_len);
+
+/// Frees a buffer returned by `a5_cell_to_wkb`; `len` must be the length it reported.
+void a5_free_wkb(uint8_t *ptr, uintptr_t len);
+
+/// Returns the cell boundary as interleaved `[lon0, lat0, lon1, lat1, ...]`,
+/// writing the number of `f64`s to `out_len`. With `closed` set the first
+/// vertex is repeated at the end. Returns null and writes 0 for an invalid
+/// cell. Free with `a5_free_f64_array`.
+double *a5_cell_to_boundary_flat(uint64_t cell, bool closed, uintptr_t *out_len);
+
+/// `a5_cell_to_boundary_flat` in `f32`, ready for a GPU vertex buffer. Free
+/// with `a5_free_f32_array`.
+///
+/// An `f32` keeps about 7 significant digits, so coordinates carry errors of
+/// up to about a meter near ±180. That is plenty for drawing cells down to
+/// around resolution 20, whose edges are several meters long, but not for
+/// analysis; use the `f64` boundary for that.
+float *a5_cell_to_boundary_f32(uint64_t cell, bool closed, uintptr_t *out_len);
+
+/// Frees a buffer returned by `a5_cell_to_boundary_flat`; `len` must be the
+/// number of `f64`s it reported.
+void a5_free_f64_array(double *ptr, uintptr_t len);
+
+/// Simplifies the `len` points at `points`, such as a ring from
+/// `a5_cells_to_multipolygon`, with Douglas-Peucker on the sphere: no dropped
+/// point lies more than `tolerance_meters` from the great-circle path through
+/// the kept ones. The first and last points are always kept, so a closed ring
+/// stays closed, and a closed ring keeps at least four distinct vertices if it
+/// had them.
+///
+/// Writes the number of points returned to `out_len`. Returns null and writes
+/// 0 if `points` is null with a nonzero `len`, a coordinate is invalid or the
+/// tolerance is negative or not finite. Free with `a5_free_lonlatdegrees`.
+LonLatDegrees *a5_simplify_ring(const LonLatDegrees *points,
+                                uintptr_t len,
+                                double tolerance_meters,
+                                uintptr_t *out_len);
+
+/// Frees a buffer returned by `a5_simplify_ring`; `len` must be the number of
+/// points it reported.
+void a5_free_lonlatdegrees(LonLatDegrees *ptr, uintptr_t len);
+
+/// Frees a buffer returned by `a5_cell_to_boundary_f32`; `len` must be the
+/// number of `f32`s it reported.
+void a5_free_f32_array(float *ptr, uintptr_t len);
+
+/// Returns the bounding box of the cell boundary. A resolution 0 cell around a
+/// pole spans all longitudes; finer cells meeting at a pole extend up to it.
+BBox a5_cell_to_bbox(uint64_t cell);
+
+/// Returns the sorted cells at `resolution` covering the polygon `ring`, either
+/// those whose center lies inside it or all that intersect it, per `mode`.
+///
+/// The ring is a single outer boundary with no holes. It may be open or closed
+/// and wound either way, and may cross the antimeridian, but must not encircle
+/// a pole. Edges are straight lines in longitude/latitude degrees. Free the
+/// result with `a5_free_cell_array_ptr`.
+CellArray a5_polygon_to_cells(const LonLatDegrees *ring,
+                              uintptr_t ring_len,
+                              int32_t resolution,
+                              FillMode mode);
+
+/// Covers the polygon `ring` with cells of mixed resolution: coarse cells
+/// over its interior and cells at `max_resolution` along its boundary, never
+/// coarser than `min_resolution`. The result is what compacting the
+/// `Intersects` fill at `max_resolution` with `a5_compact_cells_to` and floor
+/// `min_resolution` would give, but the fine interior is never computed:
+/// descending from the resolution 0 cells, a cell whose descendants must all
+/// lie inside the polygon is kept as it is.
+///
+/// The ring follows the rules of `a5_polygon_to_cells`. The output is sorted.
+/// `min_resolution` finer than `max_resolution` fails with
+/// `InvalidResolution`.
+CellArray a5_polygon_to_cells_adaptive(const LonLatDegrees *ring,
+                                       uintptr_t ring_len,
+                                       int32_t min_resolution,
+                                       int32_t max_resolution);
+
+/// Writes to `out_fraction` the share of the area of `cell` that the polygon
+/// `ring` covers, in [0, 1]: 1 for a cell wholly inside, 0 for one wholly
+/// outside. Meant for area-weighted interpolation of polygon values onto
+/// cells. The ring follows the rules of `a5_polygon_to_cells`, and its
+/// intersection with the cell is measured on the sphere like
+/// `a5_cell_exact_area`. Returns `NullPointer` if `out_fraction` is null.
+A5ErrorCode a5_cell_polygon_overlap(uint64_t cell,
+                                    const LonLatDegrees *ring,
+                                    uintptr_t ring_len,
+                                    double *out_fraction);
+
+/// Returns the sorted cells at `resolution` intersecting the longitude/latitude
+/// box, e.g. to cover a map viewport. By convention `min_lon > max_lon` means
+/// the box wraps across the antimeridian, and a box 360 degrees wide covers
+/// every longitude. This is an `Intersects` fill of the box as a polygon, so
+/// it is pruned the same way.
+CellArray a5_bbox_to_cells(double min_lon,
+                           double min_lat,
+                           double max_lon,
+                           double max_lat,
+                           int32_t resolution);
+
+/// Whether the boundary of `cell` overlaps the longitude/latitude box, with
+/// the box convention of `a5_bbox_to_cells`. The cell's own polygon is tested,
+/// not its bounding box, so a cell whose box only grazes a corner of the query
+/// is rejected; it is exactly the test `a5_bbox_to_cells` applies. Returns
+/// false for an invalid cell or box.
+bool a5_cell_intersects_bbox(uint64_t cell,
+                             double min_lon,
+                             double min_lat,
+                             double max_lon,
+                             double max_lat);
+
+/// Returns the sorted XYZ tiles at `zoom` that the boundary of `cell`
+/// overlaps, for serving cells through a slippy-map tile cache. A cell
+/// across the antimeridian gets tiles from both ends of each row, and
+/// cells beyond Web Mercator's ±85.05° limit fall in the top or bottom row,
+/// where the projection clamps them. `cell` is among the `a5_tile_to_cells`
+/// of every tile returned, at its own resolution. A zoom past
+/// `A5_MAX_TILE_ZOOM`, or one so deep that the cell spans over a million
+/// tiles, gives `OutOfBounds`. Free with `a5_free_tile_array_ptr`.
+TileArray a5_cell_to_covering_tiles(uint64_t cell, uint32_t zoom);
+
+/// Returns the sorted cells at `resolution` that overlap the XYZ tile `z/x/y`,
+/// as `a5_bbox_to_cells` would for the tile's bounds. The top and bottom rows
+/// reach the poles, since Web Mercator clamps what lies beyond them into
+/// those rows. For TMS numbering pass `y` as `2^z - 1 - y`. A tile outside the
+/// grid of its zoom gives `OutOfBounds`.
+CellArray a5_tile_to_cells(uint32_t z, uint32_t x, uint32_t y, int32_t resolution);
+
+/// Streams the cells that `a5_polygon_to_cells` would return through `cb` as
+/// they are found, without collecting them. Cells arrive in no particular
+/// order, though each is reported exactly once. Iteration stops early, still
+/// returning `None`, once `cb` returns false.
+A5ErrorCode a5_polygon_to_cells_foreach(const LonLatDegrees *ring,
+                                        uintptr_t ring_len,
+                                        int32_t resolution,
+                                        FillMode mode,
+                                        void *ctx,
+                                        A5CellCallback cb);
+
+/// Returns the sorted cells at `resolution` whose center lies inside `outer`
+/// and inside none of the holes.
+///
+/// The holes are passed as one flattened vertex buffer, `holes`, split into
+/// `num_holes` rings by the lengths in `hole_lens`. Every ring follows the
+/// rules of `a5_polygon_to_cells`. Free the result with `a5_free_cell_array_ptr`.
+CellArray a5_polygon_with_holes_to_cells(const LonLatDegrees *outer,
+                                         uintptr_t outer_len,
+                                         const LonLatDegrees *holes,
+                                         const uintptr_t *hole_lens,
+                                         uintptr_t num_holes,
+                                         int32_t resolution);
+
+/// Pass `A5_DEFAULT_RESOLUTION` as `child_resolution` for the immediate children.
+/// Any other value is forwarded as the target resolution, so an out-of-range
+/// value is reported as an error rather than silently falling back. More than
+/// `A5_DESCENDANT_LIMIT` children give `OutOfBounds`; count them first with
+/// `a5_cell_to_children_size`.
+///
+/// The order is fixed: quintant by quintant, and by ascending id within each.
+/// Below a resolution 0 cell that is its face's quintant order, which starts
+/// at the face's first quintant rather than the lowest id, so the array is
+/// not always sorted; from resolution 1 on it is. `a5_cell_child` indexes
+/// this order. Use `a5_sort_cells` for numeric order.
 CellArray a5_cell_to_children(uint64_t index, int32_t child_resolution);
 
+/// Returns a copy of the resolution 0 cells; see `a5_get_res0_cell_at` to
+/// read them without allocating.
 CellArray a5_get_res0_cells();
 
+/// Number of resolution 0 cells, the bound for `a5_get_res0_cell_at`.
+uintptr_t a5_get_res0_cells_count();
+
+/// Returns resolution 0 cell `i`, in the order of `a5_get_res0_cells`, or an
+/// `OutOfBounds` error if `i` is not below `a5_get_res0_cells_count`.
+ResultU64 a5_get_res0_cell_at(uintptr_t i);
+
 CellArray a5_compact(const uint64_t *cells, uintptr_t len);
 
+/// Compacts a set of cells into the fewest cells covering exactly the same
+/// area, replacing every complete group of siblings with their parent,
+/// recursively. Input may contain duplicates and mixed resolutions, including
+/// cells nested inside others; the output is sorted and deduplicated.
+CellArray a5_compact_cells(const uint64_t *cells, uintptr_t len);
+
+/// Compacts like `a5_compact_cells` but never past `min_resolution`: merging
+/// stops at cells of that resolution, and input cells coarser than it are
+/// expanded to it, so every output cell is at `min_resolution` or finer. With
+/// `min_resolution` 0 this is full compaction. The output is sorted, and an
+/// expansion past `A5_DESCENDANT_LIMIT` cells gives `OutOfBounds`.
+CellArray a5_compact_cells_to(const uint64_t *cells, uintptr_t len, int32_t min_resolution);
+
+/// Expands every cell down to `target_resolution`, returning the sorted and
+/// deduplicated cells at that resolution. A cell already finer than the
+/// target fails the whole call with an error rather than being skipped, and
+/// more than `A5_DESCENDANT_LIMIT` cells give `OutOfBounds`.
+CellArray a5_uncompact_cells(const uint64_t *cells, uintptr_t len, int32_t target_resolution);
+
+/// Sorts `len` cells in place by numeric id. Below resolution 30 a cell id is
+/// its face, quintant and Hilbert index, so this is face by face Hilbert
+/// order, keeping nearby ids nearby on the ground. A null `cells` with a
+/// nonzero `len` gives `NullPointer` and sorts nothing.
+A5ErrorCode a5_sort_cells(uint64_t *cells, uintptr_t len);
+
+/// Sorts `len` cells in place as `a5_sort_cells` does and moves each distinct
+/// id to the front once, returning how many there are; entries past that are
+/// left in an unspecified order. A null `cells` with a nonzero `len` returns
+/// `usize::MAX`, which no real count reaches.
+uintptr_t a5_dedup_cells(uint64_t *cells, uintptr_t len);
+
+/// Expands every cell down to `target_resolution`, in the `a5` crate's order
+/// and keeping duplicates; `a5_uncompact_cells` sorts and deduplicates.
+/// Invalid cells and resolutions are errors, and more than
+/// `A5_DESCENDANT_LIMIT` cells give `OutOfBounds`.
 CellArray a5_uncompact(const uint64_t *cells, uintptr_t len, int32_t target_resolution);
 
+/// Frees a single string returned by this library: the `error` of any result
+/// struct, or the strings from `a5_cell_to_hex_string`, `a5_u64_to_hex`,
+/// `a5_cells_to_geojson` and `a5_cell_to_wkt`. Null is ignored, so an error
+/// can be freed on its own when a result carries no data.
+void a5_free_cstring(char *s);
+
+/// Same as `a5_free_cstring`, kept for existing callers.
 void a5_free_string(char *ptr);
 
 ResultU64 a5_hex_to_u64(const char *hex);
 
+/// Formats `cell` as a lowercase, zero-padded 16 character hex string.
+/// Free the result with `a5_free_cstring`.
+char *a5_cell_to_hex_string(uint64_t cell);
+
+/// Describes `cell` for diagnostics as `A5[res=2, face=3, path=4/1]`, its
+/// resolution, face and the digits from `a5_cell_get_digit` for each level,
+/// or `A5[invalid: 0x...]` with the hex id for an invalid cell. The format is
+/// meant for people and may change. Free the result with `a5_free_cstring`.
+char *a5_cell_to_debug_string(uint64_t cell);
+
+/// Parses a hex cell id as produced by `a5_cell_to_hex_string`. A leading `0x`
+/// is accepted; empty, overlong or non-hex input is `InvalidCell` and a null
+/// string `NullPointer`.
+ResultU64 a5_hex_string_to_cell(const char *s);
+
+/// Checks a hex cell id strictly, telling apart why it is rejected:
+/// `NullPointer` for a null string, `NotHex` for a non-UTF-8 string or any
+/// character that is not a hex digit,
+/// `WrongLength` unless there are exactly 16 digits, as
+/// `a5_cell_to_hex_string` writes, and `InvalidCell` for a well-formed id
+/// that is not a cell. A leading `0x` is allowed. Returns `None` for a valid
+/// cell id.
+A5ErrorCode a5_validate_hex_string(const char *s);
+
+/// Formats `cell` as a base 10 string, for environments that cannot carry a
+/// full 64-bit integer. Free the result with `a5_free_cstring`.
+char *a5_cell_to_decimal_string(uint64_t cell);
+
+/// Parses a base 10 cell id as produced by `a5_cell_to_decimal_string`. Only
+/// ASCII digits are accepted; empty input and values above `u64::MAX` are
+/// rejected as `InvalidCell` rather than wrapped; a null string is
+/// `NullPointer`.
+ResultU64 a5_decimal_string_to_cell(const char *s);
+
+/// Writes `cell` as 8 big-endian bytes into `out`, so that the byte strings of
+/// cells sort in the same order as their numeric ids. Does nothing if `out`
+/// is null.
+void a5_cell_to_bytes(uint64_t cell, uint8_t *out);
+
+/// Reads a cell from the 8 big-endian bytes at `bytes`, as written by
+/// `a5_cell_to_bytes`. Returns `A5_INVALID_CELL` if `bytes` is null.
+uint64_t a5_cell_from_bytes(const uint8_t *bytes);
+
 char *a5_u64_to_hex(uint64_t value);
 
 uintptr_t a5_get_num_children(int32_t parent_res, int32_t child_res);
@@ -88,8 +1243,170 @@ ResultSpherical a5_cell_to_spherical(uint64_t cell);
 
 CellArray a5_spherical_cap(uint64_t cell_id, double radius);
 
-CellArray a5_grid_disk(uint64_t cell_id, uintptr_t k);
+/// Great-circle distance in meters between two points in degrees, measured on
+/// the authalic sphere of radius 6 371 007.2 m that A5 cells are equal-area on.
+double a5_haversine_distance(double lon1, double lat1, double lon2, double lat2);
+
+/// Great-circle distance in meters between the centers of two cells, as given by
+/// `a5_cell_to_lon_lat`. Returns NaN if either cell is invalid.
+double a5_great_circle_distance_cells(uint64_t a, uint64_t b);
+
+/// Initial compass bearing in degrees clockwise from true north, in
+/// [0, 360), along the great circle from the first point to the second. NaN
+/// if the points coincide, are antipodal or are not finite.
+double a5_bearing(double lon1, double lat1, double lon2, double lat2);
+
+/// `a5_bearing` from the center of `from` to the center of `to`. NaN if
+/// either cell is invalid or they are the same cell.
+double a5_cell_bearing(uint64_t from, uint64_t to);
+
+/// Returns every cell within `k` edge-steps of `cell_id`, including the origin.
+///
+/// Traversal crosses quintant and dodecahedron face boundaries seamlessly, so a
+/// disk spanning two faces has the same shape as one inside a face. The result
+/// is sorted and compacted: groups of siblings that are fully inside the disk
+/// are replaced by their parent, so uncompact to the origin's resolution for a
+/// uniform set. `k = 0` returns the origin; a negative `k`, or one beyond
+/// `A5_GRID_DISTANCE_LIMIT`, is an error.
+CellArray a5_grid_disk(uint64_t cell_id, int32_t k);
+
+/// Writes the number of cells in the disk `a5_grid_disk` returns, counted at
+/// the origin's resolution, to `out_count` and the sum of their
+/// `a5_cell_exact_area`s in square meters to `out_area_m2`, enumerating the
+/// disk once. A5 cells are all pentagons of nearly equal area, so the sum is
+/// close to the count times `a5_cell_area`, but it tracks the small
+/// differences between cells. `k` is limited as for `a5_grid_ring`, beyond
+/// which `OutOfBounds` is returned. Returns `NullPointer` if either out
+/// pointer is null.
+A5ErrorCode a5_grid_disk_area(uint64_t origin, int32_t k, double *out_area_m2, uint64_t *out_count);
+
+/// Returns the cells sharing an edge with `cell`, sorted by cell id.
+///
+/// A5 has no hexagons: every cell at resolution 0 and from resolution 2 on is a
+/// pentagon with exactly five edge neighbors, including cells touching a
+/// dodecahedron vertex. Resolution 1 cells are the triangular quintants of a
+/// face and have three.
+CellArray a5_cell_to_neighbors(uint64_t cell);
+
+/// Returns the edge neighbors of `cell`, the same cells as
+/// `a5_cell_to_neighbors`, in clockwise order: sorted by the initial bearing
+/// from the cell's center to each neighbor's center, measured clockwise from
+/// north, so position 0 is the first neighbor at or clockwise of due north.
+/// Each neighbor shares exactly one edge with the cell, so position `i` also
+/// names the `i`-th edge clockwise, and a direction index maps to a neighbor.
+///
+/// A5 has no hexagons, so there is no missing edge to place: a pentagon fills
+/// all five positions, and a resolution 1 quintant its three. The resolution
+/// 0 cell centered on the north pole measures bearings as though facing the
+/// pole from its center's meridian.
+CellArray a5_cell_to_neighbors_ordered(uint64_t cell);
+
+/// Returns true if `cell` is a pentagon. Unlike hexagonal grids, A5 has no
+/// hexagons: every cell at resolution 0 and from resolution 2 on is a
+/// pentagon, including those touching a dodecahedron vertex, so this is only
+/// false for the triangular resolution 1 quintants and for invalid cells.
+/// Neighbor counts follow from it: five edge neighbors for a pentagon, three
+/// for a quintant.
+bool a5_is_pentagon(uint64_t cell);
+
+/// Returns which of the 12 dodecahedron faces, numbered 0 to 11 like the
+/// resolution 0 cells, `cell` lies in.
+ResultU64 a5_cell_to_face(uint64_t cell);
+
+/// Returns the sorted pentagon cells at `resolution`, as `a5_is_pentagon`
+/// defines them. Since every A5 cell other than a quintant is a pentagon, this
+/// is every cell at the resolution, `a5_get_num_cells(resolution)` of them,
+/// and nothing at resolution 1. More than `A5_DESCENDANT_LIMIT` cells, past
+/// resolution 12, give `OutOfBounds`; use `a5_get_num_cells` to count them,
+/// or `a5_uncompact` on part of the sphere.
+CellArray a5_get_pentagon_cells(int32_t resolution);
+
+/// Returns true if `a` and `b` are distinct cells of the same resolution that
+/// share an edge. Cells touching only at a corner are not neighbors, matching
+/// `a5_cell_to_neighbors`. Mismatched resolutions, identical cells and invalid
+/// cells all give false rather than an error.
+///
+/// Cells whose centers are too far apart to touch are rejected before any
+/// neighbor is computed: neighbor centers are never more than about 1.3 times
+/// the cell edge scale `sqrt(area)` apart, and the cut-off allows twice that.
+bool a5_are_neighbors(uint64_t a, uint64_t b);
+
+/// Writes the local grid coordinates of `cell` relative to `origin`, which
+/// must be at the same resolution, to `out_i` and `out_j`. The origin is at
+/// `(0, 0)`; `i` runs along the lattice rows and `j` across them.
+///
+/// Local coordinates come from the Hilbert lattice inside one quintant (a
+/// fifth of a dodecahedron face), so they are only defined when `cell` lies
+/// in the same quintant as `origin`. Any other cell, even an edge neighbor
+/// across the quintant boundary, gives `OutOfBounds`, as do resolution 0 and
+/// 1, which have no lattice (`InvalidResolution`). Returns `NullPointer`
+/// if either out pointer is null.
+A5ErrorCode a5_cell_to_local_ij(uint64_t origin, uint64_t cell, int32_t *out_i, int32_t *out_j);
+
+/// Returns the cell at local grid coordinates `(i, j)` relative to `origin`,
+/// the inverse of `a5_cell_to_local_ij`. Coordinates falling outside the
+/// origin's quintant give `OutOfBounds`.
+ResultU64 a5_local_ij_to_cell(uint64_t origin, int32_t i, int32_t j);
+
+/// Returns the cells at `resolution` that the great-circle path from
+/// `(lon1, lat1)` to `(lon2, lat2)` passes through, in order from start to end
+/// with each cell listed once, at its first visit.
+///
+/// The path is sampled at a quarter of the cell scale `sqrt(area)` and
+/// bisected between samples in different cells until they are edge neighbors
+/// a millionth of the cell scale apart, so even a cell whose corner the path
+/// barely clips is found. The path is traced on the sphere, so one crossing
+/// the antimeridian or passing near a pole needs no splitting. Antipodal
+/// endpoints give `InvalidCoordinate`, as there is no unique path, and a path
+/// needing more than `A5_GEODESIC_SAMPLE_LIMIT` samples gives `OutOfBounds`.
+CellArray a5_cells_along_geodesic(double lon1,
+                                  double lat1,
+                                  double lon2,
+                                  double lat2,
+                                  int32_t resolution);
+
+/// Returns the cells exactly `k` edge-steps from `origin`, sorted by cell id:
+/// the outermost ring of `a5_grid_disk`, at the origin's resolution and never
+/// compacted. `k = 0` returns the origin alone.
+///
+/// Distance is measured over real edge adjacency, as for `a5_grid_distance`,
+/// so the ring has no gaps: every cell at distance `k` is included, also where
+/// it crosses quintant and face boundaries. Every A5 cell is a pentagon, so
+/// there are no special cells to distort it, but the tiling is not regular:
+/// ring sizes vary with position, and near a dodecahedron vertex the ring need
+/// not form one simple loop. A negative `k`, or one beyond
+/// `A5_GRID_DISTANCE_LIMIT`, is an error.
+CellArray a5_grid_ring(uint64_t origin, int32_t k);
+
+/// Writes every cell within `k` edge-steps of `origin` into `out_cells` and its
+/// distance from the origin into the same slot of `out_distances`, both
+/// caller-owned buffers holding `cap` entries. Cells are at the origin's
+/// resolution and come ring by ring, the origin first at distance 0, then
+/// sorted by id within each ring: together, the rings `a5_grid_ring` returns
+/// for 0 to `k`, so distances agree with `a5_grid_distance`. `k` is limited
+/// as for `a5_grid_ring`.
+///
+/// `out_len` receives the cell count. If `cap` is too small nothing is
+/// written and `BufferTooSmall` is returned with the required count in
+/// `out_len`.
+A5ErrorCode a5_grid_disk_distances(uint64_t origin,
+                                   int32_t k,
+                                   uint64_t *out_cells,
+                                   int32_t *out_distances,
+                                   uintptr_t cap,
+                                   uintptr_t *out_len);
+
+/// Returns the number of edge-steps between two cells of the same resolution.
+///
+/// The distance is exact, including across quintant and face boundaries, as it
+/// is found by a breadth-first search over real cell adjacency. The cost grows
+/// with the square of the distance, and cells more than
+/// `A5_GRID_DISTANCE_LIMIT` steps apart are reported as `OutOfBounds`.
+ResultU64 a5_grid_distance(uint64_t a, uint64_t b);
 
-CellArray a5_grid_disk_vertex(uint64_t cell_id, uintptr_t k);
+/// Same as `a5_grid_disk`, but a step may also cross a shared vertex. The `a5`
+/// traversal has no vertex neighbors below resolution 2, so resolution 0 and 1
+/// cells give just the origin.
+CellArray a5_grid_disk_vertex(uint64_t cell_id, int32_t k);
 
 }  // extern "C"